@@ -0,0 +1,128 @@
+//! Admin account bootstrap
+//!
+//! no credentials are shipped with the migrations, the admin is created (or its
+//! password rotated) on startup from `ADMIN_USER`/`ADMIN_PASSWORD`, or once with
+//! `backend bootstrap-admin`
+use bcrypt::verify;
+use db::{
+    db_pool::Pool,
+    functions::{
+        session::revoke_user_sessions_query,
+        user::{query_user_info, upsert_user_record, users_with_pwd_hash},
+    },
+    structs::{NewUser, UrlUserQuery, User, UserRole},
+};
+use log::{info, warn};
+use std::env;
+use warp::{http::StatusCode, Rejection};
+
+use crate::{
+    error::{convert_to_rejection, Error},
+    is_dev_server,
+    routes::user_route::{encrypt_pwd, password_policy},
+};
+
+/// CLI subcommand that bootstraps the admin and exits
+pub const BOOTSTRAP_ADMIN_COMMAND: &str = "bootstrap-admin";
+
+/// user name used when only `ADMIN_PASSWORD` is set
+const DEFAULT_ADMIN_USER: &str = "admin";
+
+/// bcrypt hash of the "admin" password that `1_users` used to seed
+const SEEDED_ADMIN_HASH: &str = "$2a$04$Plkgo7MNUzowxl8/r0tFMeQE2E8DDej6oKkuqXMr5EIUcZKTSGGIS";
+
+/// Admin credentials from `ADMIN_USER` and `ADMIN_PASSWORD`, None without a password
+pub fn admin_from_env() -> Option<(String, String)> {
+    let pwd = env::var("ADMIN_PASSWORD")
+        .ok()
+        .filter(|pwd| !pwd.is_empty())?;
+    let user_name = env::var("ADMIN_USER")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_ADMIN_USER.into());
+    Some((user_name, pwd))
+}
+
+/// Creates the admin, or makes sure it has `pwd` as password
+///
+/// fails if a user that isn't an admin already has the name,
+/// rotating the password logs the admin out everywhere,
+/// nothing is written if the password is already the right one
+pub async fn bootstrap_admin(
+    pool: &Pool,
+    user_name: String,
+    pwd: String,
+) -> Result<User, Rejection> {
+    let admin = NewUser {
+        user_name,
+        user_role: UserRole::Admin,
+        user_pwd: pwd,
+    };
+    admin
+        .validate(&admin.user_pwd, &password_policy())
+        .map_err(convert_to_rejection)?;
+
+    let mut conn = pool.get().map_err(convert_to_rejection)?;
+    let existing = query_user_info(
+        &mut conn,
+        &UrlUserQuery {
+            id: None,
+            name: Some(admin.user_name.clone()),
+        },
+    )
+    .ok();
+    if let Some(existing) = &existing {
+        if existing.user_role != UserRole::Admin {
+            return Err(Error::internal_error(
+                format!(
+                    "user {} already exists and isn't an admin, set ADMIN_USER to another name",
+                    existing.user_name
+                ),
+                StatusCode::CONFLICT,
+            )
+            .into());
+        }
+        if verify(&admin.user_pwd, &existing.user_pwd).unwrap_or(false) {
+            return Ok(existing.clone());
+        }
+    }
+
+    let user = upsert_user_record(
+        &mut conn,
+        &NewUser {
+            user_pwd: encrypt_pwd(&admin.user_pwd).await?,
+            ..admin
+        },
+    )
+    .map_err(convert_to_rejection)?;
+    if existing.is_some() {
+        revoke_user_sessions_query(&mut conn, user.id).map_err(convert_to_rejection)?;
+        info!("admin {} password rotated", user.user_name);
+    } else {
+        info!("admin {} created", user.user_name);
+    }
+    Ok(user)
+}
+
+/// Startup checks: bootstraps the admin from env vars if set, then refuses to run
+/// (outside the dev server) while a user still has the old seeded credentials
+pub async fn check_admin_on_startup(pool: &Pool) -> Result<(), Rejection> {
+    if let Some((user_name, pwd)) = admin_from_env() {
+        bootstrap_admin(pool, user_name, pwd).await?;
+    }
+
+    let mut conn = pool.get().map_err(convert_to_rejection)?;
+    let seeded = users_with_pwd_hash(&mut conn, SEEDED_ADMIN_HASH).map_err(convert_to_rejection)?;
+    if seeded.is_empty() {
+        return Ok(());
+    }
+    let msg = format!(
+        "user(s) {} still use the default seeded password, set ADMIN_USER/ADMIN_PASSWORD to rotate it",
+        seeded.join(", ")
+    );
+    if is_dev_server() {
+        warn!("{}", msg);
+        return Ok(());
+    }
+    Err(Error::internal_error(msg, StatusCode::INTERNAL_SERVER_ERROR).into())
+}
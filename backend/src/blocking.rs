@@ -0,0 +1,38 @@
+//! Runs blocking work (diesel queries, bcrypt) on tokio's blocking threads
+//!
+//! diesel and r2d2 are synchronous, calling them straight from a handler stalls the
+//! executor thread, and every other request scheduled on it, until the db answers
+use db::db_pool::{Pool, PooledPgConnection};
+use warp::{http::StatusCode, Rejection};
+
+use crate::error::{convert_to_rejection, Error};
+
+/// Runs `f` on the blocking thread pool and waits for it without blocking the executor
+pub async fn run_blocking<F, T>(f: F) -> Result<T, Rejection>
+where
+    F: FnOnce() -> Result<T, Rejection> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.map_err(|err| {
+        convert_to_rejection(Error::internal_error(
+            format!("blocking task failed: {}", err),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ))
+    })?
+}
+
+/// Gets a connection from `pool` and runs `f` with it, both on the blocking thread pool
+///
+/// the connection goes back to the pool as soon as `f` returns
+pub async fn with_conn<F, T>(pool: &Pool, f: F) -> Result<T, Rejection>
+where
+    F: FnOnce(&mut PooledPgConnection) -> Result<T, Rejection> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool.clone();
+    run_blocking(move || {
+        let mut conn = pool.get().map_err(convert_to_rejection)?;
+        f(&mut conn)
+    })
+    .await
+}
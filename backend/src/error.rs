@@ -1,19 +1,26 @@
-use std::{convert::Infallible, error::Error as StdError, num::ParseIntError, str::Utf8Error};
+use std::{
+    convert::Infallible, error::Error as StdError, num::ParseIntError, str::Utf8Error,
+    time::Duration,
+};
 
 use bcrypt::BcryptError;
 use diesel::result::DatabaseErrorKind;
-use log::error;
-use serde_json::json;
+use serde_json::{json, Value};
 
 use warp::{
     body::BodyDeserializeError,
+    http::header::{HeaderValue, RETRY_AFTER},
     hyper::StatusCode,
     reject::{self, InvalidQuery, MethodNotAllowed, Reject},
-    reply::{self, WithStatus},
+    reply::{self, Response},
     Rejection, Reply,
 };
 
-use db::db_pool::{DieselError, R2D2Err};
+use db::{
+    db_pool::{DieselError, R2D2Err, RECIPE_NAME_KEY, USER_NAME_KEY},
+    password::PasswordError,
+    structs::{ApiErrorResponse, ErrorCode, FieldError},
+};
 
 /// convert any errors to my custom Error
 pub fn convert_to_rejection<E>(error: E) -> Rejection
@@ -23,62 +30,75 @@ where
     reject::custom(error.into())
 }
 
-pub async fn handle_rejection(err: Rejection) -> Result<WithStatus<Box<dyn Reply>>, Infallible> {
+/// Like `convert_to_rejection`, but a missing row is reported with `missing`
+///
+/// e.g. `.map_err(not_found_as(Error::recipe_not_found))`
+pub fn not_found_as(missing: fn() -> Error) -> impl Fn(DieselError) -> Rejection {
+    move |err| match err {
+        DieselError::NotFound => convert_to_rejection(missing()),
+        err => convert_to_rejection(err),
+    }
+}
+
+/// Kind of the `Error` behind an error response, left in the response extensions for metrics
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorKindLabel(pub &'static str);
+
+/// JSON body of an error response
+#[derive(Debug, Clone)]
+struct ErrorBody(Value);
+
+/// Adds `request_id` to the body of an error response, returns the error message
+///
+/// other responses are left as they are
+pub fn set_request_id(response: &mut Response, request_id: &str) -> Option<String> {
+    let ErrorBody(mut body) = response.extensions_mut().remove::<ErrorBody>()?;
+    body["request_id"] = json!(request_id);
+    *response.body_mut() = body.to_string().into();
+    body["message"].as_str().map(String::from)
+}
+
+/// Marks responses to requests that didn't match any endpoint
+#[derive(Debug, Clone, Copy)]
+pub struct UnmatchedRoute;
+
+/// `response` marked as not matching any endpoint
+fn unmatched(mut response: Response) -> Response {
+    response.extensions_mut().insert(UnmatchedRoute);
+    response
+}
+
+/// Converts every rejection to an error response
+///
+/// errors are logged by `logging::log_requests`, together with the request they belong to
+pub async fn handle_rejection(err: Rejection) -> Result<Response, Infallible> {
     if let Some(err) = err.find::<Error>() {
-        error!("{:?}", err);
         Ok(err.convert_to_json())
     } else if let Some(err) = err.find::<InvalidQuery>() {
-        error!("{:?}", err);
-
-        Ok(Error::payload_error(format!(
-            "Query Error: {:?} - {:?}",
-            err.to_string(),
-            err.source()
-        ))
-        .convert_to_json())
+        Ok(Error::payload_error(format!("Invalid query: {}", err)).convert_to_json())
     } else if let Some(err) = err.find::<std::io::Error>() {
-        error!("{:?}", err);
-
-        Ok(Error::payload_error(format!(
-            "Payload Error: 
-                {:?} - msg {:?} ",
-            err.to_string(),
-            err.source()
-        ))
-        .convert_to_json())
+        Ok(Error::payload_error(format!("Invalid body: {}", err)).convert_to_json())
     } else if let Some(err) = err.find::<BodyDeserializeError>() {
         // received invalid json body
-        error!("{}", err);
-
-        Ok(Error::payload_error(format!(
-            "Payload Error: 
-                {:?} - msg {:?} ",
-            err.to_string(),
-            err.source()
-        ))
-        .convert_to_json())
-    } else if let Some(err) = err.find::<MethodNotAllowed>() {
+        let msg = match err.source() {
+            Some(source) => format!("Invalid JSON body: {}", source),
+            None => String::from("Invalid JSON body"),
+        };
+        Ok(Error::payload_error(msg).convert_to_json())
+    } else if err.find::<MethodNotAllowed>().is_some() {
         // Reject invalid HTTP req for specified path.
-        error!("{}", err);
-
-        Ok(Error::payload_error(format!(
-            "Payload Error: 
-                {:?} - msg {:?} ",
-            err.to_string(),
-            err.source()
+        Ok(unmatched(
+            Error::payload_error("Method not allowed").convert_to_json(),
         ))
-        .convert_to_json())
     } else if err.is_not_found() {
-        //received invalid json body
-        error!("{:?}", err);
-
-        Ok(Error::not_found(format!("Not Found!")).convert_to_json())
+        Ok(unmatched(Error::not_found("Not Found!").convert_to_json()))
     } else {
-        Ok(Error::internal_error(
-            format!("Internal server error: {:?}", err),
-            StatusCode::INTERNAL_SERVER_ERROR,
+        // the details stay in the server logs
+        log::error!("unhandled rejection: {:?}", err);
+        Ok(
+            Error::internal_error("Internal server error", StatusCode::INTERNAL_SERVER_ERROR)
+                .convert_to_json(),
         )
-        .convert_to_json())
     }
 }
 
@@ -91,77 +111,201 @@ enum ErrorKind {
     PayloadError,
     InternalServerError,
     UserAuthError,
+    /// access token expired, the client should call `/api/refresh` and retry
+    TokenExpired,
+    /// password broke the policy, every broken rule is sent back
+    WeakPassword,
+    /// rate limited or locked out, retry after the given time
+    TooManyRequests(Duration),
+}
+impl ErrorKind {
+    /// snake_case name, used as the `kind` label in metrics
+    fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::UniqueViolation => "unique_violation",
+            ErrorKind::DatabaseError => "database_error",
+            ErrorKind::PayloadError => "payload_error",
+            ErrorKind::InternalServerError => "internal_server_error",
+            ErrorKind::UserAuthError => "user_auth_error",
+            ErrorKind::TokenExpired => "token_expired",
+            ErrorKind::WeakPassword => "weak_password",
+            ErrorKind::TooManyRequests(_) => "too_many_requests",
+        }
+    }
 }
 #[derive(Debug)]
 /// Custom error types
 pub struct Error {
     kind: ErrorKind,
+    code: ErrorCode,
     status_code: StatusCode,
     msg: String,
+    field_errors: Vec<FieldError>,
 }
 
 impl Error {
-    // handling errors
-    pub fn not_found<S: Into<String>>(msg: S) -> Self {
+    fn new<S: Into<String>>(
+        kind: ErrorKind,
+        code: ErrorCode,
+        status_code: StatusCode,
+        msg: S,
+    ) -> Self {
         Self {
-            kind: ErrorKind::NotFound,
-            status_code: StatusCode::NOT_FOUND,
+            kind,
+            code,
+            status_code,
             msg: msg.into(),
+            field_errors: Vec::new(),
         }
     }
+
+    // handling errors
+    pub fn not_found<S: Into<String>>(msg: S) -> Self {
+        Self::new(
+            ErrorKind::NotFound,
+            ErrorCode::NotFound,
+            StatusCode::NOT_FOUND,
+            msg,
+        )
+    }
+    pub fn recipe_not_found() -> Self {
+        Self::not_found("Recipe not found").with_code(ErrorCode::RecipeNotFound)
+    }
+    pub fn step_not_found() -> Self {
+        Self::not_found("Step not found").with_code(ErrorCode::StepNotFound)
+    }
+    pub fn ingredient_not_found() -> Self {
+        Self::not_found("Ingredient not found").with_code(ErrorCode::IngredientNotFound)
+    }
+    pub fn user_not_found() -> Self {
+        Self::not_found("User not found").with_code(ErrorCode::UserNotFound)
+    }
+    /// `forbidden` for 403, `unauthorized` for any other status
     pub fn user_error<S: Into<String>>(msg: S, status_code: StatusCode) -> Self {
-        Self {
-            kind: ErrorKind::UserAuthError,
-            status_code: status_code,
-            msg: msg.into(),
-        }
+        let code = match status_code {
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            _ => ErrorCode::Unauthorized,
+        };
+        Self::new(ErrorKind::UserAuthError, code, status_code, msg)
     }
     pub fn db_error<S: Into<String>>(msg: S) -> Self {
-        Self {
-            kind: ErrorKind::DatabaseError,
-            status_code: StatusCode::INTERNAL_SERVER_ERROR,
-            msg: msg.into(),
-        }
+        Self::new(
+            ErrorKind::DatabaseError,
+            ErrorCode::DatabaseError,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            msg,
+        )
     }
     pub fn unique_violation<S: Into<String>>(msg: S) -> Self {
-        Self {
-            kind: ErrorKind::UniqueViolation,
-            status_code: StatusCode::CONFLICT,
-            msg: msg.into(),
-        }
+        Self::new(
+            ErrorKind::UniqueViolation,
+            ErrorCode::AlreadyExists,
+            StatusCode::CONFLICT,
+            msg,
+        )
     }
     /// Unknown error occured!
     pub fn internal_error<S: Into<String>>(msg: S, status_code: StatusCode) -> Self {
-        Self {
-            kind: ErrorKind::InternalServerError,
+        Self::new(
+            ErrorKind::InternalServerError,
+            ErrorCode::InternalError,
             status_code,
-            msg: msg.into(),
+            msg,
+        )
+    }
+
+    /// same answer for an unknown user and a wrong password, so logins don't tell which accounts exist
+    pub fn incorrect_login() -> Self {
+        Self::user_error("Incorrect user or password", StatusCode::UNAUTHORIZED)
+    }
+
+    pub fn token_expired() -> Self {
+        Self::new(
+            ErrorKind::TokenExpired,
+            ErrorCode::TokenExpired,
+            StatusCode::UNAUTHORIZED,
+            "Session expired",
+        )
+    }
+
+    pub fn weak_password(errors: Vec<PasswordError>) -> Self {
+        Self {
+            field_errors: errors.iter().map(FieldError::from).collect(),
+            ..Self::new(
+                ErrorKind::WeakPassword,
+                ErrorCode::WeakPassword,
+                StatusCode::BAD_REQUEST,
+                "Password doesn't follow the password policy",
+            )
         }
     }
 
+    pub fn too_many_requests(retry_after: Duration) -> Self {
+        // rounded up, "retry in 0 seconds" would be retried right away
+        let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        Self::new(
+            ErrorKind::TooManyRequests(Duration::from_secs(seconds)),
+            ErrorCode::TooManyRequests,
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Too many attempts, try again in {} seconds", seconds),
+        )
+    }
+
     pub fn payload_error<S: Into<String>>(msg: S) -> Self {
+        Self::new(
+            ErrorKind::PayloadError,
+            ErrorCode::InvalidPayload,
+            StatusCode::BAD_REQUEST,
+            msg,
+        )
+    }
+
+    /// Invalid value in `field` of the request, `msg` says why
+    pub fn field_error<F: Into<String>, S: Into<String>>(field: F, msg: S) -> Self {
+        let field_error = FieldError::new(field, msg);
         Self {
-            kind: ErrorKind::PayloadError,
-            status_code: StatusCode::BAD_REQUEST,
-            msg: msg.into(),
+            field_errors: vec![field_error.clone()],
+            ..Self::payload_error(field_error.message)
         }
     }
 
+    /// Same error, reported with `code`
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+
     /// Convert Error to a valid json reply
-    fn convert_to_json(&self) -> WithStatus<Box<dyn Reply>> {
-        let msg = &self.msg;
-        let body = match &self.kind {
-            ErrorKind::NotFound => Box::new(reply::json(&json!({ "error": msg }))),
-            ErrorKind::UniqueViolation => Box::new(reply::json(&json!({ "error": msg }))),
-            ErrorKind::DatabaseError => Box::new(reply::json(&json!({ "error": msg }))),
-            ErrorKind::PayloadError => Box::new(reply::json(&json!({ "error": msg }))),
-            ErrorKind::InternalServerError => Box::new(reply::json(&json!({ "error": msg }))),
-            ErrorKind::UserAuthError => Box::new(reply::json(&json!({"error":msg}))),
-        };
-        reply::with_status(body, self.status_code)
+    ///
+    /// the body is also kept in the response extensions, `logging::log_requests` adds the
+    /// request id to it
+    fn convert_to_json(&self) -> Response {
+        let body = json!(ApiErrorResponse {
+            code: self.code,
+            message: self.msg.clone(),
+            field_errors: self.field_errors.clone(),
+            request_id: None,
+        });
+        let mut response = reply::with_status(reply::json(&body), self.status_code).into_response();
+        if let ErrorKind::TooManyRequests(retry_after) = &self.kind {
+            if let Ok(value) = HeaderValue::from_str(&retry_after.as_secs().to_string()) {
+                response.headers_mut().insert(RETRY_AFTER, value);
+            }
+        }
+        response
+            .extensions_mut()
+            .insert(ErrorKindLabel(self.kind.name()));
+        response.extensions_mut().insert(ErrorBody(body));
+        response
     }
 }
 impl Reject for Error {}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
 
 impl From<R2D2Err> for Error {
     fn from(value: R2D2Err) -> Self {
@@ -172,15 +316,23 @@ impl From<DieselError> for Error {
     fn from(value: DieselError) -> Self {
         match value {
             DieselError::DatabaseError(kind, msg) => match kind {
+                DatabaseErrorKind::UniqueViolation
+                    if matches!(msg.constraint_name(), Some(USER_NAME_KEY | RECIPE_NAME_KEY)) =>
+                {
+                    Error::unique_violation("Name already taken").with_code(ErrorCode::NameTaken)
+                }
                 DatabaseErrorKind::UniqueViolation => {
                     Error::unique_violation("Inserted value must be unique")
                 }
+                // missing query params, see db::functions::recipe::filter_recipes
+                DatabaseErrorKind::NotNullViolation => Error::payload_error(msg.message()),
                 // other errors
                 DatabaseErrorKind::ForeignKeyViolation => {
                     Error::db_error(format!("Foreign Key violation! {}", msg.message(),))
                 }
                 _ => Error::db_error(format!("Unexpected error!: {}", msg.message())),
             },
+            DieselError::NotFound => Error::not_found("Record not found"),
             err => Error::db_error(format!("Unexpected error! {}", err.to_string())),
         }
     }
@@ -194,7 +346,16 @@ impl From<BcryptError> for Error {
 
 impl From<jsonwebtoken::errors::Error> for Error {
     fn from(value: jsonwebtoken::errors::Error) -> Self {
-        Error::user_error(value.to_string(), StatusCode::BAD_REQUEST)
+        match value.kind() {
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => Error::token_expired(),
+            _ => Error::user_error(value.to_string(), StatusCode::BAD_REQUEST),
+        }
+    }
+}
+
+impl From<Vec<PasswordError>> for Error {
+    fn from(value: Vec<PasswordError>) -> Self {
+        Error::weak_password(value)
     }
 }
 
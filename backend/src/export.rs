@@ -0,0 +1,213 @@
+//! Rendering of a `FullRecipe` into the formats offered by the export endpoint
+use db::{
+    quantity::format_quantity,
+    structs::{ExportFormat, FullRecipe, Ingredient, Unit},
+};
+use serde_json::{json, Value};
+
+/// Renders `full_recipe` as a file body in the requested format
+pub fn render_recipe(full_recipe: &FullRecipe, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::JsonLd => to_json_ld(full_recipe).to_string(),
+        ExportFormat::Markdown => to_markdown(full_recipe),
+        ExportFormat::Txt => to_plain_text(full_recipe),
+    }
+}
+
+/// `Content-Type` header sent with each format
+pub fn content_type(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::JsonLd => "application/ld+json; charset=utf-8",
+        ExportFormat::Markdown => "text/markdown; charset=utf-8",
+        ExportFormat::Txt => "text/plain; charset=utf-8",
+    }
+}
+
+/// Download file name, based on the recipe name
+///
+/// ex: "Roast Chicken!" + Markdown -> "roast-chicken.md"
+pub fn file_name(full_recipe: &FullRecipe, format: ExportFormat) -> String {
+    let extension = match format {
+        ExportFormat::JsonLd => "jsonld",
+        ExportFormat::Markdown => "md",
+        ExportFormat::Txt => "txt",
+    };
+    let mut name = String::new();
+    for c in full_recipe.recipe.recipe_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name = name.trim_end_matches('-');
+    if name.is_empty() {
+        format!("recipe-{}.{}", full_recipe.recipe.id, extension)
+    } else {
+        format!("{}.{}", name, extension)
+    }
+}
+
+/// schema.org `Recipe`, readable back by the import endpoint
+pub fn to_json_ld(full_recipe: &FullRecipe) -> Value {
+    let mut recipe = json!({
+        "@context": "https://schema.org",
+        "@type": "Recipe",
+        "name": full_recipe.recipe.recipe_name,
+        "author": {
+            "@type": "Person",
+            "name": full_recipe.recipe_owner_name,
+        },
+        "recipeYield": full_recipe.recipe.servings.to_string(),
+        "recipeIngredient": full_recipe
+            .ingredients
+            .iter()
+            .map(format_ingredient)
+            .collect::<Vec<String>>(),
+        "recipeInstructions": full_recipe
+            .steps
+            .iter()
+            .map(|step| {
+                let mut how_to = json!({
+                    "@type": "HowToStep",
+                    "name": step.step_name.trim(),
+                    "text": step.step_instruction,
+                });
+                if step.step_duration_min > 0 {
+                    how_to["timeRequired"] = json!(format_iso_duration(step.step_duration_min));
+                }
+                how_to
+            })
+            .collect::<Vec<Value>>(),
+    });
+    let total_minutes = total_duration(full_recipe);
+    if total_minutes > 0 {
+        recipe["totalTime"] = json!(format_iso_duration(total_minutes));
+    }
+    let observations = observations(full_recipe);
+    if !observations.is_empty() {
+        recipe["description"] = json!(observations.join("\n"));
+    }
+    if !full_recipe.tags.is_empty() {
+        let tags: Vec<&str> = full_recipe
+            .tags
+            .iter()
+            .map(|tag| tag.tag_name.as_str())
+            .collect();
+        recipe["keywords"] = json!(tags.join(", "));
+    }
+    recipe
+}
+
+pub fn to_markdown(full_recipe: &FullRecipe) -> String {
+    let mut out = format!("# {}\n\n", full_recipe.recipe.recipe_name);
+    if !full_recipe.recipe_owner_name.is_empty() {
+        out.push_str(&format!("*by {}*\n\n", full_recipe.recipe_owner_name));
+    }
+    out.push_str(&format!("Servings: {}\n\n", full_recipe.recipe.servings));
+    let total_minutes = total_duration(full_recipe);
+    if total_minutes > 0 {
+        out.push_str(&format!("Total time: {} min\n\n", total_minutes));
+    }
+
+    out.push_str("## Ingredients\n\n");
+    for ingredient in &full_recipe.ingredients {
+        out.push_str(&format!("- {}\n", format_ingredient(ingredient)));
+    }
+
+    out.push_str("\n## Steps\n\n");
+    for (idx, step) in full_recipe.steps.iter().enumerate() {
+        out.push_str(&format!("{}. **{}**", idx + 1, step.step_name.trim()));
+        if step.step_duration_min > 0 {
+            out.push_str(&format!(" ({} min)", step.step_duration_min));
+        }
+        out.push_str(&format!("\n   {}\n", step.step_instruction));
+    }
+
+    let observations = observations(full_recipe);
+    if !observations.is_empty() {
+        out.push_str("\n## Observations\n\n");
+        for observation in observations {
+            out.push_str(&format!("- {}\n", observation));
+        }
+    }
+    out
+}
+
+pub fn to_plain_text(full_recipe: &FullRecipe) -> String {
+    let mut out = format!("{}\n", full_recipe.recipe.recipe_name);
+    if !full_recipe.recipe_owner_name.is_empty() {
+        out.push_str(&format!("by {}\n", full_recipe.recipe_owner_name));
+    }
+    out.push_str(&format!("Servings: {}\n", full_recipe.recipe.servings));
+    let total_minutes = total_duration(full_recipe);
+    if total_minutes > 0 {
+        out.push_str(&format!("Total time: {} min\n", total_minutes));
+    }
+
+    out.push_str("\nIngredients\n");
+    for ingredient in &full_recipe.ingredients {
+        out.push_str(&format!("  - {}\n", format_ingredient(ingredient)));
+    }
+
+    out.push_str("\nSteps\n");
+    for (idx, step) in full_recipe.steps.iter().enumerate() {
+        out.push_str(&format!("  {}. {}", idx + 1, step.step_name.trim()));
+        if step.step_duration_min > 0 {
+            out.push_str(&format!(" ({} min)", step.step_duration_min));
+        }
+        out.push_str(&format!("\n     {}\n", step.step_instruction));
+    }
+
+    let observations = observations(full_recipe);
+    if !observations.is_empty() {
+        out.push_str("\nObservations\n");
+        for observation in observations {
+            out.push_str(&format!("  - {}\n", observation));
+        }
+    }
+    out
+}
+
+/// "2 cup flour", "3 eggs" for counted ingredients and "salt (to taste)" when there's no quantity
+fn format_ingredient(ingredient: &Ingredient) -> String {
+    let quantity = format_quantity(&ingredient.ingredient_quantity);
+    match ingredient.quantity_unit {
+        Unit::ToTaste => format!(
+            "{} ({})",
+            ingredient.ingredient_name, ingredient.quantity_unit
+        ),
+        Unit::Count => format!("{} {}", quantity, ingredient.ingredient_name),
+        unit => format!("{} {} {}", quantity, unit, ingredient.ingredient_name),
+    }
+}
+
+fn observations(full_recipe: &FullRecipe) -> Vec<&str> {
+    full_recipe
+        .recipe
+        .recipe_observations
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|observation| observation.as_str())
+        .filter(|observation| !observation.is_empty())
+        .collect()
+}
+
+fn total_duration(full_recipe: &FullRecipe) -> i32 {
+    full_recipe
+        .steps
+        .iter()
+        .map(|step| step.step_duration_min.max(0))
+        .sum()
+}
+
+/// 90 -> "PT1H30M"
+pub fn format_iso_duration(minutes: i32) -> String {
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    match (hours, minutes) {
+        (0, minutes) => format!("PT{}M", minutes),
+        (hours, 0) => format!("PT{}H", hours),
+        (hours, minutes) => format!("PT{}H{}M", hours, minutes),
+    }
+}
@@ -0,0 +1,385 @@
+//! Parsing of schema.org `Recipe` documents (JSON-LD, or HTML pages embedding it)
+//! into the structs used by `create_full_recipe_query`
+use db::{
+    quantity::{normalize_fractions, parse_quantity, BigDecimal, Zero},
+    structs::{NewIngredient, NewRecipe, NewStep, Unit},
+};
+use serde_json::Value;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// Max length of `recipe.recipe_name`
+const RECIPE_NAME_MAX_LEN: usize = 100;
+/// Max length of `recipe_step.step_name`
+const STEP_NAME_MAX_LEN: usize = 50;
+/// Max length of `recipe_ingredient.ingredient_name`
+const INGREDIENT_NAME_MAX_LEN: usize = 100;
+
+/// Recipe parsed from an external source, `recipe_id` and `user_id` are not set yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedRecipe {
+    pub recipe: NewRecipe,
+    pub ingredients: Vec<NewIngredient>,
+    pub steps: Vec<NewStep>,
+}
+
+/// Parses a pasted HTML document or a JSON-LD object into an `ImportedRecipe`
+///
+/// HTML documents are searched for `<script type="application/ld+json">` blocks,
+/// the first one containing a `Recipe` is used
+pub fn parse_recipe_source(source: &str) -> Result<ImportedRecipe, Error> {
+    let source = source.trim();
+    let recipe = if source.starts_with('{') || source.starts_with('[') {
+        let document: Value = serde_json::from_str(source)
+            .map_err(|err| Error::payload_error(format!("Invalid JSON-LD: {}", err)))?;
+        find_recipe_object(&document).cloned()
+    } else {
+        extract_json_ld_blocks(source)
+            .iter()
+            .filter_map(|block| serde_json::from_str::<Value>(block).ok())
+            .find_map(|document| find_recipe_object(&document).cloned())
+    };
+
+    match recipe {
+        Some(recipe) => parse_recipe_object(&recipe),
+        None => Err(Error::payload_error("No schema.org Recipe found")),
+    }
+}
+
+/// Returns the contents of every `<script type="application/ld+json">` tag
+fn extract_json_ld_blocks(html: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps byte offsets valid for slicing `html`
+    let lowercase = html.to_ascii_lowercase();
+    let mut blocks = vec![];
+    let mut cursor = 0;
+
+    while let Some(start) = lowercase[cursor..].find("<script") {
+        let tag_start = cursor + start;
+        let tag_end = match lowercase[tag_start..].find('>') {
+            Some(end) => tag_start + end + 1,
+            None => break,
+        };
+        let content_end = match lowercase[tag_end..].find("</script") {
+            Some(end) => tag_end + end,
+            None => break,
+        };
+        if lowercase[tag_start..tag_end].contains("application/ld+json") {
+            blocks.push(html[tag_end..content_end].trim());
+        }
+        cursor = content_end;
+    }
+    blocks
+}
+
+/// Walks a JSON-LD document (arrays and `@graph` included) looking for a `Recipe`
+fn find_recipe_object(document: &Value) -> Option<&Value> {
+    match document {
+        Value::Array(items) => items.iter().find_map(find_recipe_object),
+        Value::Object(object) => {
+            let is_recipe = match object.get("@type") {
+                Some(Value::String(kind)) => kind == "Recipe",
+                Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind == "Recipe"),
+                _ => false,
+            };
+            if is_recipe {
+                Some(document)
+            } else {
+                object.get("@graph").and_then(find_recipe_object)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn parse_recipe_object(recipe: &Value) -> Result<ImportedRecipe, Error> {
+    let recipe_name = match recipe.get("name").and_then(text_value) {
+        Some(name) if !name.is_empty() => truncate(&name, RECIPE_NAME_MAX_LEN),
+        _ => return Err(Error::payload_error("Recipe has no name")),
+    };
+
+    let mut observations: Vec<Option<String>> = vec![];
+    if let Some(description) = recipe.get("description").and_then(text_value) {
+        if !description.is_empty() {
+            observations.push(Some(description));
+        }
+    }
+    let recipe_yield = recipe.get("recipeYield").and_then(text_value);
+    let servings = recipe_yield.as_deref().and_then(parse_servings);
+    if let Some(recipe_yield) = &recipe_yield {
+        // a bare number is fully stored as `servings`
+        if servings.map(|servings| servings.to_string()).as_ref() != Some(recipe_yield) {
+            observations.push(Some(format!("Yield: {}", recipe_yield)));
+        }
+    }
+    for (key, label) in [
+        ("prepTime", "Prep time"),
+        ("cookTime", "Cook time"),
+        ("totalTime", "Total time"),
+    ] {
+        if let Some(minutes) = recipe
+            .get(key)
+            .and_then(Value::as_str)
+            .and_then(parse_iso_duration)
+        {
+            observations.push(Some(format!("{}: {} min", label, minutes)));
+        }
+    }
+    if let Some(url) = recipe.get("url").and_then(Value::as_str) {
+        observations.push(Some(format!("Source: {}", url)));
+    }
+
+    let ingredients = match recipe.get("recipeIngredient") {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(text_value)
+            .filter(|line| !line.is_empty())
+            .map(|line| parse_ingredient_line(&line))
+            .collect(),
+        Some(Value::String(line)) => vec![parse_ingredient_line(&decode_html_entities(line))],
+        _ => vec![],
+    };
+
+    let mut steps = vec![];
+    if let Some(instructions) = recipe.get("recipeInstructions") {
+        collect_steps(instructions, &mut steps);
+    }
+
+    Ok(ImportedRecipe {
+        recipe: NewRecipe {
+            user_id: 0,
+            recipe_name,
+            recipe_observations: if observations.is_empty() {
+                None
+            } else {
+                Some(observations)
+            },
+            servings: servings.unwrap_or(1),
+        },
+        ingredients,
+        steps,
+    })
+}
+
+/// Flattens `recipeInstructions` (text, list of text, `HowToStep` or `HowToSection`) into steps
+fn collect_steps(instructions: &Value, steps: &mut Vec<NewStep>) {
+    match instructions {
+        Value::String(text) => {
+            // a single block of text, one step per non-empty line
+            for line in decode_html_entities(text).lines() {
+                push_step(steps, None, line.trim(), None);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_steps(item, steps);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(section_items) = object.get("itemListElement") {
+                collect_steps(section_items, steps);
+                return;
+            }
+            let text = object
+                .get("text")
+                .or(object.get("description"))
+                .and_then(text_value)
+                .or(object.get("name").and_then(text_value))
+                .unwrap_or_default();
+            let name = object.get("name").and_then(text_value);
+            let duration = ["timeRequired", "performTime", "totalTime"]
+                .iter()
+                .find_map(|key| object.get(*key).and_then(Value::as_str))
+                .and_then(parse_iso_duration);
+            push_step(steps, name, &text, duration);
+        }
+        _ => {}
+    }
+}
+
+fn push_step(steps: &mut Vec<NewStep>, name: Option<String>, text: &str, duration: Option<i32>) {
+    if text.is_empty() {
+        return;
+    }
+    let step_name = match name {
+        // some sites repeat the instruction as the name
+        Some(name) if !name.is_empty() && name != text => name,
+        _ => format!("Step {}", steps.len() + 1),
+    };
+    steps.push(NewStep {
+        recipe_id: 0,
+        step_name: truncate(&step_name, STEP_NAME_MAX_LEN),
+        step_instruction: text.to_string(),
+        step_duration_min: duration.unwrap_or(0),
+    });
+}
+
+/// First whole number of a `recipeYield` ("4 servings", "Serves 4-6" -> 4)
+pub fn parse_servings(recipe_yield: &str) -> Option<i32> {
+    let digits: String = recipe_yield
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok().filter(|servings| *servings > 0)
+}
+
+/// Parses an ISO-8601 duration ("PT1H30M", "P1DT2H") into whole minutes, rounding seconds up
+pub fn parse_iso_duration(duration: &str) -> Option<i32> {
+    let duration = duration.trim().to_ascii_uppercase();
+    let mut chars = duration.strip_prefix('P')?.chars();
+    let mut in_time = false;
+    let mut number = String::new();
+    let mut seconds: f64 = 0.0;
+    let mut has_component = false;
+
+    for c in chars.by_ref() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' | '.' | ',' => number.push(if c == ',' { '.' } else { c }),
+            unit => {
+                let value: f64 = number.parse().ok()?;
+                number.clear();
+                seconds += value
+                    * match (unit, in_time) {
+                        ('W', false) => 604800.0,
+                        ('D', false) => 86400.0,
+                        ('H', true) => 3600.0,
+                        ('M', true) => 60.0,
+                        ('S', true) => 1.0,
+                        _ => return None,
+                    };
+                has_component = true;
+            }
+        }
+    }
+    if !has_component || !number.is_empty() {
+        return None;
+    }
+    Some((seconds / 60.0).ceil() as i32)
+}
+
+/// Splits an ingredient line like "1 1/2 cups flour, sifted" into quantity/unit/name
+pub fn parse_ingredient_line(line: &str) -> NewIngredient {
+    let line = normalize_fractions(line.trim());
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+
+    let (quantity, glued_unit, consumed) = parse_leading_quantity(&words);
+    words.drain(..consumed);
+
+    // "200g" -> the unit was glued to the quantity
+    let mut unit = glued_unit.and_then(|suffix| Unit::from_str(&suffix).ok());
+    if quantity.is_some() && unit.is_none() {
+        if let Some(word_unit) = words
+            .first()
+            .and_then(|word| Unit::from_str(word.trim_end_matches(['.', ','])).ok())
+        {
+            unit = Some(word_unit);
+            words.remove(0);
+            // "2 cups of flour"
+            if words.first() == Some(&"of") {
+                words.remove(0);
+            }
+        }
+    }
+
+    let name = words.join(" ");
+    let name = match name.trim_matches([',', ' ']) {
+        "" => line.as_str(),
+        name => name,
+    };
+
+    match quantity {
+        Some(quantity) if !quantity.is_zero() => NewIngredient {
+            recipe_id: 0,
+            ingredient_name: truncate(name, INGREDIENT_NAME_MAX_LEN),
+            ingredient_quantity: quantity,
+            quantity_unit: unit.unwrap_or(Unit::Count),
+            ingredient_density: None,
+        },
+        _ => NewIngredient {
+            recipe_id: 0,
+            ingredient_name: truncate(name, INGREDIENT_NAME_MAX_LEN),
+            ingredient_quantity: BigDecimal::from(0),
+            quantity_unit: Unit::ToTaste,
+            ingredient_density: None,
+        },
+    }
+}
+
+/// Reads the leading quantity of an ingredient line
+///
+/// Returns the value, any unit glued to it ("200g") and how many words were used
+fn parse_leading_quantity(words: &[&str]) -> (Option<BigDecimal>, Option<String>, usize) {
+    let first = match words.first() {
+        Some(word) => *word,
+        None => return (None, None, 0),
+    };
+
+    let number_end = first
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ',' || c == '/' || c == '-'))
+        .unwrap_or(first.len());
+    let (number, suffix) = first.split_at(number_end);
+    // ranges "2-3" use the upper bound
+    let number = number
+        .trim_end_matches('-')
+        .rsplit('-')
+        .next()
+        .unwrap_or("");
+    let value = match parse_quantity(number) {
+        Some(value) => value,
+        None => return (None, None, 0),
+    };
+
+    // "1 1/2"
+    if suffix.is_empty() && !number.contains('/') {
+        if let Some(mixed) = words
+            .get(1)
+            .filter(|word| word.contains('/'))
+            .and_then(|fraction| parse_quantity(&format!("{} {}", number, fraction)))
+        {
+            return (Some(mixed), None, 2);
+        }
+    }
+    let suffix = if suffix.is_empty() {
+        None
+    } else {
+        Some(suffix.to_string())
+    };
+    (Some(value), suffix, 1)
+}
+
+/// Reads a JSON-LD text value, which may be a string, a number or a list of those
+fn text_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(decode_html_entities(text.trim())),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(items) => items.iter().find_map(text_value),
+        _ => None,
+    }
+}
+
+fn decode_html_entities(text: &str) -> String {
+    let mut decoded = text.to_string();
+    for (entity, replacement) in [
+        ("&nbsp;", " "),
+        ("&quot;", "\""),
+        ("&#39;", "'"),
+        ("&apos;", "'"),
+        ("&lt;", "<"),
+        ("&gt;", ">"),
+        ("&frac12;", "½"),
+        ("&frac14;", "¼"),
+        ("&frac34;", "¾"),
+        // must be last so "&amp;lt;" is not decoded twice
+        ("&amp;", "&"),
+    ] {
+        decoded = decoded.replace(entity, replacement);
+    }
+    decoded
+}
+
+/// Truncates `text` to at most `max_len` characters
+fn truncate(text: &str, max_len: usize) -> String {
+    text.chars().take(max_len).collect()
+}
@@ -5,17 +5,60 @@ use jsonwebtoken::{
     Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::env;
+use sha2::{Digest, Sha256};
+use std::{
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// access token lifetime when `JWT_TTL_SECONDS` is not set (15 minutes)
+const DEFAULT_JWT_TTL: u64 = 15 * 60;
+/// refresh token lifetime when `REFRESH_TTL_SECONDS` is not set (14 days)
+const DEFAULT_REFRESH_TTL: u64 = 14 * 24 * 60 * 60;
 
 fn get_secret() -> String {
     return env::var("JWT_SECRET_KEY").expect("JWT_SECRET_KEY not found!");
 }
 
+/// Checks JWT_SECRET_KEY is set, tokens can't be signed or checked without it
+pub fn has_secret() -> bool {
+    env::var("JWT_SECRET_KEY").is_ok_and(|secret| !secret.is_empty())
+}
+
+fn ttl_from_env(var: &str, default: u64) -> u64 {
+    env::var(var)
+        .ok()
+        .and_then(|ttl| ttl.parse::<u64>().ok())
+        .filter(|ttl| *ttl > 0)
+        .unwrap_or(default)
+}
+
+/// Seconds an access token (the `jwt` cookie) is valid for
+pub fn access_token_ttl() -> u64 {
+    ttl_from_env("JWT_TTL_SECONDS", DEFAULT_JWT_TTL)
+}
+
+/// Seconds a refresh token (the `refresh_token` cookie) is valid for
+pub fn refresh_token_ttl() -> u64 {
+    ttl_from_env("REFRESH_TTL_SECONDS", DEFAULT_REFRESH_TTL)
+}
+
+/// current unix timestamp, in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
 #[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct UserClaims {
     pub user_id: i32,
     pub role: UserRole,
     pub exp: usize,
+    /// token id, checked against the revoked_token denylist
+    #[serde(default)]
+    pub jti: String,
 }
 impl Default for UserClaims {
     fn default() -> Self {
@@ -23,19 +66,28 @@ impl Default for UserClaims {
             user_id: -1,
             role: UserRole::User,
             exp: Default::default(),
+            jti: String::new(),
         }
     }
 }
 
-pub fn generate_token(user: User) -> Result<String, JWTError> {
+/// Short lived access token, expires after `access_token_ttl()`
+///
+/// `jti` is stored with the user's session so the token can be revoked, see `generate_jti()`
+pub fn generate_token(user: User, jti: String) -> Result<String, JWTError> {
     let claims = UserClaims {
         user_id: user.id,
         role: user.user_role,
-        exp: 10000000000,
+        exp: (now() + access_token_ttl()) as usize,
+        jti,
     };
+    encode_token(&claims)
+}
+
+pub fn encode_token(claims: &UserClaims) -> Result<String, JWTError> {
     return encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(get_secret().as_ref()),
     );
 }
@@ -47,3 +99,22 @@ pub fn validate_token<T: DeserializeOwned>(token: String) -> JWTResult<TokenData
         &Validation::new(Algorithm::HS256),
     );
 }
+
+/// Random access token id (`jti` claim)
+pub fn generate_jti() -> String {
+    to_hex(&rand::random::<[u8; 16]>())
+}
+
+/// Random opaque refresh token, only its hash is stored server side
+pub fn generate_refresh_token() -> String {
+    to_hex(&rand::random::<[u8; 32]>())
+}
+
+/// SHA-256 of a refresh token, as stored in `user_session.token_hash`
+pub fn hash_refresh_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
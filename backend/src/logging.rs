@@ -0,0 +1,189 @@
+//! Logger setup, request ids and access logs
+//!
+//! every request gets an id, taken from the `X-Request-Id` header when the client (or a
+//! proxy) sends a usable one, it's sent back in the same header, added to error bodies
+//! and logged with the request so a reported error can be found in the logs
+use std::{convert::Infallible, env, io::Write, net::SocketAddr, time::Instant};
+
+use chrono::{SecondsFormat, Utc};
+use log::{log, Level};
+use serde_json::{json, Map, Value};
+use warp::{
+    http::{header::HeaderValue, HeaderMap, Method, StatusCode},
+    path::FullPath,
+    reply::Response,
+    Filter,
+};
+
+use crate::{
+    error::{set_request_id, ErrorKindLabel},
+    jwt::{has_secret, validate_token, UserClaims},
+};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// log target of the access log lines
+pub const ACCESS_LOG_TARGET: &str = "backend::access";
+/// longest request id accepted from a client, longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Sets up env_logger, `LOG_FORMAT=text` for the default format, JSON lines otherwise
+///
+/// RUST_LOG still picks what is logged, it defaults to "backend"
+pub fn init_logger() {
+    if env::var_os("RUST_LOG").is_none() {
+        env::set_var("RUST_LOG", "backend")
+    }
+    let mut builder = env_logger::Builder::from_default_env();
+    if env::var("LOG_FORMAT").as_deref() != Ok("text") {
+        builder.format(|buf, record| writeln!(buf, "{}", json_log_line(record)));
+    }
+    builder.init();
+}
+
+/// One log record as a JSON object, access log fields are kept as fields instead of a message
+pub fn json_log_line(record: &log::Record) -> Value {
+    let mut line = Map::new();
+    line.insert(
+        "timestamp".into(),
+        Utc::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    line.insert("level".into(), record.level().as_str().into());
+    line.insert("target".into(), record.target().into());
+    let message = record.args().to_string();
+    match serde_json::from_str::<Map<String, Value>>(&message) {
+        Ok(fields) if record.target() == ACCESS_LOG_TARGET => line.extend(fields),
+        _ => {
+            line.insert("message".into(), message.into());
+        }
+    }
+    Value::Object(line)
+}
+
+/// Checks a client supplied request id, it's echoed in headers and logs so it must be short and plain
+pub fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c))
+}
+
+/// Random 128 bit request id, in hex
+pub fn generate_request_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Request id from the `X-Request-Id` header, or a new one
+pub fn request_id() -> impl Filter<Extract = (String,), Error = Infallible> + Clone {
+    warp::header::headers_cloned().map(|headers: HeaderMap| {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .filter(|id| is_valid_request_id(id))
+            .map(String::from)
+            .unwrap_or_else(generate_request_id)
+    })
+}
+
+/// Access log fields of a handled request
+pub struct AccessLog<'a> {
+    pub request_id: &'a str,
+    pub method: &'a Method,
+    pub path: &'a str,
+    pub status: StatusCode,
+    pub started: Instant,
+    pub user_id: Option<i32>,
+    pub remote_addr: Option<SocketAddr>,
+    /// error kind and message, for error responses
+    pub error: Option<(&'static str, String)>,
+}
+impl AccessLog<'_> {
+    pub fn to_json(&self) -> Value {
+        let mut fields = json!({
+            "request_id": self.request_id,
+            "method": self.method.as_str(),
+            "path": self.path,
+            "status": self.status.as_u16(),
+            "latency_ms": self.started.elapsed().as_secs_f64() * 1000.0,
+            "user_id": self.user_id,
+            "remote_addr": self.remote_addr.map(|addr| addr.ip().to_string()),
+        });
+        if let Some((kind, msg)) = &self.error {
+            fields["error_kind"] = json!(kind);
+            fields["error"] = json!(msg);
+        }
+        fields
+    }
+
+    /// server errors are logged as errors, client errors as warnings
+    pub fn level(&self) -> Level {
+        if self.status.is_server_error() {
+            Level::Error
+        } else if self.status.is_client_error() {
+            Level::Warn
+        } else {
+            Level::Info
+        }
+    }
+}
+
+/// user id of a valid `jwt` cookie, only for logging, revoked tokens aren't checked
+fn logged_user_id(jwt: Option<String>) -> Option<i32> {
+    if !has_secret() {
+        return None;
+    }
+    validate_token::<UserClaims>(jwt?)
+        .ok()
+        .map(|token| token.claims.user_id)
+}
+
+/// Wraps `routes` with a request id and an access log line for every request
+///
+/// `routes` must already be recovered, error bodies get the request id here
+pub fn log_requests<F>(routes: F) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (Response,), Error = Infallible> + Clone + Send + Sync + 'static,
+{
+    warp::any()
+        .map(Instant::now)
+        .and(request_id())
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(warp::cookie::optional::<String>("jwt"))
+        .and(warp::addr::remote())
+        .and(routes)
+        .map(
+            |started: Instant,
+             request_id: String,
+             method: Method,
+             path: FullPath,
+             jwt: Option<String>,
+             remote_addr: Option<SocketAddr>,
+             mut response: Response| {
+                let error = set_request_id(&mut response, &request_id).map(|msg| {
+                    let kind = response
+                        .extensions()
+                        .get::<ErrorKindLabel>()
+                        .map_or("unknown", |ErrorKindLabel(kind)| kind);
+                    (kind, msg)
+                });
+                if let Ok(value) = HeaderValue::from_str(&request_id) {
+                    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                }
+                let access = AccessLog {
+                    request_id: &request_id,
+                    method: &method,
+                    path: path.as_str(),
+                    status: response.status(),
+                    started,
+                    user_id: logged_user_id(jwt),
+                    remote_addr,
+                    error,
+                };
+                log!(target: ACCESS_LOG_TARGET, access.level(), "{}", access.to_json());
+                response
+            },
+        )
+}
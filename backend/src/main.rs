@@ -1,19 +1,35 @@
-use db::db_pool::{connect_to_db, Pool};
+use admin::{admin_from_env, bootstrap_admin, check_admin_on_startup, BOOTSTRAP_ADMIN_COMMAND};
+use db::db_pool::{connect_to_db_with, Pool, PoolConfig};
 use dotenvy::dotenv;
 use error::handle_rejection;
-use log::info;
+use log::{error, info};
+use logging::{init_logger, log_requests};
+use metrics::{instrument, metrics_endpoint, Metrics};
+use migrate::{
+    migrate, pending, run_migrations_on_startup, warn_if_pending, CHECK_MIGRATIONS_FLAG,
+    MIGRATE_ONLY_FLAG,
+};
 use routes::routing_table;
 use std::{
     env,
     net::{IpAddr, SocketAddr},
     str::FromStr,
 };
-use warp::{Filter, Rejection};
+use warp::{http::StatusCode, Filter, Rejection};
 
-use crate::error::convert_to_rejection;
+use crate::error::{convert_to_rejection, Error};
 
+mod admin;
+mod blocking;
 mod error;
+mod export;
+mod import;
 mod jwt;
+mod logging;
+mod metrics;
+mod migrate;
+mod openapi;
+mod rate_limit;
 mod routes;
 #[cfg(test)]
 mod tests;
@@ -39,21 +55,60 @@ pub fn is_dev_server() -> bool {
 
 #[tokio::main]
 async fn main() -> Result<(), Rejection> {
-    if env::var_os("RUST_LOG").is_none() {
-        env::set_var("RUST_LOG", "backend")
-    }
-    env_logger::init();
+    // read first, so RUST_LOG and LOG_FORMAT can be set in .env
     dotenv().ok();
-    let db_pool: Pool = connect_to_db(get_db_url()).map_err(convert_to_rejection)?;
+    init_logger();
+    let pool_config = PoolConfig::from_env();
+    info!("db pool: {:?}", pool_config);
+    let db_pool: Pool =
+        connect_to_db_with(get_db_url(), &pool_config).map_err(convert_to_rejection)?;
+
+    // one-shot modes, they exit without starting the server
+    match env::args().nth(1).as_deref() {
+        Some(MIGRATE_ONLY_FLAG) => return migrate(&db_pool),
+        Some(CHECK_MIGRATIONS_FLAG) => {
+            let pending = pending(&db_pool)?;
+            if pending.is_empty() {
+                info!("schema is up to date");
+                return Ok(());
+            }
+            error!("pending migrations: {}", pending.join(", "));
+            std::process::exit(1);
+        }
+        Some(BOOTSTRAP_ADMIN_COMMAND) => {
+            // create/rotate the admin
+            let Some((user_name, pwd)) = admin_from_env() else {
+                error!(
+                    "{} needs ADMIN_PASSWORD (and optionally ADMIN_USER)",
+                    BOOTSTRAP_ADMIN_COMMAND
+                );
+                std::process::exit(1);
+            };
+            bootstrap_admin(&db_pool, user_name, pwd).await?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // the schema has to be current before the admin is checked
+    if run_migrations_on_startup() {
+        migrate(&db_pool)?;
+    } else {
+        warn_if_pending(&db_pool)?;
+    }
+    check_admin_on_startup(&db_pool).await?;
 
-    let routes = routing_table(db_pool)
-        .recover(handle_rejection)
-        .boxed()
-        .and(warp::addr::remote())
-        .map(|routes, address: Option<SocketAddr>| {
-            info!("Request from: {:?} handled!", address.unwrap());
-            return routes;
-        });
+    let metrics = Metrics::new(db_pool.clone()).map_err(|err| {
+        convert_to_rejection(Error::internal_error(
+            format!("metrics setup failed: {}", err),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ))
+    })?;
+    let app = metrics_endpoint(metrics.clone())
+        .or(routing_table(db_pool))
+        .recover(handle_rejection);
+    // request ids are set outside of the metrics, so the time spent logging isn't measured
+    let routes = log_requests(instrument(metrics, app));
 
     // address used by the server
     let ip: String = env::var("SERVER_IP").unwrap_or("0.0.0.0".into());
@@ -0,0 +1,160 @@
+//! Prometheus metrics, served as text on `GET /metrics`
+//!
+//! `instrument` wraps the routes and records every request, error kinds come from the
+//! `ErrorKindLabel` that `handle_rejection` leaves on error responses, requests that didn't
+//! match an endpoint share the "unmatched" route so random paths don't become labels
+use std::{convert::Infallible, time::Instant};
+
+use db::db_pool::Pool;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use warp::{
+    http::{header::CONTENT_TYPE, Method},
+    path::FullPath,
+    reply::Response,
+    Filter, Rejection, Reply,
+};
+
+use crate::error::{ErrorKindLabel, UnmatchedRoute};
+
+/// route label of requests that didn't match any endpoint
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Clone)]
+/// Cheap to clone handle to the metrics registry
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    latency: HistogramVec,
+    errors: IntCounterVec,
+    pool: Pool,
+    pool_connections: IntGauge,
+    pool_idle: IntGauge,
+    pool_max_size: IntGauge,
+}
+impl Metrics {
+    pub fn new(pool: Pool) -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new(
+                "http_requests_total",
+                "Requests handled, by route and status",
+            ),
+            &["method", "route", "status"],
+        )?;
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to answer a request, by route",
+            ),
+            &["method", "route"],
+        )?;
+        let errors = IntCounterVec::new(
+            Opts::new("http_errors_total", "Error responses, by error kind"),
+            &["kind"],
+        )?;
+        let pool_connections =
+            IntGauge::new("db_pool_connections", "Open db connections, idle or in use")?;
+        let pool_idle = IntGauge::new("db_pool_idle_connections", "Idle db connections")?;
+        let pool_max_size = IntGauge::new("db_pool_max_size", "Max db connections")?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        registry.register(Box::new(errors.clone()))?;
+        registry.register(Box::new(pool_connections.clone()))?;
+        registry.register(Box::new(pool_idle.clone()))?;
+        registry.register(Box::new(pool_max_size.clone()))?;
+        Ok(Self {
+            registry,
+            requests,
+            latency,
+            errors,
+            pool,
+            pool_connections,
+            pool_idle,
+            pool_max_size,
+        })
+    }
+
+    /// Records a handled request
+    pub fn observe(&self, method: &Method, path: &str, response: &Response, started: Instant) {
+        let status = response.status();
+        let unmatched = response.extensions().get::<UnmatchedRoute>().is_some();
+        let route = route_label(path, unmatched);
+        self.requests
+            .with_label_values(&[method.as_str(), &route, status.as_str()])
+            .inc();
+        self.latency
+            .with_label_values(&[method.as_str(), &route])
+            .observe(started.elapsed().as_secs_f64());
+        if let Some(ErrorKindLabel(kind)) = response.extensions().get::<ErrorKindLabel>() {
+            self.errors.with_label_values(&[kind]).inc();
+        }
+    }
+
+    /// Every metric in the Prometheus text format, pool stats are read now
+    pub fn render(&self) -> String {
+        let state = self.pool.state();
+        self.pool_connections.set(state.connections as i64);
+        self.pool_idle.set(state.idle_connections as i64);
+        self.pool_max_size.set(self.pool.max_size() as i64);
+
+        let mut buffer = vec![];
+        // encoding to a Vec can't fail
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buffer);
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Route label for `path`: ids are replaced by `:id` so every recipe/user shares a label
+pub fn route_label(path: &str, unmatched: bool) -> String {
+    if unmatched {
+        return UNMATCHED_ROUTE.to_string();
+    }
+    path.split('/')
+        .map(|segment| match segment.parse::<i64>() {
+            Ok(_) => ":id",
+            Err(_) => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Wraps `routes` so every request they answer is recorded in `metrics`
+///
+/// `routes` must already be recovered, so error responses are recorded too
+pub fn instrument<F, R>(
+    metrics: Metrics,
+    routes: F,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::any()
+        .map(Instant::now)
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(routes)
+        .map(
+            move |started: Instant, method: Method, path: FullPath, reply: R| {
+                let response = reply.into_response();
+                metrics.observe(&method, path.as_str(), &response, started);
+                response
+            },
+        )
+}
+
+/// `GET /metrics` endpoint
+pub fn metrics_endpoint(
+    metrics: Metrics,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::get().and(warp::path!("metrics")).map(move || {
+        warp::reply::with_header(
+            metrics.render(),
+            CONTENT_TYPE,
+            TextEncoder::new().format_type(),
+        )
+    })
+}
@@ -0,0 +1,59 @@
+//! Schema migrations at startup, db/migrations are embedded in the `db` crate
+//!
+//! `RUN_MIGRATIONS=true` applies pending migrations before serving, deploy pipelines
+//! can use `backend --migrate-only` or `backend --check-migrations` instead
+use db::db_pool::{pending_migrations, run_pending_migrations, MigrationError, Pool};
+use log::{info, warn};
+use std::env;
+use warp::Rejection;
+
+use crate::error::{convert_to_rejection, Error};
+
+/// applies pending migrations and exits
+pub const MIGRATE_ONLY_FLAG: &str = "--migrate-only";
+/// exits with an error if migrations are pending, nothing is applied
+pub const CHECK_MIGRATIONS_FLAG: &str = "--check-migrations";
+
+/// Checks if RUN_MIGRATIONS is "true", defaults to false
+pub fn run_migrations_on_startup() -> bool {
+    env::var("RUN_MIGRATIONS").is_ok_and(|val| val == "true")
+}
+
+fn migration_error(err: MigrationError) -> Rejection {
+    convert_to_rejection(Error::db_error(format!("Migration error: {}", err)))
+}
+
+/// Applies every pending migration, waits if another instance is migrating
+pub fn migrate(pool: &Pool) -> Result<(), Rejection> {
+    let mut conn = pool.get().map_err(convert_to_rejection)?;
+    let applied = run_pending_migrations(&mut conn).map_err(migration_error)?;
+    if applied.is_empty() {
+        info!("schema is up to date");
+    } else {
+        info!("applied migrations: {}", applied.join(", "));
+    }
+    Ok(())
+}
+
+/// Names of the migrations not applied yet
+pub fn pending(pool: &Pool) -> Result<Vec<String>, Rejection> {
+    let mut conn = pool.get().map_err(convert_to_rejection)?;
+    Ok(pending_migrations(&mut conn)
+        .map_err(migration_error)?
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect())
+}
+
+/// Warns about pending migrations, the backend may not work against an old schema
+pub fn warn_if_pending(pool: &Pool) -> Result<(), Rejection> {
+    let pending = pending(pool)?;
+    if !pending.is_empty() {
+        warn!(
+            "schema is out of date, pending migrations: {} (run with {} or set RUN_MIGRATIONS=true)",
+            pending.join(", "),
+            MIGRATE_ONLY_FLAG
+        );
+    }
+    Ok(())
+}
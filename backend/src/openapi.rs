@@ -0,0 +1,691 @@
+//! OpenAPI 3 document of the API, served at `/api/openapi.json`
+//!
+//! schemas come from the `db::structs` types the handlers (de)serialize, every endpoint of
+//! `routes::ROUTES` must be listed in `openapi_spec` (checked by `test_openapi_coverage`)
+use db::{
+    password::PasswordPolicy,
+    structs::{
+        ApiErrorResponse, ApiOkResponse, FullRecipe, Ingredient, NewFullRecipe, NewIngredient,
+        NewRecipe, NewStep, NewUser, Page, PublicUser, Recipe, RecipeTags, ReorderItems, Step, Tag,
+        TagSummary, UpdateIngredient, UpdateRecipe, UpdateStep, UpdateUser, UrlExportQuery,
+        UrlPageQuery, UrlRecipeQuery, UrlSearchQuery, UrlServingsQuery, UrlTagQuery, UrlUserQuery,
+        User,
+    },
+};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+use crate::routes::health_route::HealthReport;
+
+/// Document being built, schemas referenced by endpoints end up in `components`
+pub struct OpenApi {
+    gen: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+impl OpenApi {
+    pub fn new() -> Self {
+        Self {
+            gen: SchemaGenerator::new(SchemaSettings::openapi3()),
+            paths: Map::new(),
+        }
+    }
+
+    /// Starts documenting `method` (lowercase) on `path`, call `add` to keep it
+    ///
+    /// `{name}` segments of `path` are documented as integer path params
+    pub fn endpoint(&mut self, method: &str, path: &str, summary: &str) -> Endpoint<'_> {
+        let mut operation = json!({
+            "summary": summary,
+            "responses": { "default": { "$ref": "#/components/responses/Error" } },
+        });
+        let params: Vec<Value> = path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "integer", "format": "int32" },
+                })
+            })
+            .collect();
+        if !params.is_empty() {
+            operation["parameters"] = Value::Array(params);
+        }
+        Endpoint {
+            api: self,
+            method: method.to_string(),
+            path: path.to_string(),
+            operation,
+        }
+    }
+
+    /// The finished document
+    pub fn into_json(mut self) -> Value {
+        let error = self.gen.subschema_for::<ApiErrorResponse>();
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": "recipes-app API",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "paths": self.paths,
+            "components": {
+                "schemas": self.gen.definitions(),
+                "responses": {
+                    "Error": {
+                        "description": "Error, see `code` for the reason",
+                        "content": { "application/json": { "schema": error } },
+                    },
+                },
+                "securitySchemes": {
+                    "jwt": { "type": "apiKey", "in": "cookie", "name": "jwt" },
+                },
+            },
+        })
+    }
+}
+impl Default for OpenApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One documented endpoint
+pub struct Endpoint<'a> {
+    api: &'a mut OpenApi,
+    method: String,
+    path: String,
+    operation: Value,
+}
+impl Endpoint<'_> {
+    /// Query params, one per field of `T`
+    pub fn query<T: JsonSchema>(mut self) -> Self {
+        // inlined, params can't point at a shared schema
+        let root = SchemaSettings::openapi3()
+            .with(|settings| settings.inline_subschemas = true)
+            .into_generator()
+            .into_root_schema_for::<T>();
+        let Some(object) = root.schema.object else {
+            return self;
+        };
+        let params = self.operation["parameters"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let params = params
+            .into_iter()
+            .chain(object.properties.iter().map(|(name, schema)| {
+                json!({
+                    "name": name,
+                    "in": "query",
+                    "required": object.required.contains(name),
+                    "schema": schema,
+                })
+            }));
+        self.operation["parameters"] = Value::Array(params.collect());
+        self
+    }
+
+    /// JSON body of type `T`
+    pub fn json_body<T: JsonSchema>(self) -> Self {
+        let schema = json!(self.api.gen.subschema_for::<T>());
+        self.body("application/json", schema)
+    }
+
+    /// Body sent as any of `content_types`, used for non JSON bodies
+    pub fn body(mut self, content_type: &str, schema: Value) -> Self {
+        let mut content = self.operation["requestBody"]["content"]
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        content.insert(content_type.to_string(), json!({ "schema": schema }));
+        self.operation["requestBody"] = json!({ "required": true, "content": content });
+        self
+    }
+
+    /// 200 response with a JSON `T`
+    pub fn json_response<T: JsonSchema>(self, description: &str) -> Self {
+        let schema = json!(self.api.gen.subschema_for::<T>());
+        self.response(&["application/json"], schema, description)
+    }
+
+    /// 200 response sent as any of `content_types`
+    pub fn response(mut self, content_types: &[&str], schema: Value, description: &str) -> Self {
+        let content: Map<String, Value> = content_types
+            .iter()
+            .map(|content_type| (content_type.to_string(), json!({ "schema": schema })))
+            .collect();
+        self.operation["responses"]["200"] = json!({
+            "description": description,
+            "content": content,
+        });
+        self
+    }
+
+    /// 201 response with a JSON `T`, the new item's path is in the `Location` header
+    pub fn json_created<T: JsonSchema>(mut self, description: &str) -> Self {
+        let schema = json!(self.api.gen.subschema_for::<T>());
+        self.operation["responses"]["201"] = json!({
+            "description": description,
+            "headers": { "Location": { "schema": { "type": "string" } } },
+            "content": { "application/json": { "schema": schema } },
+        });
+        self
+    }
+
+    /// 204 response, without a body
+    pub fn no_content(mut self, description: &str) -> Self {
+        self.operation["responses"]["204"] = json!({ "description": description });
+        self
+    }
+
+    /// The `jwt` cookie is read, the endpoint decides if it's required
+    pub fn auth(mut self) -> Self {
+        self.operation["security"] = json!([{ "jwt": [] }]);
+        self
+    }
+
+    pub fn add(self) {
+        let path = self.api.paths.entry(self.path).or_insert_with(|| json!({}));
+        path[self.method] = self.operation;
+    }
+}
+
+/// The API document, see the module docs
+pub fn openapi_spec() -> Value {
+    let mut api = OpenApi::new();
+    let string = json!({ "type": "string" });
+
+    // recipe endpoints
+    api.endpoint("post", "/api/create/recipe", "Create a recipe")
+        .json_body::<NewRecipe>()
+        .json_response::<Recipe>("The created recipe")
+        .auth()
+        .add();
+    api.endpoint(
+        "post",
+        "/api/create/full_recipe",
+        "Create a recipe with its ingredients and steps, all or nothing",
+    )
+    .json_body::<NewFullRecipe>()
+    .json_response::<FullRecipe>("The created recipe")
+    .auth()
+    .add();
+    api.endpoint(
+        "post",
+        "/api/import/recipe",
+        "Create a recipe from an HTML page or a JSON-LD `Recipe` (up to 2 MiB)",
+    )
+    .body("text/html", string.clone())
+    .body("application/ld+json", string.clone())
+    .json_response::<FullRecipe>("The imported recipe")
+    .auth()
+    .add();
+    api.endpoint("get", "/api/delete/recipe", "Delete a recipe by id or name")
+        .query::<UrlRecipeQuery>()
+        .json_response::<ApiOkResponse>("Recipe deleted")
+        .auth()
+        .add();
+    api.endpoint(
+        "get",
+        "/api/get/recipe",
+        "Recipe with ingredients and steps, scaled when `servings` is sent",
+    )
+    .query::<UrlRecipeQuery>()
+    .query::<UrlServingsQuery>()
+    .json_response::<FullRecipe>("The recipe")
+    .add();
+    api.endpoint(
+        "get",
+        "/api/export/recipe",
+        "Download a recipe as JSON-LD, Markdown or plain text",
+    )
+    .query::<UrlRecipeQuery>()
+    .query::<UrlExportQuery>()
+    .response(
+        &["application/ld+json", "text/markdown", "text/plain"],
+        string.clone(),
+        "The recipe file, in the requested `format`",
+    )
+    .add();
+    api.endpoint("get", "/api/get/recipes", "Recipes with a name like `name`")
+        .query::<UrlRecipeQuery>()
+        .query::<UrlTagQuery>()
+        .query::<UrlPageQuery>()
+        .json_response::<Page<Recipe>>("A page of recipes")
+        .add();
+    api.endpoint(
+        "get",
+        "/api/search/recipes",
+        "Full-text search over names, observations, ingredients and steps",
+    )
+    .query::<UrlSearchQuery>()
+    .query::<UrlTagQuery>()
+    .query::<UrlPageQuery>()
+    .json_response::<Page<Recipe>>("A page of recipes, most relevant first")
+    .add();
+    api.endpoint("post", "/api/update/recipe", "Update a recipe's fields")
+        .json_body::<UpdateRecipe>()
+        .json_response::<ApiOkResponse>("Recipe updated")
+        .auth()
+        .add();
+    api.endpoint(
+        "get",
+        "/api/get/permission",
+        "Checks the logged in user can edit a recipe",
+    )
+    .query::<UrlRecipeQuery>()
+    .json_response::<ApiOkResponse>("The user can edit the recipe")
+    .auth()
+    .add();
+
+    // step endpoints
+    api.endpoint("post", "/api/create/step", "Add steps to a recipe")
+        .json_body::<Vec<NewStep>>()
+        .json_response::<Vec<Step>>("The created steps")
+        .auth()
+        .add();
+    api.endpoint("post", "/api/delete/step", "Delete a step")
+        .json_body::<Step>()
+        .json_response::<ApiOkResponse>("Step deleted")
+        .auth()
+        .add();
+    api.endpoint("post", "/api/update/step", "Update a step's fields")
+        .json_body::<UpdateStep>()
+        .json_response::<Step>("The updated step")
+        .auth()
+        .add();
+    api.endpoint(
+        "post",
+        "/api/reorder/steps",
+        "Reorder every step of a recipe",
+    )
+    .json_body::<ReorderItems>()
+    .json_response::<Vec<Step>>("The steps, in their new order")
+    .auth()
+    .add();
+
+    // ingredient endpoints
+    api.endpoint(
+        "post",
+        "/api/create/ingredient",
+        "Add ingredients to a recipe",
+    )
+    .json_body::<Vec<NewIngredient>>()
+    .json_response::<Vec<Ingredient>>("The created ingredients")
+    .auth()
+    .add();
+    api.endpoint("post", "/api/delete/ingredient", "Delete an ingredient")
+        .json_body::<Ingredient>()
+        .json_response::<ApiOkResponse>("Ingredient deleted")
+        .auth()
+        .add();
+    api.endpoint(
+        "post",
+        "/api/update/ingredient",
+        "Update an ingredient's fields",
+    )
+    .json_body::<UpdateIngredient>()
+    .json_response::<Ingredient>("The updated ingredient")
+    .auth()
+    .add();
+    api.endpoint(
+        "post",
+        "/api/reorder/ingredients",
+        "Reorder every ingredient of a recipe",
+    )
+    .json_body::<ReorderItems>()
+    .json_response::<Vec<Ingredient>>("The ingredients, in their new order")
+    .auth()
+    .add();
+
+    // tag endpoints
+    api.endpoint("get", "/api/get/tags", "Tags used by at least one recipe")
+        .json_response::<Vec<TagSummary>>("The tags, sorted by name")
+        .add();
+    api.endpoint("post", "/api/create/tag", "Add tags to a recipe")
+        .json_body::<RecipeTags>()
+        .json_response::<Vec<Tag>>("Every tag of the recipe")
+        .auth()
+        .add();
+    api.endpoint("post", "/api/delete/tag", "Remove tags from a recipe")
+        .json_body::<RecipeTags>()
+        .json_response::<Vec<Tag>>("The remaining tags of the recipe")
+        .auth()
+        .add();
+    api.endpoint("post", "/api/update/tags", "Replace every tag of a recipe")
+        .json_body::<RecipeTags>()
+        .json_response::<Vec<Tag>>("The new tags of the recipe")
+        .auth()
+        .add();
+
+    // user endpoints
+    api.endpoint("post", "/api/create/user", "Register a user")
+        .json_body::<NewUser>()
+        .json_response::<ApiOkResponse>("User created")
+        .add();
+    api.endpoint(
+        "get",
+        "/api/delete/user",
+        "Delete a user, admins or the user itself",
+    )
+    .query::<UrlUserQuery>()
+    .json_response::<ApiOkResponse>("User deleted")
+    .auth()
+    .add();
+    api.endpoint("get", "/api/get/username", "Name of a user, in `msg`")
+        .query::<UrlUserQuery>()
+        .json_response::<ApiOkResponse>("The user name")
+        .auth()
+        .add();
+    api.endpoint(
+        "post",
+        "/api/login/user",
+        "Log in, sets the `jwt` and `refresh_token` cookies",
+    )
+    .json_body::<User>()
+    .json_response::<ApiOkResponse>("Logged in")
+    .add();
+    api.endpoint(
+        "post",
+        "/api/refresh",
+        "Swap the `refresh_token` cookie for new session cookies",
+    )
+    .json_response::<ApiOkResponse>("Session refreshed")
+    .add();
+    api.endpoint(
+        "post",
+        "/api/logout",
+        "Log out, revokes the session and expires its cookies",
+    )
+    .json_response::<ApiOkResponse>("Logged out")
+    .add();
+    api.endpoint(
+        "post",
+        "/api/revoke/sessions",
+        "Log a user out of every session, admins only",
+    )
+    .query::<UrlUserQuery>()
+    .json_response::<ApiOkResponse>("Sessions revoked")
+    .auth()
+    .add();
+    api.endpoint("post", "/api/update/user", "Update a user's fields")
+        .json_body::<UpdateUser>()
+        .json_response::<ApiOkResponse>("User updated")
+        .auth()
+        .add();
+    api.endpoint("get", "/api/list/user", "List users, admins only")
+        .query::<UrlPageQuery>()
+        .json_response::<Page<PublicUser>>("A page of users")
+        .auth()
+        .add();
+    api.endpoint(
+        "get",
+        "/api/get/password_policy",
+        "Rules new passwords must follow",
+    )
+    .json_response::<PasswordPolicy>("The password policy")
+    .add();
+    api.endpoint(
+        "get",
+        "/api/get/admin",
+        "Checks the logged in user is an admin",
+    )
+    .json_response::<ApiOkResponse>("The user is an admin")
+    .auth()
+    .add();
+
+    // v2 recipe endpoints
+    api.endpoint(
+        "get",
+        "/api/v2/recipes",
+        "Recipes matching `query` (full-text) or with a name like `name`",
+    )
+    .query::<UrlRecipeQuery>()
+    .query::<UrlSearchQuery>()
+    .query::<UrlTagQuery>()
+    .query::<UrlPageQuery>()
+    .json_response::<Page<Recipe>>("A page of recipes")
+    .add();
+    api.endpoint("post", "/api/v2/recipes", "Create a recipe")
+        .json_body::<NewRecipe>()
+        .json_created::<Recipe>("The created recipe")
+        .auth()
+        .add();
+    api.endpoint(
+        "get",
+        "/api/v2/recipes/{recipe_id}",
+        "Recipe with ingredients and steps, scaled when `servings` is sent",
+    )
+    .query::<UrlServingsQuery>()
+    .json_response::<FullRecipe>("The recipe")
+    .add();
+    api.endpoint(
+        "patch",
+        "/api/v2/recipes/{recipe_id}",
+        "Update a recipe's fields",
+    )
+    .json_body::<UpdateRecipe>()
+    .json_response::<Recipe>("The updated recipe")
+    .auth()
+    .add();
+    api.endpoint(
+        "delete",
+        "/api/v2/recipes/{recipe_id}",
+        "Delete a recipe with its ingredients and steps",
+    )
+    .no_content("Recipe deleted")
+    .auth()
+    .add();
+
+    // v2 step endpoints
+    api.endpoint(
+        "get",
+        "/api/v2/recipes/{recipe_id}/steps",
+        "Steps of a recipe, in order",
+    )
+    .json_response::<Vec<Step>>("The steps")
+    .add();
+    api.endpoint(
+        "post",
+        "/api/v2/recipes/{recipe_id}/steps",
+        "Add a step after the last one",
+    )
+    .json_body::<NewStep>()
+    .json_created::<Step>("The created step")
+    .auth()
+    .add();
+    api.endpoint(
+        "put",
+        "/api/v2/recipes/{recipe_id}/steps/order",
+        "Reorder every step of a recipe",
+    )
+    .json_body::<ReorderItems>()
+    .json_response::<Vec<Step>>("The steps, in their new order")
+    .auth()
+    .add();
+    api.endpoint(
+        "get",
+        "/api/v2/recipes/{recipe_id}/steps/{step_id}",
+        "A step",
+    )
+    .json_response::<Step>("The step")
+    .add();
+    api.endpoint(
+        "patch",
+        "/api/v2/recipes/{recipe_id}/steps/{step_id}",
+        "Update a step's fields",
+    )
+    .json_body::<UpdateStep>()
+    .json_response::<Step>("The updated step")
+    .auth()
+    .add();
+    api.endpoint(
+        "delete",
+        "/api/v2/recipes/{recipe_id}/steps/{step_id}",
+        "Delete a step",
+    )
+    .no_content("Step deleted")
+    .auth()
+    .add();
+
+    // v2 ingredient endpoints
+    api.endpoint(
+        "get",
+        "/api/v2/recipes/{recipe_id}/ingredients",
+        "Ingredients of a recipe, in order",
+    )
+    .json_response::<Vec<Ingredient>>("The ingredients")
+    .add();
+    api.endpoint(
+        "post",
+        "/api/v2/recipes/{recipe_id}/ingredients",
+        "Add an ingredient after the last one",
+    )
+    .json_body::<NewIngredient>()
+    .json_created::<Ingredient>("The created ingredient")
+    .auth()
+    .add();
+    api.endpoint(
+        "put",
+        "/api/v2/recipes/{recipe_id}/ingredients/order",
+        "Reorder every ingredient of a recipe",
+    )
+    .json_body::<ReorderItems>()
+    .json_response::<Vec<Ingredient>>("The ingredients, in their new order")
+    .auth()
+    .add();
+    api.endpoint(
+        "get",
+        "/api/v2/recipes/{recipe_id}/ingredients/{ingredient_id}",
+        "An ingredient",
+    )
+    .json_response::<Ingredient>("The ingredient")
+    .add();
+    api.endpoint(
+        "patch",
+        "/api/v2/recipes/{recipe_id}/ingredients/{ingredient_id}",
+        "Update an ingredient's fields",
+    )
+    .json_body::<UpdateIngredient>()
+    .json_response::<Ingredient>("The updated ingredient")
+    .auth()
+    .add();
+    api.endpoint(
+        "delete",
+        "/api/v2/recipes/{recipe_id}/ingredients/{ingredient_id}",
+        "Delete an ingredient",
+    )
+    .no_content("Ingredient deleted")
+    .auth()
+    .add();
+
+    // v2 tag endpoints
+    api.endpoint("get", "/api/v2/tags", "Tags used by at least one recipe")
+        .json_response::<Vec<TagSummary>>("The tags, sorted by name")
+        .add();
+    api.endpoint(
+        "get",
+        "/api/v2/recipes/{recipe_id}/tags",
+        "Tags of a recipe, sorted by name",
+    )
+    .json_response::<Vec<Tag>>("The tags")
+    .add();
+    api.endpoint(
+        "post",
+        "/api/v2/recipes/{recipe_id}/tags",
+        "Add tags to a recipe",
+    )
+    .json_body::<RecipeTags>()
+    .json_response::<Vec<Tag>>("Every tag of the recipe")
+    .auth()
+    .add();
+    api.endpoint(
+        "put",
+        "/api/v2/recipes/{recipe_id}/tags",
+        "Replace every tag of a recipe",
+    )
+    .json_body::<RecipeTags>()
+    .json_response::<Vec<Tag>>("The new tags of the recipe")
+    .auth()
+    .add();
+
+    // v2 user endpoints, `user_pwd` is always empty in responses
+    api.endpoint("post", "/api/v2/users", "Register a user")
+        .json_body::<NewUser>()
+        .json_created::<PublicUser>("The created user")
+        .add();
+    api.endpoint("get", "/api/v2/users", "List users, admins only")
+        .query::<UrlPageQuery>()
+        .json_response::<Page<PublicUser>>("A page of users")
+        .auth()
+        .add();
+    api.endpoint(
+        "get",
+        "/api/v2/users/{user_id}",
+        "A user, admins or the user itself",
+    )
+    .json_response::<PublicUser>("The user")
+    .auth()
+    .add();
+    api.endpoint(
+        "patch",
+        "/api/v2/users/{user_id}",
+        "Update a user's fields, only admins can change roles",
+    )
+    .json_body::<UpdateUser>()
+    .json_response::<PublicUser>("The updated user")
+    .auth()
+    .add();
+    api.endpoint(
+        "delete",
+        "/api/v2/users/{user_id}",
+        "Delete a user and their recipes, admins or the user itself",
+    )
+    .no_content("User deleted")
+    .auth()
+    .add();
+
+    // service endpoints
+    api.endpoint("get", "/api/ping", "Always answers, any method works")
+        .json_response::<ApiOkResponse>("The server is up")
+        .add();
+    api.endpoint("get", "/api/health/live", "Liveness probe")
+        .json_response::<HealthReport>("The server is up")
+        .add();
+    api.endpoint(
+        "get",
+        "/api/health/ready",
+        "Readiness probe, 503 if the db, migrations or JWT secret aren't ready",
+    )
+    .json_response::<HealthReport>("Ready to serve requests")
+    .add();
+    api.endpoint(
+        "get",
+        "/api/health/pool",
+        "Connection pool usage, 503 while saturated",
+    )
+    .response(
+        &["application/json"],
+        json!({ "type": "object" }),
+        "Open, idle and in use connections",
+    )
+    .add();
+    api.endpoint("get", "/api/openapi.json", "This document")
+        .response(
+            &["application/json"],
+            json!({ "type": "object" }),
+            "OpenAPI 3 document",
+        )
+        .add();
+    api.endpoint("get", "/metrics", "Prometheus metrics")
+        .response(&["text/plain"], string, "Metrics in the text format")
+        .add();
+
+    api.into_json()
+}
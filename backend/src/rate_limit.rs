@@ -0,0 +1,164 @@
+//! Request rate limiting, used to slow down login attempts
+//!
+//! every key (remote address, user name) gets a token bucket: each request takes a
+//! token and tokens come back at a steady rate, so short bursts are fine but
+//! hammering an endpoint is not
+use std::{
+    collections::HashMap,
+    env,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use db::structs::User;
+use warp::{Filter, Rejection};
+
+use crate::error::Error;
+
+/// buckets kept in memory before the full (idle) ones are dropped
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Token bucket settings
+pub struct BucketConfig {
+    /// requests allowed in a burst
+    pub capacity: f64,
+    /// tokens given back per second
+    pub refill_per_sec: f64,
+}
+impl Default for BucketConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 5.0,
+            // 10 requests a minute
+            refill_per_sec: 10.0 / 60.0,
+        }
+    }
+}
+impl BucketConfig {
+    /// Login limits, from `LOGIN_RATE_LIMIT_BURST` and `LOGIN_RATE_LIMIT_PER_MINUTE`
+    pub fn login_from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<f64>().ok())
+                .filter(|value| *value > 0.0)
+        };
+        Self {
+            capacity: var("LOGIN_RATE_LIMIT_BURST").unwrap_or(default.capacity),
+            refill_per_sec: var("LOGIN_RATE_LIMIT_PER_MINUTE")
+                .map(|per_minute| per_minute / 60.0)
+                .unwrap_or(default.refill_per_sec),
+        }
+    }
+}
+
+/// Where token buckets are kept, implement it to share limits between backend instances
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from `key`'s bucket, or returns how long to wait for the next one
+    fn take(&self, key: &str, now: Instant) -> Result<(), Duration>;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// In memory store, limits are per backend process and reset on restart
+pub struct MemoryStore {
+    config: BucketConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+impl MemoryStore {
+    pub fn new(config: BucketConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// tokens in `bucket` at `now`, counting the ones given back since its last update
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        (bucket.tokens + elapsed.as_secs_f64() * self.config.refill_per_sec)
+            .min(self.config.capacity)
+    }
+}
+impl RateLimitStore for MemoryStore {
+    fn take(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= MAX_TRACKED_KEYS {
+            // a full bucket is the same as no bucket
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.config.capacity);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.config.capacity,
+            updated_at: now,
+        });
+        let tokens = self.refilled(bucket, now);
+        if tokens < 1.0 {
+            let wait = (1.0 - tokens) / self.config.refill_per_sec;
+            return Err(Duration::from_secs_f64(wait));
+        }
+        *bucket = Bucket {
+            tokens: tokens - 1.0,
+            updated_at: now,
+        };
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+/// Cheap to clone handle to a `RateLimitStore`
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+}
+impl RateLimiter {
+    pub fn new<S: RateLimitStore + 'static>(store: S) -> Self {
+        Self {
+            store: Arc::new(store),
+        }
+    }
+
+    /// Takes a token for every key, failing with the longest wait if any bucket is empty
+    pub fn check(&self, keys: &[String]) -> Result<(), Duration> {
+        let now = Instant::now();
+        let waits: Vec<Duration> = keys
+            .iter()
+            .filter_map(|key| self.store.take(key, now).err())
+            .collect();
+        match waits.into_iter().max() {
+            Some(wait) => Err(wait),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Rate limits login requests by remote address and by user name
+///
+/// extracts the login body, so the handler gets the `User` as usual
+pub fn limit_login(
+    limiter: RateLimiter,
+) -> impl Filter<Extract = (User,), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::body::json::<User>())
+        .and_then(move |address: Option<SocketAddr>, user: User| {
+            let limiter = limiter.clone();
+            async move {
+                let mut keys = vec![format!("user:{}", user.user_name.to_lowercase())];
+                if let Some(address) = address {
+                    keys.push(format!("ip:{}", address.ip()));
+                }
+                match limiter.check(&keys) {
+                    Ok(()) => Ok(user),
+                    Err(wait) => Err(warp::reject::custom(Error::too_many_requests(wait))),
+                }
+            }
+        })
+}
@@ -1,20 +1,46 @@
 use crate::jwt::{validate_token, UserClaims};
+use db::{db_pool::Pool, functions::session::is_token_revoked_query};
 use jsonwebtoken::TokenData;
-use warp::{Filter, Rejection};
+use warp::{http::StatusCode, Filter, Rejection};
 
-use crate::error::convert_to_rejection;
+use crate::{
+    blocking::with_conn,
+    error::{convert_to_rejection, Error},
+};
 
-pub fn auth() -> impl Filter<Extract = (Option<UserClaims>,), Error = Rejection> + Clone + Copy {
+/// Reads the `jwt` cookie, rejecting expired and revoked tokens
+///
+/// the db is only queried when a cookie is sent
+pub fn auth(pool: Pool) -> impl Filter<Extract = (Option<UserClaims>,), Error = Rejection> + Clone {
     warp::cookie::optional::<String>("jwt")
+        .and(warp::any().map(move || pool.clone()))
         .and_then(check_header)
         .untuple_one()
 }
 
-async fn check_header(cookie: Option<String>) -> Result<(Option<UserClaims>,), Rejection> {
+async fn check_header(
+    cookie: Option<String>,
+    pool: Pool,
+) -> Result<(Option<UserClaims>,), Rejection> {
     if let Some(cookie_val) = cookie {
         // jwt cookie found!
         let token: TokenData<UserClaims> =
             validate_token(cookie_val).map_err(convert_to_rejection)?;
+        // tokens issued before jti was added can't be revoked, so they're refused
+        let revoked = token.claims.jti.is_empty() || {
+            let jti = token.claims.jti.clone();
+            with_conn(&pool, move |conn| {
+                is_token_revoked_query(conn, &jti).map_err(convert_to_rejection)
+            })
+            .await?
+        };
+        if revoked {
+            return Err(Error::user_error(
+                "Session was logged out, log in again",
+                StatusCode::UNAUTHORIZED,
+            )
+            .into());
+        }
         return Ok((Some(token.claims),));
     } else {
         return Ok((None,));
@@ -0,0 +1,169 @@
+//! Health endpoints for load balancers and orchestrators
+//!
+//! `live` only says the process is up, a db outage shouldn't get it restarted,
+//! `ready` checks everything a request needs and fails while any of it is missing
+use std::{collections::BTreeMap, time::Instant};
+
+use db::db_pool::{pending_migrations, ping_db, Pool};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::json;
+use warp::{http::StatusCode, Rejection, Reply};
+
+use crate::{
+    blocking::with_conn,
+    error::{convert_to_rejection, Error},
+    jwt::has_secret,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+/// Result of a single check
+pub struct Check {
+    pub status: CheckStatus,
+    /// how long the check took
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+impl Check {
+    /// Times `check`, it fails with an error message
+    pub async fn run<F>(check: F) -> Self
+    where
+        F: std::future::Future<Output = Result<(), String>>,
+    {
+        let started = Instant::now();
+        let result = check.await;
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+        match result {
+            Ok(()) => Self {
+                status: CheckStatus::Ok,
+                latency_ms,
+                error: None,
+            },
+            Err(error) => Self {
+                status: CheckStatus::Fail,
+                latency_ms,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+/// Health endpoint body, `status` is ok only if every check is
+pub struct HealthReport {
+    pub status: CheckStatus,
+    pub checks: BTreeMap<&'static str, Check>,
+}
+impl HealthReport {
+    pub fn new(checks: BTreeMap<&'static str, Check>) -> Self {
+        let status = if checks.values().all(|check| check.status == CheckStatus::Ok) {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Fail
+        };
+        Self { status, checks }
+    }
+
+    /// 200 when healthy, 503 so the instance is taken out of rotation otherwise
+    pub fn status_code(&self) -> StatusCode {
+        match self.status {
+            CheckStatus::Ok => StatusCode::OK,
+            CheckStatus::Fail => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn into_reply(self) -> impl Reply {
+        let status = self.status_code();
+        warp::reply::with_status(warp::reply::json(&self), status)
+    }
+}
+
+/// error message of a failed check
+fn check_error(err: Rejection) -> String {
+    match err.find::<Error>() {
+        Some(err) => err.to_string(),
+        None => format!("{:?}", err),
+    }
+}
+
+/// Liveness probe, answers as long as the server does
+pub async fn live() -> Result<impl Reply, Rejection> {
+    Ok(HealthReport::new(BTreeMap::new()).into_reply())
+}
+
+/// Readiness probe: db answers `SELECT 1`, no pending migrations and JWT_SECRET_KEY is set
+pub async fn ready(pool: Pool) -> Result<impl Reply, Rejection> {
+    let mut checks = BTreeMap::new();
+    checks.insert(
+        "database",
+        Check::run(async {
+            with_conn(&pool, |conn| ping_db(conn).map_err(convert_to_rejection))
+                .await
+                .map_err(check_error)
+        })
+        .await,
+    );
+    checks.insert(
+        "migrations",
+        Check::run(async {
+            let pending = with_conn(&pool, |conn| {
+                Ok(pending_migrations(conn)
+                    .map_err(|err| convert_to_rejection(Error::db_error(err.to_string())))?
+                    .iter()
+                    .map(|migration| migration.name().to_string())
+                    .collect::<Vec<String>>())
+            })
+            .await
+            .map_err(check_error)?;
+            match pending.is_empty() {
+                true => Ok(()),
+                false => Err(format!("pending migrations: {}", pending.join(", "))),
+            }
+        })
+        .await,
+    );
+    checks.insert(
+        "jwt_secret",
+        Check::run(async {
+            match has_secret() {
+                true => Ok(()),
+                false => Err("JWT_SECRET_KEY is not set".to_string()),
+            }
+        })
+        .await,
+    );
+    Ok(HealthReport::new(checks).into_reply())
+}
+
+/// Connection pool usage, 503 while every connection is in use
+///
+/// a saturated pool means requests are queueing for a connection, see `DB_POOL_MAX_SIZE`
+pub async fn pool_health(pool: Pool) -> Result<impl Reply, Rejection> {
+    let state = pool.state();
+    let max_size = pool.max_size();
+    let in_use = state.connections - state.idle_connections;
+    let saturated = in_use >= max_size;
+    let status = if saturated {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({
+            "connections": state.connections,
+            "idle": state.idle_connections,
+            "in_use": in_use,
+            "max_size": max_size,
+            "saturated": saturated,
+        })),
+        status,
+    ))
+}
@@ -1,71 +1,125 @@
 use crate::{
-    error::{convert_to_rejection, Error},
+    blocking::with_conn,
+    error::{convert_to_rejection, not_found_as, Error},
     jwt::UserClaims,
 };
 use db::{
-    db_pool::{DbConnection, PooledPgConnection},
+    conversion::validate_density,
+    db_pool::{Pool, PooledPgConnection},
     functions::{
         recipe::{query_full_recipe, query_recipe},
         recipe_ingredient::*,
     },
-    structs::{Ingredient, NewIngredient, Recipe, UpdateIngredient, UrlRecipeQuery},
+    quantity::BigDecimal,
+    structs::{Ingredient, NewIngredient, ReorderItems, Unit, UpdateIngredient, UrlRecipeQuery},
 };
 use serde_json::json;
 use warp::{http::StatusCode, Rejection, Reply};
 
-use super::validate_permission;
+use super::{is_reordering, validate_permission};
+
+/// Checks the quantity and density sent for an ingredient, as field errors
+pub fn validate_ingredient_values(
+    quantity_unit: Unit,
+    quantity: &BigDecimal,
+    density: Option<&BigDecimal>,
+) -> Result<(), Rejection> {
+    quantity_unit
+        .validate_quantity(quantity)
+        .map_err(|msg| convert_to_rejection(Error::field_error("ingredient_quantity", msg)))?;
+    if let Some(density) = density {
+        validate_density(density)
+            .map_err(|msg| convert_to_rejection(Error::field_error("ingredient_density", msg)))?;
+    }
+    Ok(())
+}
 
 /// Backend ingredient creator endpoint function
 pub async fn create_ingredient(
-    db_conn: DbConnection,
+    pool: Pool,
     ingredients: Vec<NewIngredient>,
     claims: Option<UserClaims>,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn = db_conn.map_err(convert_to_rejection)?;
-
-    let mut r = Recipe::default();
-    r.set_id(ingredients[0].recipe_id);
-    let recipe = query_full_recipe(
-        &mut conn,
-        &UrlRecipeQuery {
-            id: Some(r.id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
-
-    if validate_permission(recipe.recipe.user_id, claims) {
-        return Ok(warp::reply::json(&json!(create_ingredient_query(
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(&create_ingredients_record(
             conn,
-            &ingredients
-        )
-        .map_err(convert_to_rejection)?)));
+            ingredients,
+            claims,
+        )?))
+    })
+    .await
+}
+
+/// Adds `ingredients` to their recipe if `claims` can edit it, every ingredient must have the same recipe_id
+pub fn create_ingredients_record(
+    conn: &mut PooledPgConnection,
+    ingredients: Vec<NewIngredient>,
+    claims: Option<UserClaims>,
+) -> Result<Vec<Ingredient>, Rejection> {
+    let Some(first) = ingredients.first() else {
+        return Err(Error::payload_error("at least one ingredient must be supplied!").into());
+    };
+    if ingredients
+        .iter()
+        .any(|ingredient| ingredient.recipe_id != first.recipe_id)
+    {
+        return Err(Error::payload_error("ingredients must belong to the same recipe!").into());
+    }
+    // units themselves are checked when the payload is deserialized
+    for ingredient in &ingredients {
+        validate_ingredient_values(
+            ingredient.quantity_unit,
+            &ingredient.ingredient_quantity,
+            ingredient.ingredient_density.as_ref(),
+        )?;
+    }
+    let recipe = query_recipe(conn, UrlRecipeQuery::by_id(first.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+
+    if validate_permission(recipe.user_id, claims) {
+        return create_ingredient_query(conn, &ingredients).map_err(convert_to_rejection);
     }
-    return Err(Error::user_error("Cannot create ingredient!", StatusCode::FORBIDDEN).into());
+    Err(Error::user_error("Cannot create ingredient!", StatusCode::FORBIDDEN).into())
 }
 
 /// Backend ingredient updater endpoint function
 pub async fn update_ingredient(
-    db_connection: DbConnection,
+    pool: Pool,
     input_ingredient: UpdateIngredient,
     claims: Option<UserClaims>,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn = db_connection.map_err(convert_to_rejection)?;
-
-    let r = query_recipe(
-        &mut conn,
-        UrlRecipeQuery {
-            id: Some(input_ingredient.recipe_id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(&update_ingredient_record(
+            conn,
+            input_ingredient,
+            claims,
+        )?))
+    })
+    .await
+}
 
-    let old_ingredient =
-        get_ingredient_detail(&mut conn, input_ingredient.id).map_err(convert_to_rejection)?;
+/// Applies the fields sent in `input_ingredient`, the others keep their value
+///
+/// the ingredient must belong to `input_ingredient.recipe_id`, that's the recipe ownership is checked on
+pub fn update_ingredient_record(
+    conn: &mut PooledPgConnection,
+    input_ingredient: UpdateIngredient,
+    claims: Option<UserClaims>,
+) -> Result<Ingredient, Rejection> {
+    let r = query_recipe(conn, UrlRecipeQuery::by_id(input_ingredient.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+
+    let old_ingredient = get_ingredient_detail(conn, input_ingredient.id)
+        .map_err(not_found_as(Error::ingredient_not_found))?;
+    if old_ingredient.recipe_id != r.id {
+        return Err(Error::ingredient_not_found().into());
+    }
 
     if validate_permission(r.user_id, claims) {
-        let mut new_ingredient = Ingredient::default();
+        let mut new_ingredient = Ingredient {
+            id: old_ingredient.id,
+            ..Default::default()
+        };
         new_ingredient.set_recipe_id(old_ingredient.recipe_id);
 
         if let Some(ingredient_name) = input_ingredient.ingredient_name {
@@ -86,38 +140,101 @@ pub async fn update_ingredient(
             new_ingredient.quantity_unit = old_ingredient.quantity_unit;
         }
 
-        let update_query =
-            update_ingredient_query(conn, &new_ingredient).map_err(convert_to_rejection)?;
-        return Ok(warp::reply::json(&json!(update_query)));
+        if let Some(ingredient_density) = input_ingredient.ingredient_density {
+            new_ingredient.ingredient_density = Some(ingredient_density);
+        } else {
+            new_ingredient.ingredient_density = old_ingredient.ingredient_density;
+        }
+
+        // order is only changed by the reorder endpoint
+        new_ingredient.position = old_ingredient.position;
+
+        validate_ingredient_values(
+            new_ingredient.quantity_unit,
+            &new_ingredient.ingredient_quantity,
+            new_ingredient.ingredient_density.as_ref(),
+        )?;
+
+        return update_ingredient_query(conn, &new_ingredient).map_err(convert_to_rejection);
     }
-    return Err(Error::user_error("Cannot update ingredient!", StatusCode::FORBIDDEN).into());
+    Err(Error::user_error("Cannot update ingredient!", StatusCode::FORBIDDEN).into())
 }
 
 /// Backend ingredient delete endpoint function
 pub async fn delete_ingredient(
     ingredient: Ingredient,
     claims: Option<UserClaims>,
-    db_connection: DbConnection,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn: PooledPgConnection = db_connection.map_err(convert_to_rejection)?;
-
-    let recipe = query_full_recipe(
-        &mut conn,
-        &UrlRecipeQuery {
-            id: Some(ingredient.recipe_id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
-
-    if validate_permission(recipe.recipe.user_id, claims) {
-        if delete_ingredient_query(conn, &ingredient).map_err(convert_to_rejection)? == 0 {
-            return Err(Error::not_found("Ingredient not found").into());
+    with_conn(&pool, move |conn| {
+        delete_ingredient_record(conn, &ingredient, claims)?;
+        Ok(warp::reply::json(
+            &json!({"msg":format!("Ingredient {} deleted",ingredient.ingredient_name)}),
+        ))
+    })
+    .await
+}
+
+/// Deletes `ingredient` if `claims` can edit its recipe
+///
+/// the stored ingredient must belong to `ingredient.recipe_id`, that's the recipe ownership is checked on
+pub fn delete_ingredient_record(
+    conn: &mut PooledPgConnection,
+    ingredient: &Ingredient,
+    claims: Option<UserClaims>,
+) -> Result<(), Rejection> {
+    let stored_ingredient = get_ingredient_detail(conn, ingredient.id)
+        .map_err(not_found_as(Error::ingredient_not_found))?;
+    if stored_ingredient.recipe_id != ingredient.recipe_id {
+        return Err(Error::ingredient_not_found().into());
+    }
+    let recipe = query_recipe(conn, UrlRecipeQuery::by_id(ingredient.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+
+    if validate_permission(recipe.user_id, claims) {
+        if delete_ingredient_query(conn, ingredient).map_err(convert_to_rejection)? == 0 {
+            return Err(Error::ingredient_not_found().into());
         }
+        return Ok(());
+    }
+    Err(Error::user_error("Cannot delete ingredient!", StatusCode::FORBIDDEN).into())
+}
 
-        return Ok(warp::reply::json(
-            &json!({"msg":format!("Ingredient {} deleted",ingredient.ingredient_name)}),
-        ));
+/// Backend ingredient reorder endpoint function, `ids` must list every ingredient of the recipe
+pub async fn reorder_ingredients(
+    reorder: ReorderItems,
+    claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(&reorder_ingredients_record(
+            conn, &reorder, claims,
+        )?))
+    })
+    .await
+}
+
+/// Puts the ingredients of `reorder.recipe_id` in the order of `reorder.ids`
+pub fn reorder_ingredients_record(
+    conn: &mut PooledPgConnection,
+    reorder: &ReorderItems,
+    claims: Option<UserClaims>,
+) -> Result<Vec<Ingredient>, Rejection> {
+    let recipe = query_full_recipe(conn, &UrlRecipeQuery::by_id(reorder.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+    if !validate_permission(recipe.recipe.user_id, claims) {
+        return Err(Error::user_error("Cannot reorder ingredients!", StatusCode::FORBIDDEN).into());
     }
-    return Err(Error::user_error("Cannot delete ingredient!", StatusCode::FORBIDDEN).into());
+    let current: Vec<i32> = recipe
+        .ingredients
+        .iter()
+        .map(|ingredient| ingredient.id)
+        .collect();
+    if !is_reordering(&current, &reorder.ids) {
+        return Err(
+            Error::payload_error("ids must list every ingredient of the recipe once").into(),
+        );
+    }
+
+    reorder_ingredients_query(conn, reorder.recipe_id, &reorder.ids).map_err(convert_to_rejection)
 }
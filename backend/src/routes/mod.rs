@@ -1,35 +1,165 @@
 pub mod auth;
+pub mod health_route;
 pub mod ingredient_route;
 pub mod recipe_route;
 pub mod step_route;
+pub mod tag_route;
 pub mod user_route;
+pub mod v2;
 
 use self::{
-    ingredient_route::{create_ingredient, delete_ingredient, update_ingredient},
-    recipe_route::{delete_recipe, fuzzy_query_recipe, update_recipe, view_recipe},
-    step_route::{delete_step, update_step},
+    health_route::{live, pool_health, ready},
+    ingredient_route::{
+        create_ingredient, delete_ingredient, reorder_ingredients, update_ingredient,
+    },
+    recipe_route::{
+        create_full_recipe, delete_recipe, export_recipe, fuzzy_query_recipe, import_recipe,
+        search_recipe, update_recipe, view_recipe,
+    },
+    step_route::{delete_step, reorder_steps, update_step},
+    tag_route::{add_tags, list_tags, remove_tags, set_tags},
     user_route::{
-        create_user, delete_user, get_user_name, list_users, login_user_route,
-        update_user_info_route,
+        create_user, delete_user, get_password_policy, get_user_name, list_users, login_user_route,
+        logout_user, refresh_session, revoke_user_sessions, update_user_info_route,
     },
 };
-use crate::routes::auth::auth;
 use crate::{
     jwt::UserClaims,
+    openapi::openapi_spec,
+    rate_limit::{limit_login, BucketConfig, MemoryStore, RateLimiter},
     routes::{recipe_route::create_recipe, step_route::create_step},
 };
 use db::{
     db_pool::Pool,
-    structs::{Ingredient, UrlRecipeQuery, UrlUserQuery, UserRole},
+    structs::{
+        Ingredient, UrlExportQuery, UrlPageQuery, UrlRecipeQuery, UrlSearchQuery, UrlServingsQuery,
+        UrlTagQuery, UrlUserQuery, UserRole,
+    },
 };
 use recipe_route::check_edit_permission;
 use serde_json::json;
 use user_route::is_admin;
 use warp::{http::method::Method, path, Filter, Rejection, Reply};
 
+/// Max body size accepted by the recipe import endpoint (2 MiB)
+const IMPORT_BODY_LIMIT: u64 = 2 * 1024 * 1024;
+
+/// Every endpoint of `routing_table` as `(method, path)`, the paths are the ones of `openapi_spec`
+///
+/// `{name}` segments are integer path params. `test_route_count` checks `routing_table` combines as
+/// many filters, `test_route_table` that each entry is routed and `test_openapi_coverage` that it's documented.
+/// `/metrics` is served next to it, see `metrics::metrics_endpoint`
+pub const ROUTES: &[(&str, &str)] = &[
+    // recipes
+    ("post", "/api/create/recipe"),
+    ("post", "/api/create/full_recipe"),
+    ("post", "/api/import/recipe"),
+    ("get", "/api/delete/recipe"),
+    ("get", "/api/get/recipe"),
+    ("get", "/api/export/recipe"),
+    ("get", "/api/get/recipes"),
+    ("get", "/api/search/recipes"),
+    ("post", "/api/update/recipe"),
+    ("get", "/api/get/permission"),
+    // steps
+    ("post", "/api/create/step"),
+    ("post", "/api/delete/step"),
+    ("post", "/api/update/step"),
+    ("post", "/api/reorder/steps"),
+    // ingredients
+    ("post", "/api/create/ingredient"),
+    ("post", "/api/delete/ingredient"),
+    ("post", "/api/update/ingredient"),
+    ("post", "/api/reorder/ingredients"),
+    // tags
+    ("get", "/api/get/tags"),
+    ("post", "/api/create/tag"),
+    ("post", "/api/delete/tag"),
+    ("post", "/api/update/tags"),
+    // users
+    ("post", "/api/create/user"),
+    ("get", "/api/delete/user"),
+    ("get", "/api/get/username"),
+    ("post", "/api/login/user"),
+    ("post", "/api/refresh"),
+    ("post", "/api/logout"),
+    ("post", "/api/revoke/sessions"),
+    ("post", "/api/update/user"),
+    ("get", "/api/list/user"),
+    ("get", "/api/get/password_policy"),
+    ("get", "/api/get/admin"),
+    // v2
+    ("get", "/api/v2/recipes"),
+    ("post", "/api/v2/recipes"),
+    ("get", "/api/v2/recipes/{recipe_id}"),
+    ("patch", "/api/v2/recipes/{recipe_id}"),
+    ("delete", "/api/v2/recipes/{recipe_id}"),
+    ("get", "/api/v2/recipes/{recipe_id}/steps"),
+    ("post", "/api/v2/recipes/{recipe_id}/steps"),
+    ("put", "/api/v2/recipes/{recipe_id}/steps/order"),
+    ("get", "/api/v2/recipes/{recipe_id}/steps/{step_id}"),
+    ("patch", "/api/v2/recipes/{recipe_id}/steps/{step_id}"),
+    ("delete", "/api/v2/recipes/{recipe_id}/steps/{step_id}"),
+    ("get", "/api/v2/recipes/{recipe_id}/ingredients"),
+    ("post", "/api/v2/recipes/{recipe_id}/ingredients"),
+    ("put", "/api/v2/recipes/{recipe_id}/ingredients/order"),
+    (
+        "get",
+        "/api/v2/recipes/{recipe_id}/ingredients/{ingredient_id}",
+    ),
+    (
+        "patch",
+        "/api/v2/recipes/{recipe_id}/ingredients/{ingredient_id}",
+    ),
+    (
+        "delete",
+        "/api/v2/recipes/{recipe_id}/ingredients/{ingredient_id}",
+    ),
+    ("get", "/api/v2/tags"),
+    ("get", "/api/v2/recipes/{recipe_id}/tags"),
+    ("post", "/api/v2/recipes/{recipe_id}/tags"),
+    ("put", "/api/v2/recipes/{recipe_id}/tags"),
+    ("post", "/api/v2/users"),
+    ("get", "/api/v2/users"),
+    ("get", "/api/v2/users/{user_id}"),
+    ("patch", "/api/v2/users/{user_id}"),
+    ("delete", "/api/v2/users/{user_id}"),
+    // service
+    // any method is answered, documented as a GET
+    ("get", "/api/ping"),
+    ("get", "/api/health/live"),
+    ("get", "/api/health/ready"),
+    ("get", "/api/health/pool"),
+    ("get", "/api/openapi.json"),
+];
+
+/// `$first.or($rest)...` along with the number of filters combined, see `routing_table_with_count`
+macro_rules! any_of {
+    (@count $filter:ident) => {
+        1
+    };
+    ($first:ident $(, $rest:ident)* $(,)?) => {
+        ($first$(.or($rest))*, 1 $(+ any_of!(@count $rest))*)
+    };
+}
+
 pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    // this filter will be used to get a valid connection to the db pool
-    let pool_filter = warp::any().map(move || pool.get());
+    routing_table_with_count(pool).0
+}
+
+/// `routing_table` along with the number of endpoint filters it combines, which should be `ROUTES.len()`
+pub fn routing_table_with_count(
+    pool: Pool,
+) -> (
+    impl Filter<Extract = impl Reply, Error = Rejection> + Clone,
+    usize,
+) {
+    // auth() checks the token denylist, so it keeps its own handle to the pool
+    let auth_pool = pool.clone();
+    let auth = move || auth::auth(auth_pool.clone());
+    // handlers get a handle to the pool, connections are taken inside `blocking::with_conn`
+    // so waiting for one doesn't block the executor
+    let pool_filter = warp::any().map(move || pool.clone());
 
     // setting up CORS
     // these settings will be ALLOWED by the server so the client knows what the backend accept
@@ -40,10 +170,19 @@ pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Re
             "content-type",
         ])
         // allowing methods that will be used by/allowed to the client
-        .allow_methods(vec![Method::POST, Method::GET])
+        .allow_methods(vec![
+            Method::POST,
+            Method::GET,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
         // from my understanding, since this is a public API, I can allow any origin here
         .allow_any_origin();
 
+    // login attempts per address and user name
+    let login_limiter = RateLimiter::new(MemoryStore::new(BucketConfig::login_from_env()));
+
     /* list of API endpoints */
 
     // recipe endpoints
@@ -53,6 +192,20 @@ pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Re
         .and(auth())
         .and(pool_filter.clone())
         .and_then(create_recipe);
+    let create_full_recipe = warp::post()
+        .and(path!("api" / "create" / "full_recipe"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(create_full_recipe);
+    let import_recipe = warp::post()
+        .and(path!("api" / "import" / "recipe"))
+        // pasted pages can be large, but not unbounded
+        .and(warp::body::content_length_limit(IMPORT_BODY_LIMIT))
+        .and(warp::body::bytes())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(import_recipe);
     let delete_recipe = warp::get()
         .and(path!("api" / "delete" / "recipe"))
         .and(warp::query::<UrlRecipeQuery>())
@@ -62,13 +215,29 @@ pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Re
     let view_recipe = warp::get()
         .and(path!("api" / "get" / "recipe"))
         .and(warp::query::<UrlRecipeQuery>())
+        .and(warp::query::<UrlServingsQuery>())
         .and(pool_filter.clone())
         .and_then(view_recipe);
+    let export_recipe = warp::get()
+        .and(path!("api" / "export" / "recipe"))
+        .and(warp::query::<UrlRecipeQuery>())
+        .and(warp::query::<UrlExportQuery>())
+        .and(pool_filter.clone())
+        .and_then(export_recipe);
     let fuzzy_query = warp::get()
         .and(path!("api" / "get" / "recipes"))
         .and(warp::query::<UrlRecipeQuery>())
+        .and(warp::query::<UrlTagQuery>())
+        .and(warp::query::<UrlPageQuery>())
         .and(pool_filter.clone())
         .and_then(fuzzy_query_recipe);
+    let search_recipe = warp::get()
+        .and(path!("api" / "search" / "recipes"))
+        .and(warp::query::<UrlSearchQuery>())
+        .and(warp::query::<UrlTagQuery>())
+        .and(warp::query::<UrlPageQuery>())
+        .and(pool_filter.clone())
+        .and_then(search_recipe);
     let update_recipe = warp::post()
         .and(path!("api" / "update" / "recipe"))
         .and(warp::body::json())
@@ -101,6 +270,12 @@ pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Re
         .and(warp::body::json())
         .and(auth())
         .and_then(update_step);
+    let reorder_recipe_steps = warp::post()
+        .and(path!("api" / "reorder" / "steps"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(reorder_steps);
 
     //  ingredient endpoits
     let create_recipe_ingredient = warp::post()
@@ -121,6 +296,36 @@ pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Re
         .and(warp::body::json())
         .and(auth())
         .and_then(update_ingredient);
+    let reorder_recipe_ingredients = warp::post()
+        .and(path!("api" / "reorder" / "ingredients"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(reorder_ingredients);
+
+    // tag endpoints
+    let list_all_tags = warp::get()
+        .and(path!("api" / "get" / "tags"))
+        .and(pool_filter.clone())
+        .and_then(list_tags);
+    let add_recipe_tags = warp::post()
+        .and(path!("api" / "create" / "tag"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(add_tags);
+    let remove_recipe_tags = warp::post()
+        .and(path!("api" / "delete" / "tag"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(remove_tags);
+    let set_recipe_tags = warp::post()
+        .and(path!("api" / "update" / "tags"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(set_tags);
 
     // user endpoints
     let create_user = warp::post()
@@ -143,8 +348,26 @@ pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Re
     let login_user = warp::post()
         .and(path!("api" / "login" / "user"))
         .and(pool_filter.clone())
-        .and(warp::body::json())
+        // checked before any bcrypt work is done
+        .and(limit_login(login_limiter))
         .and_then(login_user_route);
+    let refresh_session = warp::post()
+        .and(path!("api" / "refresh"))
+        .and(pool_filter.clone())
+        .and(warp::cookie::optional::<String>("refresh_token"))
+        .and_then(refresh_session);
+    let logout_user = warp::post()
+        .and(path!("api" / "logout"))
+        .and(pool_filter.clone())
+        .and(warp::cookie::optional::<String>("jwt"))
+        .and(warp::cookie::optional::<String>("refresh_token"))
+        .and_then(logout_user);
+    let revoke_user_sessions = warp::post()
+        .and(path!("api" / "revoke" / "sessions"))
+        .and(warp::query::<UrlUserQuery>())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(revoke_user_sessions);
     let update_user = warp::post()
         .and(path!("api" / "update" / "user"))
         .and(pool_filter.clone())
@@ -153,42 +376,304 @@ pub fn routing_table(pool: Pool) -> impl Filter<Extract = impl Reply, Error = Re
         .and_then(update_user_info_route);
     let list_users = warp::get()
         .and(path!("api" / "list" / "user"))
+        .and(warp::query::<UrlPageQuery>())
         .and(pool_filter.clone())
         .and(auth())
         .and_then(list_users);
+    let password_policy = warp::get()
+        .and(path!("api" / "get" / "password_policy"))
+        .and_then(get_password_policy);
     let is_admin = warp::get()
         .and(path!("api" / "get" / "admin"))
         .and(auth())
         .and_then(is_admin);
 
     let ping_endpoint = warp::any().and(path!("api" / "ping")).and_then(ping);
+    let pool_health = warp::get()
+        .and(path!("api" / "health" / "pool"))
+        .and(pool_filter.clone())
+        .and_then(pool_health);
+    let health_live = warp::get()
+        .and(path!("api" / "health" / "live"))
+        .and_then(live);
+    let health_ready = warp::get()
+        .and(path!("api" / "health" / "ready"))
+        .and(pool_filter.clone())
+        .and_then(ready);
+    // built once, the document only changes with the code
+    let spec = openapi_spec();
+    let openapi = warp::get()
+        .and(path!("api" / "openapi.json"))
+        .map(move || warp::reply::json(&spec));
+
+    /* /api/v2 endpoints, resource paths and HTTP verbs */
+
+    // recipes
+    let v2_list_recipes = warp::get()
+        .and(path!("api" / "v2" / "recipes"))
+        .and(warp::query::<UrlRecipeQuery>())
+        .and(warp::query::<UrlSearchQuery>())
+        .and(warp::query::<UrlTagQuery>())
+        .and(warp::query::<UrlPageQuery>())
+        .and(pool_filter.clone())
+        .and_then(v2::list_recipes);
+    let v2_create_recipe = warp::post()
+        .and(path!("api" / "v2" / "recipes"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::create_recipe);
+    let v2_get_recipe = warp::get()
+        .and(path!("api" / "v2" / "recipes" / i32))
+        .and(warp::query::<UrlServingsQuery>())
+        .and(pool_filter.clone())
+        .and_then(v2::get_recipe);
+    let v2_update_recipe = warp::patch()
+        .and(path!("api" / "v2" / "recipes" / i32))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::update_recipe);
+    let v2_delete_recipe = warp::delete()
+        .and(path!("api" / "v2" / "recipes" / i32))
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::delete_recipe);
+
+    // steps
+    let v2_list_steps = warp::get()
+        .and(path!("api" / "v2" / "recipes" / i32 / "steps"))
+        .and(pool_filter.clone())
+        .and_then(v2::list_steps);
+    let v2_create_step = warp::post()
+        .and(path!("api" / "v2" / "recipes" / i32 / "steps"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::create_step);
+    let v2_reorder_steps = warp::put()
+        .and(path!("api" / "v2" / "recipes" / i32 / "steps" / "order"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::reorder_steps);
+    let v2_get_step = warp::get()
+        .and(path!("api" / "v2" / "recipes" / i32 / "steps" / i32))
+        .and(pool_filter.clone())
+        .and_then(v2::get_step);
+    let v2_update_step = warp::patch()
+        .and(path!("api" / "v2" / "recipes" / i32 / "steps" / i32))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::update_step);
+    let v2_delete_step = warp::delete()
+        .and(path!("api" / "v2" / "recipes" / i32 / "steps" / i32))
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::delete_step);
+
+    // ingredients
+    let v2_list_ingredients = warp::get()
+        .and(path!("api" / "v2" / "recipes" / i32 / "ingredients"))
+        .and(pool_filter.clone())
+        .and_then(v2::list_ingredients);
+    let v2_create_ingredient = warp::post()
+        .and(path!("api" / "v2" / "recipes" / i32 / "ingredients"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::create_ingredient);
+    let v2_reorder_ingredients = warp::put()
+        .and(path!(
+            "api" / "v2" / "recipes" / i32 / "ingredients" / "order"
+        ))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::reorder_ingredients);
+    let v2_get_ingredient = warp::get()
+        .and(path!("api" / "v2" / "recipes" / i32 / "ingredients" / i32))
+        .and(pool_filter.clone())
+        .and_then(v2::get_ingredient);
+    let v2_update_ingredient = warp::patch()
+        .and(path!("api" / "v2" / "recipes" / i32 / "ingredients" / i32))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::update_ingredient);
+    let v2_delete_ingredient = warp::delete()
+        .and(path!("api" / "v2" / "recipes" / i32 / "ingredients" / i32))
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::delete_ingredient);
+
+    // tags
+    let v2_list_tags = warp::get()
+        .and(path!("api" / "v2" / "tags"))
+        .and(pool_filter.clone())
+        .and_then(list_tags);
+    let v2_list_recipe_tags = warp::get()
+        .and(path!("api" / "v2" / "recipes" / i32 / "tags"))
+        .and(pool_filter.clone())
+        .and_then(v2::list_recipe_tags);
+    let v2_add_recipe_tags = warp::post()
+        .and(path!("api" / "v2" / "recipes" / i32 / "tags"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::add_recipe_tags);
+    let v2_set_recipe_tags = warp::put()
+        .and(path!("api" / "v2" / "recipes" / i32 / "tags"))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::set_recipe_tags);
+
+    // users
+    let v2_create_user = warp::post()
+        .and(path!("api" / "v2" / "users"))
+        .and(warp::body::json())
+        .and(pool_filter.clone())
+        .and_then(v2::create_user);
+    let v2_list_users = warp::get()
+        .and(path!("api" / "v2" / "users"))
+        .and(warp::query::<UrlPageQuery>())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::list_users);
+    let v2_get_user = warp::get()
+        .and(path!("api" / "v2" / "users" / i32))
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::get_user);
+    let v2_update_user = warp::patch()
+        .and(path!("api" / "v2" / "users" / i32))
+        .and(warp::body::json())
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::patch_user);
+    let v2_delete_user = warp::delete()
+        .and(path!("api" / "v2" / "users" / i32))
+        .and(auth())
+        .and(pool_filter.clone())
+        .and_then(v2::delete_user);
+
+    let (user_endpoints, user_count) = any_of!(
+        create_user,
+        get_user_info,
+        is_admin,
+        password_policy,
+        list_users,
+        delete_user,
+        login_user,
+        refresh_session,
+        logout_user,
+        revoke_user_sessions,
+        update_user
+    );
+    let (recipe_endpoints, recipe_count) = any_of!(
+        create_recipe,
+        create_full_recipe,
+        update_recipe,
+        delete_recipe,
+        view_recipe,
+        check_permission,
+        fuzzy_query,
+        search_recipe,
+        import_recipe,
+        export_recipe
+    );
+    let (recipe_step_endpoints, recipe_step_count) = any_of!(
+        create_recipe_step,
+        update_recipe_step,
+        delete_recipe_step,
+        reorder_recipe_steps
+    );
+    let (tag_endpoints, tag_count) = any_of!(
+        list_all_tags,
+        add_recipe_tags,
+        remove_recipe_tags,
+        set_recipe_tags
+    );
+    let (recipe_ingredient_endpoints, recipe_ingredient_count) = any_of!(
+        create_recipe_ingredient,
+        delete_recipe_ingredient,
+        update_recipe_ingredient,
+        reorder_recipe_ingredients
+    );
+
+    let (v2_recipe_endpoints, v2_recipe_count) = any_of!(
+        v2_list_recipes,
+        v2_create_recipe,
+        v2_get_recipe,
+        v2_update_recipe,
+        v2_delete_recipe
+    );
+    let (v2_step_endpoints, v2_step_count) = any_of!(
+        v2_list_steps,
+        v2_create_step,
+        v2_reorder_steps,
+        v2_get_step,
+        v2_update_step,
+        v2_delete_step
+    );
+    let (v2_ingredient_endpoints, v2_ingredient_count) = any_of!(
+        v2_list_ingredients,
+        v2_create_ingredient,
+        v2_reorder_ingredients,
+        v2_get_ingredient,
+        v2_update_ingredient,
+        v2_delete_ingredient
+    );
+    let (v2_tag_endpoints, v2_tag_count) = any_of!(
+        v2_list_tags,
+        v2_list_recipe_tags,
+        v2_add_recipe_tags,
+        v2_set_recipe_tags
+    );
+    let (v2_user_endpoints, v2_user_count) = any_of!(
+        v2_create_user,
+        v2_list_users,
+        v2_get_user,
+        v2_update_user,
+        v2_delete_user
+    );
+    let v2_endpoints = v2_recipe_endpoints
+        .or(v2_step_endpoints)
+        .or(v2_ingredient_endpoints)
+        .or(v2_tag_endpoints)
+        .or(v2_user_endpoints);
+
+    let (service_endpoints, service_count) = any_of!(
+        ping_endpoint,
+        openapi,
+        health_live,
+        health_ready,
+        pool_health
+    );
 
-    let user_endpoints = create_user
-        .or(get_user_info)
-        .or(is_admin)
-        .or(list_users)
-        .or(delete_user)
-        .or(login_user)
-        .or(update_user);
-    let recipe_endpoints = create_recipe
-        .or(update_recipe)
-        .or(delete_recipe)
-        .or(view_recipe)
-        .or(check_permission)
-        .or(fuzzy_query);
-    let recipe_step_endpoints = create_recipe_step
-        .or(update_recipe_step)
-        .or(delete_recipe_step);
-    let recipe_ingredient_endpoints = create_recipe_ingredient
-        .or(delete_recipe_ingredient)
-        .or(update_recipe_ingredient);
-
-    ping_endpoint
+    let route_count = service_count
+        + user_count
+        + recipe_count
+        + recipe_step_count
+        + recipe_ingredient_count
+        + tag_count
+        + v2_recipe_count
+        + v2_step_count
+        + v2_ingredient_count
+        + v2_tag_count
+        + v2_user_count;
+
+    let routes = service_endpoints
         .or(user_endpoints)
         .or(recipe_endpoints)
         .or(recipe_step_endpoints)
         .or(recipe_ingredient_endpoints)
-        .with(cors)
+        .or(tag_endpoints)
+        .or(v2_endpoints)
+        .with(cors);
+    (routes, route_count)
 }
 
 pub async fn ping() -> Result<impl Reply, Rejection> {
@@ -211,3 +696,12 @@ pub fn validate_permission(user_id: i32, claims: Option<UserClaims>) -> bool {
         return false;
     }
 }
+
+/// checks if `requested` has exactly the ids in `current`, each once, in any order
+pub fn is_reordering(current: &[i32], requested: &[i32]) -> bool {
+    let mut current = current.to_vec();
+    let mut requested = requested.to_vec();
+    current.sort_unstable();
+    requested.sort_unstable();
+    current == requested
+}
@@ -1,148 +1,326 @@
 use serde_json::json;
-use warp::{http::StatusCode, Rejection, Reply};
+use warp::{http::StatusCode, hyper::body::Bytes, Rejection, Reply};
 
 use crate::{
-    error::{convert_to_rejection, Error},
+    blocking::with_conn,
+    error::{convert_to_rejection, not_found_as, Error},
+    export::{content_type, file_name, render_recipe},
+    import::parse_recipe_source,
     jwt::UserClaims,
 };
 use db::{
-    db_pool::{DbConnection, PooledPgConnection},
+    db_pool::Pool,
     functions::recipe::{
-        create_recipe_query, delete_recipe_query, fuzzy_query, query_full_recipe, query_recipe,
-        update_recipe_query,
+        create_full_recipe_query, create_recipe_query, delete_recipe_query, fuzzy_query,
+        query_full_recipe, query_recipe, search_recipes, update_recipe_query,
+    },
+    scaling::scale_recipe,
+    structs::{
+        ExportFormat, NewFullRecipe, NewIngredient, NewRecipe, Page, Recipe, UpdateRecipe,
+        UrlExportQuery, UrlPageQuery, UrlRecipeQuery, UrlSearchQuery, UrlServingsQuery,
+        UrlTagQuery,
     },
-    structs::{NewRecipe, Recipe, UpdateRecipe, UrlRecipeQuery},
 };
 
-use super::validate_permission;
+use super::{ingredient_route::validate_ingredient_values, validate_permission};
 
 pub async fn create_recipe(
     mut recipe: NewRecipe,
     user_claims: Option<UserClaims>,
-    db_connection: DbConnection,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    if recipe.servings < 1 {
+        return Err(Error::field_error("servings", "servings must be at least 1!").into());
+    }
+    with_conn(&pool, move |conn| {
+        if let Some(claims) = user_claims.clone() {
+            recipe.set_user_id(claims.user_id)
+        }
+        if validate_permission(recipe.user_id, user_claims) {
+            return Ok(warp::reply::json(
+                // sending query to db
+                &create_recipe_query(conn, &recipe).map_err(convert_to_rejection)?,
+            ));
+        } else {
+            return Err(Error::user_error("User not logged in", StatusCode::UNAUTHORIZED).into());
+        }
+    })
+    .await
+}
+
+/// `validate_ingredient_values` over the ingredients of a recipe that's about to be created
+fn validate_new_ingredients(ingredients: &[NewIngredient]) -> Result<(), Rejection> {
+    for ingredient in ingredients {
+        validate_ingredient_values(
+            ingredient.quantity_unit,
+            &ingredient.ingredient_quantity,
+            ingredient.ingredient_density.as_ref(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Creates a recipe together with its ingredients and steps, either everything is saved or nothing is
+pub async fn create_full_recipe(
+    mut full_recipe: NewFullRecipe,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn: PooledPgConnection = db_connection.map_err(convert_to_rejection)?;
-    if let Some(claims) = user_claims.clone() {
-        recipe.set_user_id(claims.user_id)
+    let claims = match user_claims {
+        Some(claims) => claims,
+        None => {
+            return Err(Error::user_error("User not logged in", StatusCode::UNAUTHORIZED).into())
+        }
+    };
+    if full_recipe.recipe.recipe_name.trim().is_empty() {
+        return Err(Error::field_error("recipe_name", "recipe name must be supplied!").into());
     }
-    if validate_permission(recipe.user_id, user_claims) {
-        return Ok(warp::reply::json(
-            // sending query to db
-            &create_recipe_query(&mut conn, &recipe).map_err(convert_to_rejection)?,
-        ));
-    } else {
-        return Err(Error::user_error("User not logged in", StatusCode::UNAUTHORIZED).into());
+    if full_recipe.recipe.servings < 1 {
+        return Err(Error::field_error("servings", "servings must be at least 1!").into());
     }
+    validate_new_ingredients(&full_recipe.ingredients)?;
+    full_recipe.recipe.set_user_id(claims.user_id);
+
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(
+            &create_full_recipe_query(
+                conn,
+                &full_recipe.recipe,
+                &full_recipe.ingredients,
+                &full_recipe.steps,
+            )
+            .map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
+}
+
+/// Creates a full recipe from a pasted HTML page or JSON-LD `Recipe` object
+pub async fn import_recipe(
+    body: Bytes,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = match user_claims {
+        Some(claims) => claims,
+        None => {
+            return Err(Error::user_error("User not logged in", StatusCode::UNAUTHORIZED).into())
+        }
+    };
+    let source = std::str::from_utf8(&body).map_err(convert_to_rejection)?;
+    let mut imported = parse_recipe_source(source).map_err(convert_to_rejection)?;
+    validate_new_ingredients(&imported.ingredients)?;
+    imported.recipe.set_user_id(claims.user_id);
+
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(
+            &create_full_recipe_query(
+                conn,
+                &imported.recipe,
+                &imported.ingredients,
+                &imported.steps,
+            )
+            .map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
 }
 
 pub async fn delete_recipe(
     incoming_query: UrlRecipeQuery,
     user_claims: Option<UserClaims>,
-    db_connection: DbConnection,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
     if incoming_query.id.is_none() && incoming_query.name.is_none() {
         return Err(Error::payload_error("name or id must be supplied!").into());
     }
 
-    let mut conn: PooledPgConnection = db_connection.map_err(convert_to_rejection)?;
+    with_conn(&pool, move |conn| {
+        let recipe = query_full_recipe(conn, &incoming_query)
+            .map_err(not_found_as(Error::recipe_not_found))?;
 
-    let recipe = query_full_recipe(&mut conn, &incoming_query).map_err(convert_to_rejection)?;
+        if validate_permission(recipe.recipe.user_id, user_claims) {
+            if delete_recipe_query(conn, &incoming_query).map_err(convert_to_rejection)? == 0 {
+                return Err(Error::recipe_not_found().into());
+            }
+            return Ok(warp::reply::json(
+                &json!({"msg":format!("recipe {} deleted", recipe.recipe.recipe_name)
+                }),
+            ));
+        } else {
+            return Err(
+                Error::user_error("Recipe cannot be deleted", StatusCode::FORBIDDEN).into(),
+            );
+        }
+    })
+    .await
+}
 
-    if validate_permission(recipe.recipe.user_id, user_claims) {
-        if delete_recipe_query(&mut conn, &incoming_query).map_err(convert_to_rejection)? == 0 {
-            return Err(Error::not_found("Recipe not found").into());
+/// Sends the full recipe, with ingredients scaled when `servings` is supplied
+pub async fn view_recipe(
+    incoming_query: UrlRecipeQuery,
+    servings_query: UrlServingsQuery,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    if let Some(servings) = servings_query.servings {
+        if servings < 1 {
+            return Err(Error::field_error("servings", "servings must be at least 1!").into());
         }
-        return Ok(warp::reply::json(
-            &json!({"msg":format!("recipe {} deleted", recipe.recipe.recipe_name)
-            }),
-        ));
-    } else {
-        return Err(Error::user_error("Recipe cannot be deleted", StatusCode::FORBIDDEN).into());
     }
+    with_conn(&pool, move |conn| {
+        let full_recipe = query_full_recipe(conn, &incoming_query)
+            .map_err(not_found_as(Error::recipe_not_found))?;
+        return Ok(warp::reply::json(&match servings_query.servings {
+            Some(servings) => scale_recipe(&full_recipe, servings),
+            None => full_recipe,
+        }));
+    })
+    .await
 }
 
-pub async fn view_recipe(
+/// Sends the full recipe as a downloadable JSON-LD, Markdown or plain text file
+pub async fn export_recipe(
     incoming_query: UrlRecipeQuery,
-    db_connection: DbConnection,
+    export_query: UrlExportQuery,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn = db_connection.map_err(convert_to_rejection)?;
-    return Ok(warp::reply::json(
-        &query_full_recipe(&mut conn, &incoming_query).map_err(convert_to_rejection)?,
-    ));
+    if incoming_query.id.is_none() && incoming_query.name.is_none() {
+        return Err(Error::payload_error("name or id must be supplied!").into());
+    }
+    with_conn(&pool, move |conn| {
+        let full_recipe = query_full_recipe(conn, &incoming_query)
+            .map_err(not_found_as(Error::recipe_not_found))?;
+        let format = export_query.format.unwrap_or(ExportFormat::JsonLd);
+
+        Ok(warp::reply::with_header(
+            warp::reply::with_header(
+                render_recipe(&full_recipe, format),
+                "content-type",
+                content_type(format),
+            ),
+            "content-disposition",
+            format!(
+                "attachment; filename=\"{}\"",
+                file_name(&full_recipe, format)
+            ),
+        ))
+    })
+    .await
 }
 
 pub async fn fuzzy_query_recipe(
     incoming_query: UrlRecipeQuery,
-    db_connection: DbConnection,
+    tag_query: UrlTagQuery,
+    page: UrlPageQuery,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
     if incoming_query.name.is_none() {
         return Err(Error::payload_error("name must be supplied!").into());
     }
 
-    let mut conn = db_connection.map_err(convert_to_rejection)?;
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json::<Page<Recipe>>(
+            &fuzzy_query(
+                conn,
+                &incoming_query.name.unwrap(),
+                tag_query.tag.as_deref(),
+                &page,
+            )
+            .map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
+}
 
-    Ok(warp::reply::json::<Vec<Recipe>>(
-        &fuzzy_query(&mut conn, &incoming_query.name.unwrap()).map_err(convert_to_rejection)?,
-    ))
+/// Full-text recipe search, results are sorted by relevance unless `sort` is supplied
+pub async fn search_recipe(
+    incoming_query: UrlSearchQuery,
+    tag_query: UrlTagQuery,
+    page: UrlPageQuery,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    if incoming_query.query.is_none() {
+        return Err(Error::payload_error("query must be supplied!").into());
+    }
+
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json::<Page<Recipe>>(
+            &search_recipes(
+                conn,
+                &incoming_query.query.unwrap(),
+                tag_query.tag.as_deref(),
+                &page,
+            )
+            .map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
 }
 
 pub async fn update_recipe(
     incoming_recipe: UpdateRecipe,
     user_claims: Option<UserClaims>,
-    db_connection: DbConnection,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn: PooledPgConnection = db_connection.map_err(convert_to_rejection)?;
-
-    // querying recipe so we can validate ownership
-    let old_recipe = query_recipe(
-        &mut conn,
-        UrlRecipeQuery {
-            id: Some(incoming_recipe.id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
-
-    if validate_permission(old_recipe.user_id, user_claims) {
-        let mut updated_recipe = Recipe::default();
-        if let Some(recipe_name) = incoming_recipe.recipe_name {
-            updated_recipe.recipe_name = recipe_name;
+    with_conn(&pool, move |conn| {
+        // querying recipe so we can validate ownership
+        let old_recipe = query_recipe(conn, UrlRecipeQuery::by_id(incoming_recipe.id))
+            .map_err(not_found_as(Error::recipe_not_found))?;
+
+        if validate_permission(old_recipe.user_id, user_claims) {
+            let mut updated_recipe = Recipe::default();
+            updated_recipe.set_id(old_recipe.id);
+            updated_recipe.set_user_id(old_recipe.user_id);
+            if let Some(recipe_name) = incoming_recipe.recipe_name {
+                updated_recipe.recipe_name = recipe_name;
+            } else {
+                updated_recipe.recipe_name = old_recipe.recipe_name
+            }
+            updated_recipe.recipe_observations = incoming_recipe.recipe_observations;
+            match incoming_recipe.servings {
+                Some(servings) if servings < 1 => {
+                    return Err(
+                        Error::field_error("servings", "servings must be at least 1!").into(),
+                    )
+                }
+                Some(servings) => updated_recipe.servings = servings,
+                None => updated_recipe.servings = old_recipe.servings,
+            }
+
+            update_recipe_query(conn, &updated_recipe).map_err(convert_to_rejection)?;
+            return Ok(warp::reply::json(&json!({"msg":"recipe updated!"})));
         } else {
-            updated_recipe.recipe_name = old_recipe.recipe_name
+            return Err(Error::user_error("Cannot update recipe", StatusCode::UNAUTHORIZED).into());
         }
-        updated_recipe.recipe_observations = incoming_recipe.recipe_observations;
-
-        update_recipe_query(&mut conn, &updated_recipe).map_err(convert_to_rejection)?;
-        return Ok(warp::reply::json(&json!({"msg":"recipe updated!"})));
-    } else {
-        return Err(Error::user_error("Cannot update recipe", StatusCode::UNAUTHORIZED).into());
-    }
+    })
+    .await
 }
 
 pub async fn check_edit_permission(
     incoming_query: UrlRecipeQuery,
     user_claims: Option<UserClaims>,
-    db_connection: DbConnection,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
     if incoming_query.id.is_none() {
         return Err(Error::payload_error("Insert a recipe id!").into());
     }
-    let mut conn: PooledPgConnection = db_connection.map_err(convert_to_rejection)?;
-
-    // querying recipe so we can validate ownership
-    let recipe = query_full_recipe(
-        &mut conn,
-        &UrlRecipeQuery {
-            id: incoming_query.id,
-            name: None,
-        },
-    )
-    // returns error if no recipe is found
-    .map_err(convert_to_rejection)?;
-
-    if validate_permission(recipe.recipe.user_id, user_claims) {
-        return Ok(warp::reply::json(&json!({"msg":"user can edit recipe!"})));
-    } else {
-        return Err(Error::user_error("Cannot edit recipe", StatusCode::UNAUTHORIZED).into());
-    }
+    with_conn(&pool, move |conn| {
+        // querying recipe so we can validate ownership
+        let recipe = query_full_recipe(
+            conn,
+            &UrlRecipeQuery {
+                id: incoming_query.id,
+                ..Default::default()
+            },
+        )
+        // returns error if no recipe is found
+        .map_err(not_found_as(Error::recipe_not_found))?;
+
+        if validate_permission(recipe.recipe.user_id, user_claims) {
+            return Ok(warp::reply::json(&json!({"msg":"user can edit recipe!"})));
+        } else {
+            return Err(Error::user_error("Cannot edit recipe", StatusCode::UNAUTHORIZED).into());
+        }
+    })
+    .await
 }
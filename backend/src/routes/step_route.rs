@@ -2,64 +2,95 @@ use serde_json::json;
 use warp::{http::StatusCode, reject::Rejection, reply::Reply};
 
 use crate::{
-    error::{convert_to_rejection, Error},
+    blocking::with_conn,
+    error::{convert_to_rejection, not_found_as, Error},
     jwt::UserClaims,
 };
 use db::{
-    db_pool::{DbConnection, PooledPgConnection},
+    db_pool::{Pool, PooledPgConnection},
     functions::{
         recipe::{query_full_recipe, query_recipe},
         recipe_step::{update_step_query, *},
     },
-    structs::{NewStep, Recipe, Step, UpdateStep, UrlRecipeQuery},
+    structs::{NewStep, ReorderItems, Step, UpdateStep, UrlRecipeQuery},
 };
 
-use super::validate_permission;
+use super::{is_reordering, validate_permission};
 
 pub async fn create_step(
-    db_connection: DbConnection,
+    pool: Pool,
     recipe_steps: Vec<NewStep>,
     user_claims: Option<UserClaims>,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn = db_connection.map_err(convert_to_rejection)?;
-    let mut r = Recipe::default();
-    r.set_id(recipe_steps[0].recipe_id);
-    let recipe = query_full_recipe(
-        &mut conn,
-        &UrlRecipeQuery {
-            id: Some(r.id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
-
-    if validate_permission(recipe.recipe.user_id, user_claims) {
-        return Ok(warp::reply::json(&json!(create_step_query(
-            &mut conn,
-            &recipe_steps
-        )
-        .map_err(convert_to_rejection)?)));
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(&create_steps_record(
+            conn,
+            recipe_steps,
+            user_claims,
+        )?))
+    })
+    .await
+}
+
+/// Adds `recipe_steps` to their recipe if `user_claims` can edit it, every step must have the same recipe_id
+pub fn create_steps_record(
+    conn: &mut PooledPgConnection,
+    recipe_steps: Vec<NewStep>,
+    user_claims: Option<UserClaims>,
+) -> Result<Vec<Step>, Rejection> {
+    let Some(first) = recipe_steps.first() else {
+        return Err(Error::payload_error("at least one step must be supplied!").into());
+    };
+    if recipe_steps
+        .iter()
+        .any(|step| step.recipe_id != first.recipe_id)
+    {
+        return Err(Error::payload_error("steps must belong to the same recipe!").into());
     }
-    return Err(Error::user_error("Cannot create step!", StatusCode::FORBIDDEN).into());
+    let recipe = query_recipe(conn, UrlRecipeQuery::by_id(first.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+
+    if validate_permission(recipe.user_id, user_claims) {
+        return create_step_query(conn, &recipe_steps).map_err(convert_to_rejection);
+    }
+    Err(Error::user_error("Cannot create step!", StatusCode::FORBIDDEN).into())
 }
 
 pub async fn update_step(
-    db_connection: DbConnection,
+    pool: Pool,
     input_step: UpdateStep,
     user_claims: Option<UserClaims>,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn = db_connection.map_err(convert_to_rejection)?;
-    let r = query_recipe(
-        &mut conn,
-        UrlRecipeQuery {
-            id: Some(input_step.recipe_id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
-    let old_step = get_step_detail(&mut conn, input_step.id).map_err(convert_to_rejection)?;
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(&update_step_record(
+            conn,
+            input_step,
+            user_claims,
+        )?))
+    })
+    .await
+}
+
+/// Applies the fields sent in `input_step`, the others keep their value
+///
+/// the step must belong to `input_step.recipe_id`, that's the recipe ownership is checked on
+pub fn update_step_record(
+    conn: &mut PooledPgConnection,
+    input_step: UpdateStep,
+    user_claims: Option<UserClaims>,
+) -> Result<Step, Rejection> {
+    let r = query_recipe(conn, UrlRecipeQuery::by_id(input_step.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+    let old_step =
+        get_step_detail(conn, input_step.id).map_err(not_found_as(Error::step_not_found))?;
+    if old_step.recipe_id != r.id {
+        return Err(Error::step_not_found().into());
+    }
     if validate_permission(r.user_id, user_claims) {
-        let mut updated_step = Step::default();
+        let mut updated_step = Step {
+            id: old_step.id,
+            ..Default::default()
+        };
         updated_step.set_recipe_id(old_step.recipe_id);
 
         if let Some(step_name) = input_step.step_name {
@@ -80,35 +111,83 @@ pub async fn update_step(
             updated_step.step_duration_min = old_step.step_duration_min
         }
 
-        let update_query = update_step_query(conn, &updated_step).map_err(convert_to_rejection)?;
-        return Ok(warp::reply::json(&json!(update_query)));
+        // order is only changed by the reorder endpoint
+        updated_step.position = old_step.position;
+
+        return update_step_query(conn, &updated_step).map_err(convert_to_rejection);
     }
-    return Err(Error::user_error("Cannot update step!", StatusCode::FORBIDDEN).into());
+    Err(Error::user_error("Cannot update step!", StatusCode::FORBIDDEN).into())
 }
 
 pub async fn delete_step(
     incoming_query: Step,
     user_claims: Option<UserClaims>,
-    db_connection: DbConnection,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn: PooledPgConnection = db_connection.map_err(convert_to_rejection)?;
-
-    let recipe = query_full_recipe(
-        &mut conn,
-        &UrlRecipeQuery {
-            id: Some(incoming_query.recipe_id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
-    if validate_permission(recipe.recipe.user_id, user_claims) {
-        if delete_step_query(conn, &incoming_query).map_err(convert_to_rejection)? == 0 {
-            return Err(Error::not_found("Step not found").into());
+    with_conn(&pool, move |conn| {
+        delete_step_record(conn, &incoming_query, user_claims)?;
+        Ok(warp::reply::json(&json!({
+            "msg": format!("step {} deleted", incoming_query.step_name)
+        })))
+    })
+    .await
+}
+
+/// Deletes `step` if `user_claims` can edit its recipe
+///
+/// the stored step must belong to `step.recipe_id`, that's the recipe ownership is checked on
+pub fn delete_step_record(
+    conn: &mut PooledPgConnection,
+    step: &Step,
+    user_claims: Option<UserClaims>,
+) -> Result<(), Rejection> {
+    let stored_step =
+        get_step_detail(conn, step.id).map_err(not_found_as(Error::step_not_found))?;
+    if stored_step.recipe_id != step.recipe_id {
+        return Err(Error::step_not_found().into());
+    }
+    let recipe = query_recipe(conn, UrlRecipeQuery::by_id(step.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+    if validate_permission(recipe.user_id, user_claims) {
+        if delete_step_query(conn, step).map_err(convert_to_rejection)? == 0 {
+            return Err(Error::step_not_found().into());
         }
+        return Ok(());
+    }
+    Err(Error::user_error("Cannot delete recipe step!", StatusCode::FORBIDDEN).into())
+}
 
-        return Ok(warp::reply::json(&json!({
-            "msg": format!("step {} deleted", incoming_query.step_name)
-        })));
+/// Backend step reorder endpoint function, `ids` must list every step of the recipe
+pub async fn reorder_steps(
+    reorder: ReorderItems,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(&reorder_steps_record(
+            conn,
+            &reorder,
+            user_claims,
+        )?))
+    })
+    .await
+}
+
+/// Puts the steps of `reorder.recipe_id` in the order of `reorder.ids`
+pub fn reorder_steps_record(
+    conn: &mut PooledPgConnection,
+    reorder: &ReorderItems,
+    user_claims: Option<UserClaims>,
+) -> Result<Vec<Step>, Rejection> {
+    let recipe = query_full_recipe(conn, &UrlRecipeQuery::by_id(reorder.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+    if !validate_permission(recipe.recipe.user_id, user_claims) {
+        return Err(Error::user_error("Cannot reorder steps!", StatusCode::FORBIDDEN).into());
+    }
+    let current: Vec<i32> = recipe.steps.iter().map(|step| step.id).collect();
+    if !is_reordering(&current, &reorder.ids) {
+        return Err(Error::payload_error("ids must list every step of the recipe once").into());
     }
-    return Err(Error::user_error("Cannot delete recipe step!", StatusCode::FORBIDDEN).into());
+
+    reorder_steps_query(conn, reorder.recipe_id, &reorder.ids).map_err(convert_to_rejection)
 }
@@ -0,0 +1,97 @@
+use warp::{http::StatusCode, Rejection, Reply};
+
+use crate::{
+    blocking::with_conn,
+    error::{convert_to_rejection, not_found_as, Error},
+    jwt::UserClaims,
+};
+use db::{
+    db_pool::{DieselError, Pool, PooledPgConnection},
+    functions::{
+        recipe::query_recipe,
+        tag::{
+            add_recipe_tags_query, list_tags_query, normalize_tags, remove_recipe_tags_query,
+            set_recipe_tags_query,
+        },
+    },
+    structs::{RecipeTags, Tag, UrlRecipeQuery},
+};
+
+use super::validate_permission;
+
+/// db function applying the tag names to a recipe, see `change_tags_record`
+pub type TagChange = fn(&mut PooledPgConnection, i32, &[String]) -> Result<Vec<Tag>, DieselError>;
+
+/// Backend tag list endpoint function, only tags used by a recipe are sent
+pub async fn list_tags(pool: Pool) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(
+            &list_tags_query(conn).map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
+}
+
+/// Backend endpoint adding tags to a recipe
+pub async fn add_tags(
+    recipe_tags: RecipeTags,
+    claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    change_tags(recipe_tags, claims, pool, add_recipe_tags_query).await
+}
+
+/// Backend endpoint removing tags from a recipe
+pub async fn remove_tags(
+    recipe_tags: RecipeTags,
+    claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    change_tags(recipe_tags, claims, pool, remove_recipe_tags_query).await
+}
+
+/// Backend endpoint replacing every tag of a recipe
+pub async fn set_tags(
+    recipe_tags: RecipeTags,
+    claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    change_tags(recipe_tags, claims, pool, set_recipe_tags_query).await
+}
+
+async fn change_tags(
+    recipe_tags: RecipeTags,
+    claims: Option<UserClaims>,
+    pool: Pool,
+    change: TagChange,
+) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        Ok(warp::reply::json(&change_tags_record(
+            conn,
+            &recipe_tags,
+            claims,
+            change,
+        )?))
+    })
+    .await
+}
+
+/// Applies `change` to `recipe_tags.recipe_id` if `claims` can edit it, returns the tags it ends up with
+///
+/// names are normalized first, see `normalize_tags`
+pub fn change_tags_record(
+    conn: &mut PooledPgConnection,
+    recipe_tags: &RecipeTags,
+    claims: Option<UserClaims>,
+    change: TagChange,
+) -> Result<Vec<Tag>, Rejection> {
+    let tag_names = normalize_tags(&recipe_tags.tags)
+        .map_err(|msg| convert_to_rejection(Error::field_error("tags", msg)))?;
+    let recipe = query_recipe(conn, UrlRecipeQuery::by_id(recipe_tags.recipe_id))
+        .map_err(not_found_as(Error::recipe_not_found))?;
+
+    if validate_permission(recipe.user_id, claims) {
+        return change(conn, recipe.id, &tag_names).map_err(convert_to_rejection);
+    }
+    Err(Error::user_error("Cannot change recipe tags!", StatusCode::FORBIDDEN).into())
+}
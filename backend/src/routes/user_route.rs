@@ -1,162 +1,391 @@
 use crate::error::Error;
 use bcrypt::{hash, verify};
 
+use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
-use warp::{http::header::*, hyper::StatusCode, reject::Rejection, reply::Reply};
+use std::env;
+use warp::{
+    http::header::*,
+    hyper::StatusCode,
+    reject::Rejection,
+    reply::{Reply, Response},
+};
 
 use crate::{
-    error::convert_to_rejection,
+    blocking::{run_blocking, with_conn},
+    error::{convert_to_rejection, not_found_as},
     is_dev_server,
-    jwt::{generate_token, UserClaims},
+    jwt::{
+        access_token_ttl, generate_jti, generate_refresh_token, generate_token, hash_refresh_token,
+        refresh_token_ttl, validate_token, UserClaims,
+    },
 };
 use db::{
-    db_pool::{DbConnection, PooledPgConnection},
-    functions::user::{
-        create_user_record, delete_user_record, list_users_query, query_user_info,
-        update_user_record,
+    db_pool::{DieselError, Pool},
+    functions::{
+        login_attempt::{clear_failed_logins, login_locked_until, record_failed_login},
+        session::{
+            create_session_query, delete_session_query, revoke_token_query,
+            revoke_user_sessions_query, rotate_session_query,
+        },
+        user::{
+            create_user_record, delete_user_record, list_users_query, query_user_info,
+            update_user_record,
+        },
+    },
+    password::PasswordPolicy,
+    structs::{
+        NewUser, NewUserSession, RevokedToken, UpdateUser, UrlPageQuery, UrlUserQuery, User,
+        UserRole,
     },
-    structs::{NewUser, UpdateUser, UrlUserQuery, User, UserRole},
 };
 
-pub async fn create_user(db_conn: DbConnection, user: NewUser) -> Result<impl Reply, Rejection> {
-    let mut conn: PooledPgConnection = db_conn.map_err(convert_to_rejection)?;
-
-    match user.validate(&user.user_pwd) {
-        Ok(_) => {
-            let user = NewUser {
-                user_name: user.user_name,
-                user_role: user.user_role,
-                // encrypting password
-                user_pwd: encrypt_pwd(&user.user_pwd).await?,
-            };
-            // running query
-            create_user_record(&mut conn, &user).map_err(convert_to_rejection)?;
+pub async fn create_user(pool: Pool, user: NewUser) -> Result<impl Reply, Rejection> {
+    let user = register_user(pool, user).await?;
+    Ok(warp::reply::json(&json!({
+        "msg": format!("user {} created", user.user_name)
+    })))
+}
 
-            Ok(warp::reply::json(&json!({
-                "msg": format!("user {} created", user.user_name)
-            })))
-        }
-        Err(err) => {
-            return Err(Error::payload_error(format!(
-                "invalid password {}",
-                err.kind().to_string()
-            ))
-            .into())
-        }
-    }
+/// Creates `user` if the password follows the policy, the password is stored hashed
+pub async fn register_user(pool: Pool, user: NewUser) -> Result<User, Rejection> {
+    user.validate(&user.user_pwd, &password_policy())
+        .map_err(convert_to_rejection)?;
+    let user = NewUser {
+        user_name: user.user_name,
+        user_role: user.user_role,
+        // encrypting password
+        user_pwd: encrypt_pwd(&user.user_pwd).await?,
+    };
+    // running query
+    with_conn(&pool, move |conn| {
+        create_user_record(conn, &user).map_err(convert_to_rejection)
+    })
+    .await
 }
 
 pub async fn delete_user(
     user_query: UrlUserQuery,
     user_claims: Option<UserClaims>,
-    db_conn: DbConnection,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
-    let mut conn: PooledPgConnection = db_conn.map_err(convert_to_rejection)?;
-    if user_query.id.is_none() {
-        return Err(Error::payload_error("missing user ID field").into());
-    }
+    with_conn(&pool, move |conn| {
+        if user_query.id.is_none() {
+            return Err(Error::payload_error("missing user ID field").into());
+        }
 
-    // getting user info inserted in URL
-    let usr = query_user_info(&mut conn, &user_query).map_err(convert_to_rejection)?;
+        // getting user info inserted in URL
+        let usr =
+            query_user_info(conn, &user_query).map_err(not_found_as(Error::user_not_found))?;
 
-    // check if user can delete queried user (admins only) OR if user can delete themselves
-    if check_user_permission(&usr, &user_claims) {
-        // running query
-        if delete_user_record(&mut conn, &user_query).map_err(convert_to_rejection)? == 0 {
-            return Err(Error::not_found("User not found").into());
+        // check if user can delete queried user (admins only) OR if user can delete themselves
+        if check_user_permission(&usr, &user_claims) {
+            // running query
+            if delete_user_record(conn, &user_query).map_err(convert_to_rejection)? == 0 {
+                return Err(Error::user_not_found().into());
+            }
+            return Ok(warp::reply::json(&json!({
+                "msg": format!("user deleted")
+            })));
         }
-        return Ok(warp::reply::json(&json!({
-            "msg": format!("user deleted")
-        })));
-    }
-    return Err(Error::user_error("User cannot be deleted", StatusCode::FORBIDDEN).into());
+        return Err(Error::user_error("User cannot be deleted", StatusCode::FORBIDDEN).into());
+    })
+    .await
 }
 
 pub async fn get_user_name(
     user_id: UrlUserQuery,
     user_claims: Option<UserClaims>,
-    db_conn: DbConnection,
+    pool: Pool,
 ) -> Result<impl Reply, Rejection> {
     if user_id.id.is_none() {
         return Err(Error::payload_error("missing user ID field (.../id=<user_id>)").into());
     }
 
-    let mut conn: PooledPgConnection = db_conn.map_err(convert_to_rejection)?;
-
-    // running query
-    let query = query_user_info(&mut conn, &user_id).map_err(convert_to_rejection)?;
-    if check_user_permission(&query, &user_claims) {
-        return Ok(warp::reply::json(&json!({"msg": query.user_name})));
-    }
-    Err(Error::user_error("User cannot be viewed", StatusCode::FORBIDDEN).into())
+    with_conn(&pool, move |conn| {
+        // running query
+        let query = query_user_info(conn, &user_id).map_err(not_found_as(Error::user_not_found))?;
+        if check_user_permission(&query, &user_claims) {
+            return Ok(warp::reply::json(&json!({"msg": query.user_name})));
+        }
+        Err(Error::user_error("User cannot be viewed", StatusCode::FORBIDDEN).into())
+    })
+    .await
 }
 
-pub async fn login_user_route(db_conn: DbConnection, user: User) -> Result<impl Reply, Rejection> {
-    let mut conn: PooledPgConnection = db_conn.map_err(convert_to_rejection)?;
+pub async fn login_user_route(pool: Pool, user: User) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        let query = query_user_info(
+            conn,
+            &UrlUserQuery {
+                id: None,
+                name: Some(user.user_name),
+            },
+        )
+        .map_err(not_found_as(Error::incorrect_login))?;
+
+        if let Some(locked_until) =
+            login_locked_until(conn, query.id).map_err(convert_to_rejection)?
+        {
+            // refused before checking the password, guessing is pointless while locked
+            let wait = (locked_until - Utc::now()).to_std().unwrap_or_default();
+            return Err(Error::too_many_requests(wait).into());
+        }
 
-    let query = query_user_info(
-        &mut conn,
-        &UrlUserQuery {
-            id: None,
-            name: Some(user.user_name),
-        },
-    )
-    .map_err(convert_to_rejection)?;
-
-    if verify(&user.user_pwd, &query.user_pwd).map_err(convert_to_rejection)? {
-        let token = generate_token(query).map_err(convert_to_rejection)?;
-        let cookie: String;
-        if is_dev_server() {
-            // dev server uses http
-            cookie = format!(
-                // the below jwt works in dev server + HTTP (lack of Secure flag)
-                "jwt={}; Path=/; HttpOnly; Max-Age=1209600; SameSite=Strict",
-                token
-            );
+        if verify(&user.user_pwd, &query.user_pwd).map_err(convert_to_rejection)? {
+            clear_failed_logins(conn, query.id).map_err(convert_to_rejection)?;
+            let refresh_token = generate_refresh_token();
+            let jti = generate_jti();
+            create_session_query(conn, &new_session(query.id, &refresh_token, &jti))
+                .map_err(convert_to_rejection)?;
+            let token = generate_token(query, jti).map_err(convert_to_rejection)?;
+
+            let json_resp = warp::reply::json(&json!(
+                {
+                    "msg":format!("login success!",)
+                }
+            ));
+            Ok(with_session_cookies(json_resp, &token, &refresh_token))
         } else {
-            cookie = format!(
-                // the below jwt works in prod server + HTTPS (Secure flag)
-                "jwt={}; Path=/; HttpOnly; Max-Age=1209600; SameSite=Strict;Secure",
-                token
-            );
+            let (max_failed, lockout) = login_lockout_config();
+            record_failed_login(conn, query.id, max_failed, lockout)
+                .map_err(convert_to_rejection)?;
+            Err(Error::incorrect_login().into())
         }
-        let json_resp = warp::reply::json(&json!(
-            {
-                "msg":format!("login success!",)
+    })
+    .await
+}
+
+/// Failed logins before an account is locked and how long the first lockout lasts,
+/// from `LOGIN_MAX_FAILED` (default 5) and `LOGIN_LOCKOUT_SECONDS` (default 60)
+fn login_lockout_config() -> (i32, Duration) {
+    let var = |name: &str, default: i64| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(default)
+    };
+    (
+        var("LOGIN_MAX_FAILED", 5) as i32,
+        // too long for a Duration is still capped to an hour by record_failed_login
+        Duration::try_seconds(var("LOGIN_LOCKOUT_SECONDS", 60)).unwrap_or(Duration::MAX),
+    )
+}
+
+/// Backend refresh endpoint, swaps a valid `refresh_token` cookie for a new access token
+///
+/// the refresh token is rotated on every call, so a stolen one only works once
+pub async fn refresh_session(
+    pool: Pool,
+    refresh_cookie: Option<String>,
+) -> Result<impl Reply, Rejection> {
+    let Some(refresh_token) = refresh_cookie else {
+        return Err(Error::user_error("Not logged in", StatusCode::UNAUTHORIZED).into());
+    };
+    with_conn(&pool, move |conn| {
+        let new_refresh_token = generate_refresh_token();
+        let jti = generate_jti();
+        let session = match rotate_session_query(
+            conn,
+            &hash_refresh_token(&refresh_token),
+            // user_id is taken from the old session
+            &new_session(-1, &new_refresh_token, &jti),
+        ) {
+            Ok(session) => session,
+            Err(DieselError::NotFound) => {
+                return Err(Error::user_error(
+                    "Session expired, log in again",
+                    StatusCode::UNAUTHORIZED,
+                )
+                .into())
+            }
+            Err(err) => return Err(convert_to_rejection(err)),
+        };
+        let user = query_user_info(
+            conn,
+            &UrlUserQuery {
+                id: Some(session.user_id),
+                name: None,
+            },
+        )
+        .map_err(convert_to_rejection)?;
+        let token = generate_token(user, jti).map_err(convert_to_rejection)?;
+
+        let json_resp = warp::reply::json(&json!({"msg": "session refreshed"}));
+        Ok(with_session_cookies(json_resp, &token, &new_refresh_token))
+    })
+    .await
+}
+
+/// Backend logout endpoint, revokes the access token, drops the session and expires both cookies
+///
+/// works with an expired or missing token, so the user can always log out
+pub async fn logout_user(
+    pool: Pool,
+    jwt_cookie: Option<String>,
+    refresh_cookie: Option<String>,
+) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        // an expired or invalid token can't be used anyway, no need to revoke it
+        if let Some(Ok(token)) = jwt_cookie.map(validate_token::<UserClaims>) {
+            if !token.claims.jti.is_empty() {
+                let expires_at = DateTime::from_timestamp(token.claims.exp as i64, 0)
+                    .unwrap_or_else(|| Utc::now() + Duration::seconds(access_token_ttl() as i64));
+                revoke_token_query(
+                    conn,
+                    &RevokedToken {
+                        jti: token.claims.jti,
+                        expires_at,
+                    },
+                )
+                .map_err(convert_to_rejection)?;
             }
-        ));
-        Ok(warp::reply::with_header(json_resp, SET_COOKIE, cookie))
+        }
+        if let Some(refresh_token) = refresh_cookie {
+            delete_session_query(conn, &hash_refresh_token(&refresh_token))
+                .map_err(convert_to_rejection)?;
+        }
+
+        let json_resp = warp::reply::json(&json!({"msg": "logged out"}));
+        Ok(with_cookies(
+            json_resp,
+            [
+                expired_cookie("jwt", "/"),
+                expired_cookie("refresh_token", "/api"),
+            ],
+        ))
+    })
+    .await
+}
+
+/// Backend admin endpoint, logs user `id` out of every session
+pub async fn revoke_user_sessions(
+    user_query: UrlUserQuery,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    if !user_claims.is_some_and(|claims| claims.role == UserRole::Admin) {
+        return Err(Error::user_error("Cannot revoke sessions", StatusCode::FORBIDDEN).into());
+    }
+    let Some(user_id) = user_query.id else {
+        return Err(Error::payload_error("missing user ID field (.../id=<user_id>)").into());
+    };
+    with_conn(&pool, move |conn| {
+        // checking the user exists
+        let user =
+            query_user_info(conn, &user_query).map_err(not_found_as(Error::user_not_found))?;
+        let revoked = revoke_user_sessions_query(conn, user_id).map_err(convert_to_rejection)?;
+
+        Ok(warp::reply::json(&json!({
+            "msg": format!("{} session(s) of user {} logged out", revoked, user.user_name)
+        })))
+    })
+    .await
+}
+
+/// user_session row for `refresh_token`, valid for `refresh_token_ttl()`
+fn new_session(user_id: i32, refresh_token: &str, access_jti: &str) -> NewUserSession {
+    NewUserSession {
+        user_id,
+        token_hash: hash_refresh_token(refresh_token),
+        expires_at: Utc::now() + Duration::seconds(refresh_token_ttl() as i64),
+        access_jti: Some(access_jti.to_string()),
+    }
+}
+
+/// Builds a HttpOnly session cookie, `Secure` unless running the dev server (plain HTTP)
+fn session_cookie(name: &str, value: &str, path: &str) -> String {
+    // both cookies live as long as the refresh token, an expired jwt is still
+    // sent so auth() can tell the client to refresh instead of logging out
+    cookie_with_max_age(name, value, path, refresh_token_ttl())
+}
+
+/// Cookie that makes the browser drop `name` right away
+fn expired_cookie(name: &str, path: &str) -> String {
+    cookie_with_max_age(name, "", path, 0)
+}
+
+fn cookie_with_max_age(name: &str, value: &str, path: &str, max_age: u64) -> String {
+    let cookie = format!(
+        "{}={}; Path={}; HttpOnly; Max-Age={}; SameSite=Strict",
+        name, value, path, max_age
+    );
+    if is_dev_server() {
+        cookie
     } else {
-        Err(Error::user_error("Incorred User or Password!", StatusCode::UNAUTHORIZED).into())
+        cookie + ";Secure"
+    }
+}
+
+/// Adds the `jwt` and `refresh_token` cookies to `reply`
+fn with_session_cookies(reply: impl Reply, token: &str, refresh_token: &str) -> Response {
+    with_cookies(
+        reply,
+        [
+            session_cookie("jwt", token, "/"),
+            // only sent to the API
+            session_cookie("refresh_token", refresh_token, "/api"),
+        ],
+    )
+}
+
+fn with_cookies(reply: impl Reply, cookies: [String; 2]) -> Response {
+    let mut response = reply.into_response();
+    for cookie in cookies {
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(SET_COOKIE, value);
+        }
     }
+    response
 }
 
 pub async fn update_user_info_route(
-    db_conn: DbConnection,
+    pool: Pool,
     input_user: UpdateUser,
     user_claims: Option<UserClaims>,
 ) -> Result<impl Reply, Rejection> {
+    update_user(pool, input_user, user_claims).await?;
+    Ok(warp::reply::json(&json!({
+        "msg": format!("user updated")
+    })))
+}
+
+/// Applies the fields sent in `input_user`, returns the updated user
+///
+/// users can update themselves, admins anyone, only admins can change roles
+pub async fn update_user(
+    pool: Pool,
+    input_user: UpdateUser,
+    user_claims: Option<UserClaims>,
+) -> Result<User, Rejection> {
     if input_user.id < 0 {
         return Err(Error::user_error("invalid user id", StatusCode::UNAUTHORIZED).into());
     }
-    // pwd validation
+    let user_id = input_user.id;
+    let old_user = with_conn(&pool, move |conn| {
+        query_user_info(
+            conn,
+            &UrlUserQuery {
+                id: Some(user_id),
+                name: None,
+            },
+        )
+        .map_err(not_found_as(Error::user_not_found))
+    })
+    .await?;
+    // pwd validation, against the new user name if it's also changing
     if let Some(pwd) = &input_user.user_pwd {
-        match NewUser::default().validate(pwd) {
-            Err(err) => {
-                return Err(Error::from(err).into());
-            }
-            _ => {}
-        }
+        let user = NewUser {
+            user_name: input_user
+                .user_name
+                .clone()
+                .unwrap_or(old_user.user_name.clone()),
+            ..Default::default()
+        };
+        user.validate(pwd, &password_policy())
+            .map_err(convert_to_rejection)?;
     }
-    let mut conn: PooledPgConnection = db_conn.map_err(convert_to_rejection)?;
-    let old_user = query_user_info(
-        &mut conn,
-        &UrlUserQuery {
-            id: Some(input_user.id),
-            name: None,
-        },
-    )
-    .map_err(convert_to_rejection)?;
     if check_user_permission(&old_user, &user_claims) {
         let mut updated_user: User = Default::default();
         updated_user.set_id(old_user.id);
@@ -168,6 +397,8 @@ pub async fn update_user_info_route(
             // if None, set updated == old part
             updated_user.user_name = old_user.user_name.clone()
         }
+        // non admins keep their role
+        updated_user.user_role = old_user.user_role;
         if let Some(user_role) = input_user.user_role {
             if user_claims.unwrap().role == UserRole::Admin {
                 updated_user.user_role = user_role
@@ -180,17 +411,20 @@ pub async fn update_user_info_route(
         }
 
         // running query
-        update_user_record(&mut conn, &updated_user).map_err(convert_to_rejection)?;
+        let user = updated_user.clone();
+        with_conn(&pool, move |conn| {
+            update_user_record(conn, &updated_user).map_err(convert_to_rejection)
+        })
+        .await?;
 
-        return Ok(warp::reply::json(&json!({
-            "msg": format!("user updated")
-        })));
+        return Ok(user);
     }
     return Err(Error::user_error("User cannot be updated", StatusCode::FORBIDDEN).into());
 }
 
 pub async fn list_users(
-    db_conn: DbConnection,
+    page: UrlPageQuery,
+    pool: Pool,
     user_claims: Option<UserClaims>,
 ) -> Result<impl Reply, Rejection> {
     if user_claims.is_none()
@@ -202,11 +436,12 @@ pub async fn list_users(
         return Err(Error::user_error("Cannot see list of users", StatusCode::FORBIDDEN).into());
     }
 
-    let mut conn: PooledPgConnection = db_conn.map_err(convert_to_rejection)?;
-
-    let users = list_users_query(&mut conn).map_err(convert_to_rejection)?;
+    with_conn(&pool, move |conn| {
+        let users = list_users_query(conn, &page).map_err(convert_to_rejection)?;
 
-    return Ok(warp::reply::json(&json!(users)));
+        return Ok(warp::reply::json(&json!(users)));
+    })
+    .await
 }
 
 pub async fn is_admin(claims: Option<UserClaims>) -> Result<impl Reply, Rejection> {
@@ -220,8 +455,36 @@ pub async fn is_admin(claims: Option<UserClaims>) -> Result<impl Reply, Rejectio
     return Err(Error::user_error("No JWT found!", StatusCode::BAD_REQUEST).into());
 }
 
-async fn encrypt_pwd(pwd: &str) -> Result<String, Rejection> {
-    Ok(hash(pwd, 4).map_err(convert_to_rejection)?)
+/// Backend endpoint with the password rules, so forms can check passwords as they're typed
+pub async fn get_password_policy() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&password_policy()))
+}
+
+/// Password rules, from the `PASSWORD_*` env vars (see env.example) or the defaults
+pub fn password_policy() -> PasswordPolicy {
+    let default = PasswordPolicy::default();
+    let flag = |var: &str, default: bool| match env::var(var).as_deref() {
+        Ok("true") => true,
+        Ok("false") => false,
+        _ => default,
+    };
+    PasswordPolicy {
+        min_length: env::var("PASSWORD_MIN_LENGTH")
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(default.min_length),
+        require_lowercase: flag("PASSWORD_REQUIRE_LOWERCASE", default.require_lowercase),
+        require_uppercase: flag("PASSWORD_REQUIRE_UPPERCASE", default.require_uppercase),
+        require_digit: flag("PASSWORD_REQUIRE_DIGIT", default.require_digit),
+        require_symbol: flag("PASSWORD_REQUIRE_SYMBOL", default.require_symbol),
+        ..default
+    }
+}
+
+/// bcrypt hash of `pwd`, computed on the blocking thread pool
+pub async fn encrypt_pwd(pwd: &str) -> Result<String, Rejection> {
+    let pwd = pwd.to_string();
+    run_blocking(move || hash(pwd, 4).map_err(convert_to_rejection)).await
 }
 
 /// Function to check user permission
@@ -231,7 +494,7 @@ async fn encrypt_pwd(pwd: &str) -> Result<String, Rejection> {
 /// Users can edit themselves
 ///
 /// Returns true if user can edit
-fn check_user_permission(user: &User, claims: &Option<UserClaims>) -> bool {
+pub fn check_user_permission(user: &User, claims: &Option<UserClaims>) -> bool {
     if let Some(claim) = claims {
         // UserClaims found!
         if claim.role == UserRole::Admin || user.id.eq(&claim.user_id) {
@@ -0,0 +1,548 @@
+//! `/api/v2` handlers, resources live at their own path and are changed with the HTTP verbs
+//!
+//! creates answer 201 with a `Location` header, deletes 204, missing items 404, duplicates 409
+//! and requests without a login 401. The work is shared with the v1 handlers where possible
+use serde::Serialize;
+use warp::{
+    http::{header::LOCATION, StatusCode},
+    reply::{self, Response},
+    Rejection, Reply,
+};
+
+use crate::{
+    blocking::with_conn,
+    error::{convert_to_rejection, not_found_as, Error},
+    jwt::UserClaims,
+};
+use db::{
+    db_pool::Pool,
+    functions::{
+        recipe::{
+            create_recipe_query, delete_recipe_query, fuzzy_query, query_full_recipe, query_recipe,
+            search_recipes, update_recipe_query,
+        },
+        recipe_ingredient::get_ingredient_detail,
+        recipe_step::get_step_detail,
+        tag::{add_recipe_tags_query, recipe_tags_query, set_recipe_tags_query},
+        user::{delete_user_record, list_users_query, query_user_info},
+    },
+    structs::{
+        NewIngredient, NewRecipe, NewStep, NewUser, Page, PublicUser, RecipeTags, ReorderItems,
+        UpdateIngredient, UpdateRecipe, UpdateStep, UpdateUser, UrlPageQuery, UrlRecipeQuery,
+        UrlSearchQuery, UrlServingsQuery, UrlTagQuery, UrlUserQuery, UserRole,
+    },
+};
+
+use super::{
+    ingredient_route::{
+        create_ingredients_record, delete_ingredient_record, reorder_ingredients_record,
+        update_ingredient_record,
+    },
+    recipe_route::view_recipe,
+    step_route::{
+        create_steps_record, delete_step_record, reorder_steps_record, update_step_record,
+    },
+    tag_route::{change_tags_record, TagChange},
+    user_route::{check_user_permission, register_user, update_user},
+    validate_permission,
+};
+
+/// Claims of the logged in user, 401 without a login
+fn require_login(claims: Option<UserClaims>) -> Result<UserClaims, Rejection> {
+    claims.ok_or_else(|| Error::user_error("User not logged in", StatusCode::UNAUTHORIZED).into())
+}
+
+/// 201 with `body`, `location` is the path of the new item
+fn created<T: Serialize>(body: &T, location: String) -> Response {
+    reply::with_header(
+        reply::with_status(reply::json(body), StatusCode::CREATED),
+        LOCATION,
+        location,
+    )
+    .into_response()
+}
+
+/// 204, sent by deletes
+fn no_content() -> Response {
+    reply::with_status(reply::reply(), StatusCode::NO_CONTENT).into_response()
+}
+
+// recipes
+
+/// `GET /recipes`, full-text search with `query`, otherwise recipes whose name starts with `name`
+///
+/// both are narrowed to the recipes tagged `tag` if it's sent
+pub async fn list_recipes(
+    name_query: UrlRecipeQuery,
+    search_query: UrlSearchQuery,
+    tag_query: UrlTagQuery,
+    page: UrlPageQuery,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        let tag = tag_query.tag.as_deref();
+        let recipes: Page<_> = match search_query.query {
+            Some(query) => search_recipes(conn, &query, tag, &page),
+            None => fuzzy_query(conn, &name_query.name.unwrap_or_default(), tag, &page),
+        }
+        .map_err(convert_to_rejection)?;
+        Ok(reply::json(&recipes))
+    })
+    .await
+}
+
+/// `POST /recipes`, the recipe belongs to the logged in user
+pub async fn create_recipe(
+    mut recipe: NewRecipe,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    if recipe.recipe_name.trim().is_empty() {
+        return Err(Error::field_error("recipe_name", "recipe name must be supplied!").into());
+    }
+    if recipe.servings < 1 {
+        return Err(Error::field_error("servings", "servings must be at least 1!").into());
+    }
+    recipe.set_user_id(claims.user_id);
+    with_conn(&pool, move |conn| {
+        let recipe = create_recipe_query(conn, &recipe).map_err(convert_to_rejection)?;
+        Ok(created(&recipe, format!("/api/v2/recipes/{}", recipe.id)))
+    })
+    .await
+}
+
+/// `GET /recipes/{id}`, with ingredients and steps, scaled when `servings` is sent
+pub async fn get_recipe(
+    recipe_id: i32,
+    servings_query: UrlServingsQuery,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    view_recipe(UrlRecipeQuery::by_id(recipe_id), servings_query, pool).await
+}
+
+/// `PATCH /recipes/{id}`, fields that aren't sent keep their value
+pub async fn update_recipe(
+    recipe_id: i32,
+    incoming_recipe: UpdateRecipe,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    if incoming_recipe
+        .servings
+        .is_some_and(|servings| servings < 1)
+    {
+        return Err(Error::field_error("servings", "servings must be at least 1!").into());
+    }
+    with_conn(&pool, move |conn| {
+        let mut recipe = query_recipe(conn, UrlRecipeQuery::by_id(recipe_id))
+            .map_err(not_found_as(Error::recipe_not_found))?;
+        if !validate_permission(recipe.user_id, Some(claims)) {
+            return Err(Error::user_error("Cannot update recipe", StatusCode::FORBIDDEN).into());
+        }
+        if let Some(recipe_name) = incoming_recipe.recipe_name {
+            recipe.set_name(recipe_name);
+        }
+        if let Some(observations) = incoming_recipe.recipe_observations {
+            recipe.set_observation(Some(observations));
+        }
+        if let Some(servings) = incoming_recipe.servings {
+            recipe.servings = servings;
+        }
+        Ok(reply::json(
+            &update_recipe_query(conn, &recipe).map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
+}
+
+/// `DELETE /recipes/{id}`, its ingredients and steps go with it
+pub async fn delete_recipe(
+    recipe_id: i32,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    with_conn(&pool, move |conn| {
+        let recipe = query_recipe(conn, UrlRecipeQuery::by_id(recipe_id))
+            .map_err(not_found_as(Error::recipe_not_found))?;
+        if !validate_permission(recipe.user_id, Some(claims)) {
+            return Err(
+                Error::user_error("Recipe cannot be deleted", StatusCode::FORBIDDEN).into(),
+            );
+        }
+        delete_recipe_query(conn, &UrlRecipeQuery::by_id(recipe_id))
+            .map_err(convert_to_rejection)?;
+        Ok(no_content())
+    })
+    .await
+}
+
+// steps
+
+/// `GET /recipes/{id}/steps`, in order
+pub async fn list_steps(recipe_id: i32, pool: Pool) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        let recipe = query_full_recipe(conn, &UrlRecipeQuery::by_id(recipe_id))
+            .map_err(not_found_as(Error::recipe_not_found))?;
+        Ok(reply::json(&recipe.steps))
+    })
+    .await
+}
+
+/// `POST /recipes/{id}/steps`, the step goes last
+pub async fn create_step(
+    recipe_id: i32,
+    mut step: NewStep,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    step.recipe_id = recipe_id;
+    with_conn(&pool, move |conn| {
+        let step = create_steps_record(conn, vec![step], Some(claims))?.remove(0);
+        let location = format!("/api/v2/recipes/{}/steps/{}", recipe_id, step.id);
+        Ok(created(&step, location))
+    })
+    .await
+}
+
+/// `GET /recipes/{id}/steps/{step_id}`
+pub async fn get_step(recipe_id: i32, step_id: i32, pool: Pool) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        let step = get_step_detail(conn, step_id).map_err(not_found_as(Error::step_not_found))?;
+        if step.recipe_id != recipe_id {
+            return Err(Error::step_not_found().into());
+        }
+        Ok(reply::json(&step))
+    })
+    .await
+}
+
+/// `PATCH /recipes/{id}/steps/{step_id}`, fields that aren't sent keep their value
+pub async fn update_step(
+    recipe_id: i32,
+    step_id: i32,
+    mut step: UpdateStep,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    step.id = step_id;
+    step.recipe_id = recipe_id;
+    with_conn(&pool, move |conn| {
+        Ok(reply::json(&update_step_record(conn, step, Some(claims))?))
+    })
+    .await
+}
+
+/// `DELETE /recipes/{id}/steps/{step_id}`
+pub async fn delete_step(
+    recipe_id: i32,
+    step_id: i32,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    with_conn(&pool, move |conn| {
+        let mut step =
+            get_step_detail(conn, step_id).map_err(not_found_as(Error::step_not_found))?;
+        // a step of another recipe is missing from this one
+        step.recipe_id = recipe_id;
+        delete_step_record(conn, &step, Some(claims))?;
+        Ok(no_content())
+    })
+    .await
+}
+
+/// `PUT /recipes/{id}/steps/order`, `ids` must list every step of the recipe
+pub async fn reorder_steps(
+    recipe_id: i32,
+    mut reorder: ReorderItems,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    reorder.recipe_id = recipe_id;
+    with_conn(&pool, move |conn| {
+        Ok(reply::json(&reorder_steps_record(
+            conn,
+            &reorder,
+            Some(claims),
+        )?))
+    })
+    .await
+}
+
+// ingredients
+
+/// `GET /recipes/{id}/ingredients`, in order
+pub async fn list_ingredients(recipe_id: i32, pool: Pool) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        let recipe = query_full_recipe(conn, &UrlRecipeQuery::by_id(recipe_id))
+            .map_err(not_found_as(Error::recipe_not_found))?;
+        Ok(reply::json(&recipe.ingredients))
+    })
+    .await
+}
+
+/// `POST /recipes/{id}/ingredients`, the ingredient goes last
+pub async fn create_ingredient(
+    recipe_id: i32,
+    mut ingredient: NewIngredient,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    ingredient.recipe_id = recipe_id;
+    with_conn(&pool, move |conn| {
+        let ingredient = create_ingredients_record(conn, vec![ingredient], Some(claims))?.remove(0);
+        let location = format!(
+            "/api/v2/recipes/{}/ingredients/{}",
+            recipe_id, ingredient.id
+        );
+        Ok(created(&ingredient, location))
+    })
+    .await
+}
+
+/// `GET /recipes/{id}/ingredients/{ingredient_id}`
+pub async fn get_ingredient(
+    recipe_id: i32,
+    ingredient_id: i32,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        let ingredient = get_ingredient_detail(conn, ingredient_id)
+            .map_err(not_found_as(Error::ingredient_not_found))?;
+        if ingredient.recipe_id != recipe_id {
+            return Err(Error::ingredient_not_found().into());
+        }
+        Ok(reply::json(&ingredient))
+    })
+    .await
+}
+
+/// `PATCH /recipes/{id}/ingredients/{ingredient_id}`, fields that aren't sent keep their value
+pub async fn update_ingredient(
+    recipe_id: i32,
+    ingredient_id: i32,
+    mut ingredient: UpdateIngredient,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    ingredient.id = ingredient_id;
+    ingredient.recipe_id = recipe_id;
+    with_conn(&pool, move |conn| {
+        Ok(reply::json(&update_ingredient_record(
+            conn,
+            ingredient,
+            Some(claims),
+        )?))
+    })
+    .await
+}
+
+/// `DELETE /recipes/{id}/ingredients/{ingredient_id}`
+pub async fn delete_ingredient(
+    recipe_id: i32,
+    ingredient_id: i32,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    with_conn(&pool, move |conn| {
+        let mut ingredient = get_ingredient_detail(conn, ingredient_id)
+            .map_err(not_found_as(Error::ingredient_not_found))?;
+        // an ingredient of another recipe is missing from this one
+        ingredient.recipe_id = recipe_id;
+        delete_ingredient_record(conn, &ingredient, Some(claims))?;
+        Ok(no_content())
+    })
+    .await
+}
+
+/// `PUT /recipes/{id}/ingredients/order`, `ids` must list every ingredient of the recipe
+pub async fn reorder_ingredients(
+    recipe_id: i32,
+    mut reorder: ReorderItems,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    reorder.recipe_id = recipe_id;
+    with_conn(&pool, move |conn| {
+        Ok(reply::json(&reorder_ingredients_record(
+            conn,
+            &reorder,
+            Some(claims),
+        )?))
+    })
+    .await
+}
+
+// tags
+
+/// `GET /recipes/{id}/tags`, sorted by name
+pub async fn list_recipe_tags(recipe_id: i32, pool: Pool) -> Result<impl Reply, Rejection> {
+    with_conn(&pool, move |conn| {
+        let recipe = query_recipe(conn, UrlRecipeQuery::by_id(recipe_id))
+            .map_err(not_found_as(Error::recipe_not_found))?;
+        Ok(reply::json(
+            &recipe_tags_query(conn, recipe.id).map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
+}
+
+/// `POST /recipes/{id}/tags`, tags the recipe already has are kept
+pub async fn add_recipe_tags(
+    recipe_id: i32,
+    recipe_tags: RecipeTags,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    change_recipe_tags(
+        recipe_id,
+        recipe_tags,
+        user_claims,
+        pool,
+        add_recipe_tags_query,
+    )
+    .await
+}
+
+/// `PUT /recipes/{id}/tags`, replaces every tag of the recipe
+pub async fn set_recipe_tags(
+    recipe_id: i32,
+    recipe_tags: RecipeTags,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    change_recipe_tags(
+        recipe_id,
+        recipe_tags,
+        user_claims,
+        pool,
+        set_recipe_tags_query,
+    )
+    .await
+}
+
+async fn change_recipe_tags(
+    recipe_id: i32,
+    mut recipe_tags: RecipeTags,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+    change: TagChange,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    recipe_tags.recipe_id = recipe_id;
+    with_conn(&pool, move |conn| {
+        Ok(reply::json(&change_tags_record(
+            conn,
+            &recipe_tags,
+            Some(claims),
+            change,
+        )?))
+    })
+    .await
+}
+
+// users
+
+/// `POST /users`, registers a user
+pub async fn create_user(user: NewUser, pool: Pool) -> Result<impl Reply, Rejection> {
+    let user = register_user(pool, user).await?;
+    let location = format!("/api/v2/users/{}", user.id);
+    Ok(created(&PublicUser::from(user), location))
+}
+
+/// `GET /users`, admins only
+pub async fn list_users(
+    page: UrlPageQuery,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    if require_login(user_claims)?.role != UserRole::Admin {
+        return Err(Error::user_error("Cannot see list of users", StatusCode::FORBIDDEN).into());
+    }
+    with_conn(&pool, move |conn| {
+        Ok(reply::json(
+            &list_users_query(conn, &page).map_err(convert_to_rejection)?,
+        ))
+    })
+    .await
+}
+
+/// `GET /users/{id}`, admins or the user itself
+pub async fn get_user(
+    user_id: i32,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    with_conn(&pool, move |conn| {
+        let user = query_user_info(
+            conn,
+            &UrlUserQuery {
+                id: Some(user_id),
+                name: None,
+            },
+        )
+        .map_err(not_found_as(Error::user_not_found))?;
+        if !check_user_permission(&user, &Some(claims)) {
+            return Err(Error::user_error("User cannot be viewed", StatusCode::FORBIDDEN).into());
+        }
+        Ok(reply::json(&PublicUser::from(user)))
+    })
+    .await
+}
+
+/// `PATCH /users/{id}`, only admins can change roles
+pub async fn patch_user(
+    user_id: i32,
+    mut user: UpdateUser,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    user.id = user_id;
+    let user = update_user(pool, user, Some(claims)).await?;
+    Ok(reply::json(&PublicUser::from(user)))
+}
+
+/// `DELETE /users/{id}`, admins or the user itself, the user's recipes go with it
+pub async fn delete_user(
+    user_id: i32,
+    user_claims: Option<UserClaims>,
+    pool: Pool,
+) -> Result<impl Reply, Rejection> {
+    let claims = require_login(user_claims)?;
+    with_conn(&pool, move |conn| {
+        let user = query_user_info(
+            conn,
+            &UrlUserQuery {
+                id: Some(user_id),
+                name: None,
+            },
+        )
+        .map_err(not_found_as(Error::user_not_found))?;
+        if !check_user_permission(&user, &Some(claims)) {
+            return Err(Error::user_error("User cannot be deleted", StatusCode::FORBIDDEN).into());
+        }
+        // deleted by name, see delete_user_record
+        delete_user_record(
+            conn,
+            &UrlUserQuery {
+                id: None,
+                name: Some(user.user_name),
+            },
+        )
+        .map_err(convert_to_rejection)?;
+        Ok(no_content())
+    })
+    .await
+}
@@ -0,0 +1,11 @@
+/* refresh tokens, one row per logged in browser
+   only a SHA-256 hash of the token is stored, the token itself lives in the refresh_token cookie */
+CREATE TABLE IF NOT EXISTS user_session (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL,
+    token_hash TEXT UNIQUE NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT fk_user_session FOREIGN KEY (user_id) REFERENCES recipe_users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS user_session_user_idx ON user_session (user_id);
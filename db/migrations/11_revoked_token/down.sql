@@ -0,0 +1,2 @@
+ALTER TABLE user_session DROP COLUMN IF EXISTS access_jti;
+DROP TABLE IF EXISTS revoked_token;
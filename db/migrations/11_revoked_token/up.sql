@@ -0,0 +1,10 @@
+/* access tokens (jwt) revoked before they expire, by logging out or by an admin
+   checked on every authenticated request, rows are useless once expires_at has passed */
+CREATE TABLE IF NOT EXISTS revoked_token (
+    jti TEXT PRIMARY KEY,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+/* id (jti) of the access token last issued with a session, so every token of a user can be revoked */
+ALTER TABLE user_session ADD COLUMN IF NOT EXISTS access_jti TEXT;
@@ -0,0 +1,9 @@
+/* failed logins per user, the account is locked for a while after too many of them
+   the row is dropped on a successful login */
+CREATE TABLE IF NOT EXISTS login_attempt (
+    user_id INT PRIMARY KEY,
+    failed_count INT NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ,
+    CONSTRAINT fk_login_attempt FOREIGN KEY (user_id) REFERENCES recipe_users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,4 @@
+ALTER TABLE recipe DROP CONSTRAINT IF EXISTS recipe_user_slug_key;
+ALTER TABLE recipe DROP COLUMN slug;
+ALTER TABLE recipe DROP CONSTRAINT IF EXISTS recipe_user_name_key;
+ALTER TABLE recipe ADD CONSTRAINT recipe_recipe_name_key UNIQUE (recipe_name);
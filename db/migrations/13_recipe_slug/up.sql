@@ -0,0 +1,17 @@
+/* recipe names are unique per user instead of across every user
+   the slug is the URL-safe name used by the frontend (/recipe/<user>/<slug>), set once when the
+   recipe is created, see functions::recipe::slugify */
+ALTER TABLE recipe DROP CONSTRAINT IF EXISTS recipe_recipe_name_key;
+ALTER TABLE recipe ADD CONSTRAINT recipe_user_name_key UNIQUE (user_id, recipe_name);
+
+ALTER TABLE recipe ADD COLUMN slug VARCHAR(120);
+UPDATE recipe SET slug = COALESCE(
+    NULLIF(TRIM(BOTH '-' FROM LEFT(TRIM(BOTH '-' FROM REGEXP_REPLACE(LOWER(recipe_name), '[^a-z0-9]+', '-', 'g')), 100)), ''),
+    'recipe'
+);
+/* names that only differed in punctuation or case get the id appended */
+UPDATE recipe r SET slug = r.slug || '-' || r.id
+FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY user_id, slug ORDER BY id) AS n FROM recipe) dup
+WHERE r.id = dup.id AND dup.n > 1;
+ALTER TABLE recipe ALTER COLUMN slug SET NOT NULL;
+ALTER TABLE recipe ADD CONSTRAINT recipe_user_slug_key UNIQUE (user_id, slug);
@@ -0,0 +1,15 @@
+/* tags group recipes ("breakfast", "vegan"...), they are shared by every user
+   names are stored trimmed and lowercase, see functions::tag::normalize_tag */
+CREATE TABLE IF NOT EXISTS tag (
+    id SERIAL PRIMARY KEY,
+    tag_name VARCHAR(50) NOT NULL,
+    CONSTRAINT tag_name_key UNIQUE (tag_name)
+);
+CREATE TABLE IF NOT EXISTS recipe_tag (
+    recipe_id INT NOT NULL,
+    tag_id INT NOT NULL,
+    PRIMARY KEY (recipe_id, tag_id),
+    CONSTRAINT fk_recipe_tag_recipe FOREIGN KEY (recipe_id) REFERENCES recipe(id) ON DELETE CASCADE ON UPDATE CASCADE,
+    CONSTRAINT fk_recipe_tag_tag FOREIGN KEY (tag_id) REFERENCES tag(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS recipe_tag_tag_id ON recipe_tag (tag_id);
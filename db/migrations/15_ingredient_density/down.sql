@@ -0,0 +1 @@
+ALTER TABLE recipe_ingredient DROP COLUMN ingredient_density;
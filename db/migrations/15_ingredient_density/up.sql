@@ -0,0 +1,4 @@
+/* density of the ingredient in g/mL, used to convert between mass and volume units
+   NULL falls back to the densities known by name (db::conversion::known_density) */
+ALTER TABLE recipe_ingredient ADD COLUMN ingredient_density NUMERIC(6,3)
+    CONSTRAINT ingredient_density_positive CHECK (ingredient_density > 0);
@@ -4,11 +4,4 @@ CREATE TABLE IF NOT EXISTS recipe_users (
     user_pwd TEXT NOT NULL,
     user_role TEXT NOT NULL
 );
-/* creating default admin user */
-INSERT INTO recipe_users (id, user_name, user_pwd, user_role)
-VALUES (
-    0,
-    'admin',
-    '$2a$04$Plkgo7MNUzowxl8/r0tFMeQE2E8DDej6oKkuqXMr5EIUcZKTSGGIS',
-    'admin'
-) ON CONFLICT DO NOTHING;
\ No newline at end of file
+/* no admin is seeded here, the backend creates it on startup from ADMIN_USER/ADMIN_PASSWORD */
@@ -0,0 +1,8 @@
+DROP TRIGGER IF EXISTS recipe_search_step_trigger ON recipe_step;
+DROP TRIGGER IF EXISTS recipe_search_ingredient_trigger ON recipe_ingredient;
+DROP TRIGGER IF EXISTS recipe_search_recipe_trigger ON recipe;
+DROP FUNCTION IF EXISTS recipe_search_on_child();
+DROP FUNCTION IF EXISTS recipe_search_on_recipe();
+DROP FUNCTION IF EXISTS recipe_search_refresh(INT);
+DROP FUNCTION IF EXISTS recipe_search_document(INT);
+DROP TABLE IF EXISTS recipe_search;
\ No newline at end of file
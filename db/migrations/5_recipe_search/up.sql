@@ -0,0 +1,62 @@
+/* full-text search document for every recipe
+   weights: A -> recipe name, B -> ingredient names, C -> observations, D -> step instructions */
+CREATE TABLE IF NOT EXISTS recipe_search (
+    recipe_id INT PRIMARY KEY,
+    document TSVECTOR NOT NULL,
+    CONSTRAINT fk_recipe_search FOREIGN KEY (recipe_id) REFERENCES recipe(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS recipe_search_document_idx ON recipe_search USING GIN (document);
+
+CREATE OR REPLACE FUNCTION recipe_search_document(_recipe_id INT) RETURNS TSVECTOR AS $$
+    SELECT
+        setweight(to_tsvector('simple', r.recipe_name), 'A') ||
+        setweight(to_tsvector('simple', coalesce(
+            (SELECT string_agg(i.ingredient_name, ' ') FROM recipe_ingredient i WHERE i.recipe_id = r.id), ''
+        )), 'B') ||
+        setweight(to_tsvector('simple', coalesce(array_to_string(r.recipe_observations, ' '), '')), 'C') ||
+        setweight(to_tsvector('simple', coalesce(
+            (SELECT string_agg(s.step_instruction, ' ') FROM recipe_step s WHERE s.recipe_id = r.id), ''
+        )), 'D')
+    FROM recipe r
+    WHERE r.id = _recipe_id;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION recipe_search_refresh(_recipe_id INT) RETURNS VOID AS $$
+    INSERT INTO recipe_search (recipe_id, document)
+    SELECT r.id, recipe_search_document(r.id) FROM recipe r WHERE r.id = _recipe_id
+    ON CONFLICT (recipe_id) DO UPDATE SET document = EXCLUDED.document;
+$$ LANGUAGE sql;
+
+/* recipe row changed -> rebuild its own document */
+CREATE OR REPLACE FUNCTION recipe_search_on_recipe() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM recipe_search_refresh(NEW.id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+/* ingredient/step changed -> rebuild the parent recipe document */
+CREATE OR REPLACE FUNCTION recipe_search_on_child() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM recipe_search_refresh(OLD.recipe_id);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM recipe_search_refresh(NEW.recipe_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER recipe_search_recipe_trigger
+    AFTER INSERT OR UPDATE ON recipe
+    FOR EACH ROW EXECUTE PROCEDURE recipe_search_on_recipe();
+CREATE TRIGGER recipe_search_ingredient_trigger
+    AFTER INSERT OR UPDATE OR DELETE ON recipe_ingredient
+    FOR EACH ROW EXECUTE PROCEDURE recipe_search_on_child();
+CREATE TRIGGER recipe_search_step_trigger
+    AFTER INSERT OR UPDATE OR DELETE ON recipe_step
+    FOR EACH ROW EXECUTE PROCEDURE recipe_search_on_child();
+
+/* indexing recipes created before this migration */
+SELECT recipe_search_refresh(id) FROM recipe;
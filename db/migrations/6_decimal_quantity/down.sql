@@ -0,0 +1,2 @@
+ALTER TABLE recipe_ingredient
+    ALTER COLUMN ingredient_quantity TYPE INT USING ROUND(ingredient_quantity)::INT;
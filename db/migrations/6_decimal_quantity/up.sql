@@ -0,0 +1,3 @@
+-- allows fractional quantities ("1/2 cup", "1.5 kg"), 3 decimal places are enough for thirds
+ALTER TABLE recipe_ingredient
+    ALTER COLUMN ingredient_quantity TYPE NUMERIC(10, 3) USING ingredient_quantity::NUMERIC(10, 3);
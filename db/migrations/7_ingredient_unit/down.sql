@@ -0,0 +1,2 @@
+-- normalized units can't be restored, nothing to undo
+SELECT 1;
@@ -0,0 +1,22 @@
+-- units are now validated by the API (db::structs::Unit), rows saved before that
+-- are normalized so they can be read back; unknown units become "unit"
+UPDATE recipe_ingredient SET quantity_unit = CASE LOWER(TRIM(quantity_unit))
+    WHEN 'g' THEN 'gram'
+    WHEN 'grams' THEN 'gram'
+    WHEN 'kg' THEN 'kilogram'
+    WHEN 'oz' THEN 'ounce'
+    WHEN 'lb' THEN 'pound'
+    WHEN 'lbs' THEN 'pound'
+    WHEN 'ml' THEN 'milliliter'
+    WHEN 'l' THEN 'liter'
+    WHEN 'tsp' THEN 'teaspoon'
+    WHEN 'tbsp' THEN 'tablespoon'
+    WHEN 'cups' THEN 'cup'
+    ELSE LOWER(TRIM(quantity_unit))
+END;
+
+UPDATE recipe_ingredient SET quantity_unit = 'unit'
+WHERE quantity_unit NOT IN (
+    'gram', 'kilogram', 'ounce', 'pound', 'milliliter', 'liter', 'teaspoon',
+    'tablespoon', 'cup', 'unit', 'pinch', 'clove', 'to taste'
+);
@@ -0,0 +1,3 @@
+-- number of servings the ingredient quantities are written for
+ALTER TABLE recipe
+    ADD COLUMN servings INT NOT NULL DEFAULT 1 CHECK (servings > 0);
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS recipe_step_position ON recipe_step;
+DROP TRIGGER IF EXISTS recipe_ingredient_position ON recipe_ingredient;
+DROP FUNCTION IF EXISTS append_position();
+ALTER TABLE recipe_step DROP COLUMN position;
+ALTER TABLE recipe_ingredient DROP COLUMN position;
@@ -0,0 +1,32 @@
+/* explicit order of steps and ingredients inside a recipe, 0 is the first one
+   existing rows keep the order they were created in */
+ALTER TABLE recipe_step ADD COLUMN position INT;
+ALTER TABLE recipe_ingredient ADD COLUMN position INT;
+
+UPDATE recipe_step s SET position = ordered.position
+FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY recipe_id ORDER BY id) - 1 AS position FROM recipe_step) ordered
+WHERE s.id = ordered.id;
+UPDATE recipe_ingredient i SET position = ordered.position
+FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY recipe_id ORDER BY id) - 1 AS position FROM recipe_ingredient) ordered
+WHERE i.id = ordered.id;
+
+ALTER TABLE recipe_step ALTER COLUMN position SET NOT NULL;
+ALTER TABLE recipe_ingredient ALTER COLUMN position SET NOT NULL;
+
+/* rows inserted without a position go after the last one of their recipe
+   (rows inserted earlier by the same statement are visible here) */
+CREATE OR REPLACE FUNCTION append_position() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.position IS NULL THEN
+        EXECUTE format('SELECT COALESCE(MAX(position) + 1, 0) FROM %I WHERE recipe_id = $1', TG_TABLE_NAME)
+        INTO NEW.position
+        USING NEW.recipe_id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER recipe_step_position BEFORE INSERT ON recipe_step
+    FOR EACH ROW EXECUTE FUNCTION append_position();
+CREATE TRIGGER recipe_ingredient_position BEFORE INSERT ON recipe_ingredient
+    FOR EACH ROW EXECUTE FUNCTION append_position();
@@ -0,0 +1,167 @@
+//! Conversion of ingredient quantities between units and measuring systems
+//!
+//! mass converts to mass and volume to volume, going between the two needs the
+//! ingredient's density. Pure functions, used by the frontend's metric/imperial toggle
+use bigdecimal::num_bigint::Sign;
+use std::str::FromStr;
+
+use crate::{
+    quantity::{BigDecimal, Zero, QUANTITY_SCALE},
+    scaling::normalize_unit,
+    structs::{Ingredient, Unit, UnitKind, UnitSystem},
+};
+
+/// significant digits kept for converted quantities, "125.392 g" is shown as "125 g"
+const CONVERTED_PRECISION: u64 = 3;
+
+/// Approximate densities (g/mL) of common dry ingredients, matched by word
+///
+/// only a fallback for ingredients saved without a density, see `ingredient_density`.
+/// liquids are left out on purpose, they stay in volume units in both systems
+const DENSITIES: &[(&str, &str)] = &[
+    ("brown sugar", "0.9"),
+    ("powdered sugar", "0.51"),
+    ("icing sugar", "0.51"),
+    ("sugar", "0.85"),
+    ("flour", "0.53"),
+    ("butter", "0.96"),
+    ("rice", "0.78"),
+    ("oats", "0.38"),
+    ("cocoa", "0.36"),
+    ("honey", "1.42"),
+];
+
+/// Size of a mass unit in grams, or of a volume unit in milliliters
+///
+/// imperial volumes are the US customary ones
+fn base_size(unit: Unit) -> Option<BigDecimal> {
+    let size = match unit {
+        Unit::Gram | Unit::Milliliter => "1",
+        Unit::Kilogram | Unit::Liter => "1000",
+        Unit::Ounce => "28.349523125",
+        Unit::Pound => "453.59237",
+        Unit::Teaspoon => "4.92892159375",
+        Unit::Tablespoon => "14.78676478125",
+        Unit::Cup => "236.5882365",
+        Unit::Count | Unit::Pinch | Unit::Clove | Unit::ToTaste => return None,
+    };
+    BigDecimal::from_str(size).ok()
+}
+
+/// Smallest unit of `kind` in `system`, conversions land there before `normalize_unit`
+fn smallest_unit(system: UnitSystem, kind: UnitKind) -> Option<Unit> {
+    match (system, kind) {
+        (UnitSystem::Metric, UnitKind::Mass) => Some(Unit::Gram),
+        (UnitSystem::Metric, UnitKind::Volume) => Some(Unit::Milliliter),
+        (UnitSystem::Imperial, UnitKind::Mass) => Some(Unit::Ounce),
+        (UnitSystem::Imperial, UnitKind::Volume) => Some(Unit::Teaspoon),
+        _ => None,
+    }
+}
+
+/// Converts `quantity` from one unit to another, rounded to `QUANTITY_SCALE`
+///
+/// `density` (g/mL) is only used between mass and volume units,
+/// returns None when the units can't be converted
+pub fn convert(
+    quantity: &BigDecimal,
+    from: Unit,
+    to: Unit,
+    density: Option<&BigDecimal>,
+) -> Option<BigDecimal> {
+    if from == to {
+        return Some(quantity.clone());
+    }
+    let base = quantity * base_size(from)?;
+    let base = match (from.kind(), to.kind()) {
+        (from_kind, to_kind) if from_kind == to_kind => base,
+        (UnitKind::Volume, UnitKind::Mass) => base * density?,
+        (UnitKind::Mass, UnitKind::Volume) => {
+            base / density.filter(|density| !density.is_zero())?
+        }
+        _ => return None,
+    };
+    Some((base / base_size(to)?).round(QUANTITY_SCALE).normalized())
+}
+
+/// Approximate density (g/mL) of an ingredient, from its name
+///
+/// ex: "all-purpose flour" -> 0.53, "buttermilk" -> None
+pub fn known_density(ingredient_name: &str) -> Option<BigDecimal> {
+    let words: Vec<String> = ingredient_name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect();
+    // padded so "rice" doesn't match "licorice"
+    let name = format!(" {} ", words.join(" "));
+    DENSITIES
+        .iter()
+        .find(|(ingredient, _)| name.contains(&format!(" {} ", ingredient)))
+        .and_then(|(_, density)| BigDecimal::from_str(density).ok())
+}
+
+/// Densities must be below this, same as the `NUMERIC(6,3)` column
+pub const MAX_DENSITY: u32 = 1000;
+
+/// Checks a density (g/mL) sent for an ingredient
+pub fn validate_density(density: &BigDecimal) -> Result<(), String> {
+    if density.sign() != Sign::Plus {
+        return Err(String::from("density must be greater than zero"));
+    }
+    let max = BigDecimal::from(MAX_DENSITY);
+    if *density >= max {
+        return Err(format!("density must be less than {} g/mL", MAX_DENSITY));
+    }
+    Ok(())
+}
+
+/// Density (g/mL) used to convert `ingredient`: its own, or the one known from its name
+pub fn ingredient_density(ingredient: &Ingredient) -> Option<BigDecimal> {
+    ingredient
+        .ingredient_density
+        .clone()
+        .filter(|density| density.sign() == Sign::Plus)
+        .or_else(|| known_density(&ingredient.ingredient_name))
+}
+
+/// Rewrites an ingredient in `system`, in the most readable unit
+///
+/// ingredients with a density are weighed in metric and measured in
+/// cups/spoons in imperial. Counted and unmeasured ingredients, and ingredients
+/// already in `system`, are kept as they are
+pub fn convert_ingredient(ingredient: &Ingredient, system: UnitSystem) -> Ingredient {
+    let unit = ingredient.quantity_unit;
+    let density = ingredient_density(ingredient);
+    let target_kind = match (unit.kind(), system, &density) {
+        (UnitKind::Volume, UnitSystem::Metric, Some(_)) => UnitKind::Mass,
+        (UnitKind::Mass, UnitSystem::Imperial, Some(_)) => UnitKind::Volume,
+        (kind @ (UnitKind::Mass | UnitKind::Volume), _, _) => kind,
+        _ => return ingredient.clone(),
+    };
+    if unit.system() == Some(system) && unit.kind() == target_kind {
+        return ingredient.clone();
+    }
+
+    let converted = smallest_unit(system, target_kind).and_then(|target| {
+        let quantity = convert(
+            &ingredient.ingredient_quantity,
+            unit,
+            target,
+            density.as_ref(),
+        )?;
+        Some(normalize_unit(&quantity, target))
+    });
+    match converted {
+        Some((quantity, quantity_unit)) => Ingredient {
+            ingredient_quantity: quantity
+                .with_prec(CONVERTED_PRECISION)
+                .round(QUANTITY_SCALE)
+                .normalized(),
+            quantity_unit,
+            ..ingredient.clone()
+        },
+        None => ingredient.clone(),
+    }
+}
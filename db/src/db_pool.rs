@@ -1,21 +1,159 @@
+use std::env;
 use std::fmt::Display;
 use std::time::Duration;
 
+use diesel::migration::Migration;
 use diesel::r2d2::PooledConnection;
+use diesel::sql_types::BigInt;
+use diesel::RunQueryDsl;
 use diesel::{
-    pg::PgConnection,
+    pg::{Pg, PgConnection},
     r2d2::{ConnectionManager, Pool as R2D2Pool},
 };
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 
 pub type DieselError = diesel::result::Error;
 pub type R2D2Err = r2d2::Error;
 pub type Pool = R2D2Pool<ConnectionManager<PgConnection>>;
 pub type DbConnection = Result<PooledConnection<ConnectionManager<PgConnection>>, R2D2Err>;
 pub type PooledPgConnection = PooledConnection<ConnectionManager<PgConnection>>;
+pub type MigrationError = Box<dyn std::error::Error + Send + Sync>;
+
+/// db/migrations, built into the binary
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// key of the advisory lock taken by `run_pending_migrations`
+pub const MIGRATION_LOCK_KEY: i64 = 0x7265_6369_7065_7321;
+
+/// unique constraint on `recipe_users.user_name`, see migrations/1_users
+pub const USER_NAME_KEY: &str = "recipe_users_user_name_key";
+/// unique constraint on a user's recipe names, see migrations/13_recipe_slug
+pub const RECIPE_NAME_KEY: &str = "recipe_user_name_key";
+
+/// Connection pool settings, see `PoolConfig::from_env`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolConfig {
+    /// connections kept open at most
+    pub max_size: u32,
+    /// idle connections kept ready, None keeps up to `max_size`
+    pub min_idle: Option<u32>,
+    /// how long a request waits for a free connection before failing
+    pub connection_timeout: Duration,
+    /// idle connections are closed after this, None keeps them
+    pub idle_timeout: Option<Duration>,
+    /// connections are replaced after this, None keeps them
+    pub max_lifetime: Option<Duration>,
+}
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10,
+            min_idle: None,
+            connection_timeout: Duration::from_secs(1),
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+        }
+    }
+}
+impl PoolConfig {
+    /// Settings from the `DB_POOL_*` env vars, falling back to the defaults
+    ///
+    /// timeouts are in seconds, `0` disables the idle timeout and max lifetime,
+    /// `DB_POOL_MIN_IDLE` is clamped to the max size
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str| env::var(name).ok().and_then(|val| val.parse::<u64>().ok());
+        let optional_secs = |name: &str, default: Option<Duration>| match var(name) {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => default,
+        };
+        let max_size = var("DB_POOL_MAX_SIZE")
+            .filter(|size| *size > 0)
+            .map(|size| size as u32)
+            .unwrap_or(default.max_size);
+        Self {
+            max_size,
+            // r2d2 panics if more idle connections than max_size are asked for
+            min_idle: var("DB_POOL_MIN_IDLE")
+                .map(|idle| idle.min(max_size as u64) as u32)
+                .or(default.min_idle),
+            connection_timeout: var("DB_POOL_CONNECTION_TIMEOUT_SECONDS")
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(default.connection_timeout),
+            idle_timeout: optional_secs("DB_POOL_IDLE_TIMEOUT_SECONDS", default.idle_timeout),
+            max_lifetime: optional_secs("DB_POOL_MAX_LIFETIME_SECONDS", default.max_lifetime),
+        }
+    }
+}
 
 pub fn connect_to_db<S: Display>(url: S) -> Result<Pool, R2D2Err> {
+    connect_to_db_with(url, &PoolConfig::default())
+}
+
+pub fn connect_to_db_with<S: Display>(url: S, config: &PoolConfig) -> Result<Pool, R2D2Err> {
     let manager = ConnectionManager::<PgConnection>::new(url.to_string());
     Pool::builder()
-        .connection_timeout(Duration::from_secs(1))
+        .max_size(config.max_size)
+        .min_idle(config.min_idle)
+        .connection_timeout(config.connection_timeout)
+        .idle_timeout(config.idle_timeout)
+        .max_lifetime(config.max_lifetime)
         .build(manager)
 }
+
+/// Runs `SELECT 1`, fails if the db can't answer queries
+pub fn ping_db(conn: &mut PooledPgConnection) -> Result<(), DieselError> {
+    diesel::sql_query("SELECT 1").execute(conn)?;
+    Ok(())
+}
+
+/// Migrations that weren't applied to the db yet, in the order they must run
+///
+/// versions are plain numbers ("1_users", "10_user_session"), sorted as numbers here
+/// since diesel sorts them as text and would run 10 before 2
+pub fn pending_migrations(
+    conn: &mut PooledPgConnection,
+) -> Result<Vec<Box<dyn Migration<Pg>>>, MigrationError> {
+    let mut pending = conn.pending_migrations(MIGRATIONS)?;
+    pending.sort_by_key(|migration| {
+        migration
+            .name()
+            .version()
+            .to_string()
+            .parse::<u64>()
+            .unwrap_or(u64::MAX)
+    });
+    Ok(pending)
+}
+
+/// Applies every pending migration, each in its own transaction
+///
+/// holds the `MIGRATION_LOCK_KEY` advisory lock meanwhile, so instances starting together
+/// wait for each other and the later ones find nothing pending.
+/// returns the names of the applied migrations, empty if the schema was up to date
+pub fn run_pending_migrations(
+    conn: &mut PooledPgConnection,
+) -> Result<Vec<String>, MigrationError> {
+    diesel::sql_query("SELECT pg_advisory_lock($1)")
+        .bind::<BigInt, _>(MIGRATION_LOCK_KEY)
+        .execute(conn)?;
+    let applied = apply_pending_migrations(conn);
+    // the connection goes back to the pool, the lock must not stay with it
+    let unlocked = diesel::sql_query("SELECT pg_advisory_unlock($1)")
+        .bind::<BigInt, _>(MIGRATION_LOCK_KEY)
+        .execute(conn);
+    let applied = applied?;
+    unlocked?;
+    Ok(applied)
+}
+
+fn apply_pending_migrations(conn: &mut PooledPgConnection) -> Result<Vec<String>, MigrationError> {
+    let mut applied = vec![];
+    for migration in pending_migrations(conn)? {
+        conn.run_migration(migration.as_ref())?;
+        applied.push(migration.name().to_string());
+    }
+    Ok(applied)
+}
@@ -0,0 +1,69 @@
+use crate::{
+    db_pool::{DieselError, PooledPgConnection},
+    schema::login_attempt::dsl as attempt_dsl,
+    structs::LoginAttempt,
+};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{dsl::now, prelude::*, upsert::excluded};
+
+/// lockouts never get longer than this, however many logins failed
+const MAX_LOCKOUT: Duration = Duration::hours(1);
+
+/// Returns when the user's account unlocks, None if it isn't locked
+pub fn login_locked_until(
+    conn: &mut PooledPgConnection,
+    user_id: i32,
+) -> Result<Option<DateTime<Utc>>, DieselError> {
+    Ok(attempt_dsl::login_attempt
+        .select(attempt_dsl::locked_until)
+        .filter(attempt_dsl::user_id.eq(user_id))
+        .filter(attempt_dsl::locked_until.gt(now))
+        .first::<Option<DateTime<Utc>>>(conn)
+        .optional()?
+        .flatten())
+}
+
+/// Counts a failed login, locking the account once `max_failed` is reached
+///
+/// the lockout starts at `lockout` and doubles with every further failure, up to an hour
+pub fn record_failed_login(
+    conn: &mut PooledPgConnection,
+    user_id: i32,
+    max_failed: i32,
+    lockout: Duration,
+) -> Result<LoginAttempt, DieselError> {
+    conn.transaction(|conn| {
+        let attempt: LoginAttempt = diesel::insert_into(attempt_dsl::login_attempt)
+            .values((
+                attempt_dsl::user_id.eq(user_id),
+                attempt_dsl::failed_count.eq(1),
+            ))
+            .on_conflict(attempt_dsl::user_id)
+            .do_update()
+            .set((
+                attempt_dsl::failed_count
+                    .eq(attempt_dsl::failed_count + excluded(attempt_dsl::failed_count)),
+                attempt_dsl::last_failed_at.eq(now),
+            ))
+            .get_result(conn)?;
+        if attempt.failed_count < max_failed {
+            return Ok(attempt);
+        }
+        // 1x, 2x, 4x... the base lockout
+        let doublings = (attempt.failed_count - max_failed).min(16) as u32;
+        let locked_for = lockout
+            .checked_mul(2_i32.pow(doublings))
+            .map_or(MAX_LOCKOUT, |locked_for| locked_for.min(MAX_LOCKOUT));
+        diesel::update(attempt_dsl::login_attempt.find(user_id))
+            .set(attempt_dsl::locked_until.eq(Utc::now() + locked_for))
+            .get_result(conn)
+    })
+}
+
+/// Forgets the failed logins of a user, after they log in
+pub fn clear_failed_logins(
+    conn: &mut PooledPgConnection,
+    user_id: i32,
+) -> Result<usize, DieselError> {
+    diesel::delete(attempt_dsl::login_attempt.find(user_id)).execute(conn)
+}
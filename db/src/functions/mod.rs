@@ -1,4 +1,7 @@
+pub mod login_attempt;
 pub mod recipe;
 pub mod recipe_ingredient;
 pub mod recipe_step;
+pub mod session;
+pub mod tag;
 pub mod user;
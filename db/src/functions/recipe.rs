@@ -1,84 +1,188 @@
-use crate::structs::{NewRecipe, UrlRecipeQuery};
+use crate::structs::{
+    ListSort, NewIngredient, NewRecipe, NewStep, Page, UrlPageQuery, UrlRecipeQuery,
+};
 
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::DatabaseErrorKind;
 
 use crate::{
     db_pool::{DieselError, PooledPgConnection},
+    schema::recipe,
     structs::{FullRecipe, Recipe, Step},
 };
 
-use super::user::get_user_name;
-pub fn query_recipe(
+use super::{
+    tag::{normalize_tag, recipe_tags_query},
+    user::get_user_name,
+};
+
+/// URL-safe version of a recipe name: lowercase ASCII letters and digits joined by `-`
+///
+/// ex: "Grandma's Apple Pie!" -> "grandma-s-apple-pie", same rule the 13_recipe_slug migration
+/// used for existing recipes
+pub fn slugify(recipe_name: &str) -> String {
+    let slug: String = recipe_name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(100)
+        .collect();
+    match slug.trim_matches('-') {
+        "" => String::from("recipe"),
+        slug => slug.to_string(),
+    }
+}
+
+/// `slug`, or `slug-2`, `slug-3`... if the user already has a recipe with it
+fn unused_slug(
     conn: &mut PooledPgConnection,
-    incoming_recipe: UrlRecipeQuery,
-) -> Result<Recipe, DieselError> {
+    user_id: i32,
+    slug: String,
+) -> Result<String, DieselError> {
     use crate::schema::recipe::dsl as recipe_dsl;
-    if let Some(input_id) = &incoming_recipe.id {
-        Ok(recipe_dsl::recipe
-            .select((
-                recipe_dsl::id,
-                recipe_dsl::user_id,
-                recipe_dsl::recipe_name,
-                recipe_dsl::recipe_observations,
-            ))
-            .filter(recipe_dsl::id.eq(input_id))
-            .first::<Recipe>(conn)?)
-    } else if let Some(input_name) = &incoming_recipe.name {
-        Ok(recipe_dsl::recipe
-            .select((
-                recipe_dsl::id,
-                recipe_dsl::user_id,
-                recipe_dsl::recipe_name,
-                recipe_dsl::recipe_observations,
-            ))
-            .filter(recipe_dsl::recipe_name.eq(input_name))
-            .first::<Recipe>(conn)?)
-    } else {
-        Err(DieselError::DatabaseError(
+    let taken: Vec<String> = recipe_dsl::recipe
+        .filter(recipe_dsl::user_id.eq(user_id))
+        .filter(
+            recipe_dsl::slug
+                .eq(&slug)
+                .or(recipe_dsl::slug.like(format!("{}-%", slug))),
+        )
+        .select(recipe_dsl::slug)
+        .load(conn)?;
+    if !taken.contains(&slug) {
+        return Ok(slug);
+    }
+    Ok((2..)
+        .map(|n| format!("{}-{}", slug, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded range"))
+}
+
+/// Recipes matching `incoming_query`: by id, otherwise by slug or name of `user`
+///
+/// names and slugs are only unique per user, so they're refused without `user`
+fn filter_recipes(
+    incoming_query: &UrlRecipeQuery,
+) -> Result<recipe::BoxedQuery<'_, Pg>, DieselError> {
+    use crate::schema::recipe::dsl as recipe_dsl;
+    use crate::schema::recipe_users::dsl as user_dsl;
+
+    let missing = |msg: &str| {
+        DieselError::DatabaseError(
             DatabaseErrorKind::NotNullViolation,
-            Box::new(String::from("Name or ID must be supplied")),
-        ))
+            Box::new(String::from(msg)),
+        )
+    };
+    let query = recipe_dsl::recipe.order(recipe_dsl::id).into_boxed();
+    if let Some(input_id) = incoming_query.id {
+        return Ok(query.filter(recipe_dsl::id.eq(input_id)));
     }
+    let query = match (&incoming_query.slug, &incoming_query.name) {
+        (Some(slug), _) => query.filter(recipe_dsl::slug.eq(slug)),
+        (None, Some(name)) => query.filter(recipe_dsl::recipe_name.eq(name)),
+        (None, None) => return Err(missing("Name, slug or ID must be supplied")),
+    };
+    let Some(user_name) = &incoming_query.user else {
+        return Err(missing("user must be supplied with a name or slug"));
+    };
+    Ok(query.filter(
+        recipe_dsl::user_id.eq_any(
+            user_dsl::recipe_users
+                .filter(user_dsl::user_name.eq(user_name))
+                .select(user_dsl::id),
+        ),
+    ))
 }
 
+pub fn query_recipe(
+    conn: &mut PooledPgConnection,
+    incoming_recipe: UrlRecipeQuery,
+) -> Result<Recipe, DieselError> {
+    filter_recipes(&incoming_recipe)?.first::<Recipe>(conn)
+}
+
+/// Inserts the recipe with an unused slug, returns the created row
 pub fn create_recipe_query(
     conn: &mut PooledPgConnection,
     incoming_recipe: &NewRecipe,
 ) -> Result<Recipe, DieselError> {
     use crate::schema::recipe::dsl as recipe_dsl;
-    diesel::insert_into(recipe_dsl::recipe)
-        .values(incoming_recipe.clone())
-        .execute(conn)?;
-    Ok(query_recipe(
+    let slug = unused_slug(
         conn,
-        UrlRecipeQuery {
-            id: None,
-            name: Some(incoming_recipe.recipe_name.clone()),
-        },
-    )?)
+        incoming_recipe.user_id,
+        slugify(&incoming_recipe.recipe_name),
+    )?;
+    diesel::insert_into(recipe_dsl::recipe)
+        .values((incoming_recipe, recipe_dsl::slug.eq(slug)))
+        .returning(Recipe::as_returning())
+        .get_result(conn)
 }
 
-/// Deletes Recipe record based on id
+/// Creates a recipe with all of its ingredients and steps in a single transaction
+///
+/// `recipe_id` from ingredients/steps is ignored, the new recipe id is used instead.
+/// If any insert fails, nothing is persisted
+pub fn create_full_recipe_query(
+    conn: &mut PooledPgConnection,
+    incoming_recipe: &NewRecipe,
+    ingredients: &[NewIngredient],
+    steps: &[NewStep],
+) -> Result<FullRecipe, DieselError> {
+    use crate::schema::recipe_ingredient::dsl as ingredient_dsl;
+    use crate::schema::recipe_step::dsl as step_dsl;
+
+    conn.transaction(|conn| {
+        let recipe = create_recipe_query(conn, incoming_recipe)?;
+
+        let ingredients: Vec<NewIngredient> = ingredients
+            .iter()
+            .map(|ingredient| NewIngredient {
+                recipe_id: recipe.id,
+                ..ingredient.clone()
+            })
+            .collect();
+        let steps: Vec<NewStep> = steps
+            .iter()
+            .map(|step| NewStep {
+                recipe_id: recipe.id,
+                ..step.clone()
+            })
+            .collect();
+
+        let mut full_recipe = FullRecipe::default();
+        full_recipe.set_owner_name(get_user_name(conn, recipe.user_id)?);
+        full_recipe.set_ingredients(
+            diesel::insert_into(ingredient_dsl::recipe_ingredient)
+                .values(&ingredients)
+                .get_results(conn)?,
+        );
+        full_recipe.set_steps(
+            diesel::insert_into(step_dsl::recipe_step)
+                .values(&steps)
+                .get_results(conn)?,
+        );
+        full_recipe.set_recipe(recipe);
+        Ok(full_recipe)
+    })
+}
+
+/// Deletes the Recipe record `incoming_recipe` points to, see `UrlRecipeQuery`
+///
+/// returns the number of deleted rows
 pub fn delete_recipe_query(
     conn: &mut PooledPgConnection,
     incoming_recipe: &UrlRecipeQuery,
 ) -> Result<usize, DieselError> {
     use crate::schema::recipe::dsl as recipe_dsl;
-    if let Some(input_id) = &incoming_recipe.id {
-        Ok(diesel::delete(recipe_dsl::recipe)
-            .filter(recipe_dsl::id.eq(input_id))
-            .execute(conn)?)
-    } else if let Some(input_name) = &incoming_recipe.name {
-        Ok(diesel::delete(recipe_dsl::recipe)
-            .filter(recipe_dsl::recipe_name.eq(input_name))
-            .execute(conn)?)
-    } else {
-        Err(DieselError::DatabaseError(
-            DatabaseErrorKind::NotNullViolation,
-            Box::new(String::from("Name or ID must be supplied")),
-        ))
-    }
+    let recipe = match query_recipe(conn, incoming_recipe.clone()) {
+        Err(DieselError::NotFound) => return Ok(0),
+        recipe => recipe?,
+    };
+    diesel::delete(recipe_dsl::recipe.find(recipe.id)).execute(conn)
 }
 
 /// Returns full recipe with all fields
@@ -93,44 +197,168 @@ pub fn query_full_recipe(
 ) -> Result<FullRecipe, DieselError> {
     use crate::schema::recipe_ingredient::dsl as ingredient_dsl;
     use crate::schema::recipe_step::dsl as step_dsl;
-    use crate::{schema::recipe::dsl as recipe_dsl, structs::Ingredient};
+    use crate::structs::Ingredient;
     let mut full_recipe = FullRecipe::default();
-    // creating a Recipe
-    // conditionally set recipe based on URL query
-    if let Some(id) = &incoming_query.id {
-        let query_recipe: Recipe = recipe_dsl::recipe
-            .filter(recipe_dsl::id.eq(id))
-            .get_result(conn)?;
-        full_recipe.set_recipe(query_recipe);
-    } else if let Some(name) = &incoming_query.name {
-        let query_recipe: Recipe = recipe_dsl::recipe
-            .filter(recipe_dsl::recipe_name.eq(name))
-            .get_result(conn)?;
-        full_recipe.set_recipe(query_recipe);
-    }
+    full_recipe.set_recipe(query_recipe(conn, incoming_query.clone())?);
     full_recipe.set_owner_name(get_user_name(conn, full_recipe.recipe.user_id)?);
 
     let query_steps: Vec<Step> = step_dsl::recipe_step
         .filter(step_dsl::recipe_id.eq(full_recipe.recipe.id))
+        .order_by((step_dsl::position, step_dsl::id))
         .get_results::<Step>(conn)?;
     full_recipe.set_steps(query_steps);
     let query_ingredients: Vec<Ingredient> = ingredient_dsl::recipe_ingredient
         .filter(ingredient_dsl::recipe_id.eq(full_recipe.recipe.id))
+        .order_by((ingredient_dsl::position, ingredient_dsl::id))
         .get_results::<Ingredient>(conn)?;
     full_recipe.set_ingredients(query_ingredients);
+    full_recipe.set_tags(recipe_tags_query(conn, full_recipe.recipe.id)?);
 
     Ok(full_recipe)
 }
 
-/// Returns a list of `Recipe` struct
+/// Recipes whose name is like `pattern`, only the ones tagged `tag` if it's sent
+fn recipes_like<'a>(pattern: &'a str, tag: Option<&str>) -> recipe::BoxedQuery<'a, Pg> {
+    use crate::schema::recipe::dsl as recipe_dsl;
+    use crate::schema::recipe_tag::dsl as recipe_tag_dsl;
+    use crate::schema::tag::dsl as tag_dsl;
+
+    let query = recipe_dsl::recipe
+        .filter(recipe_dsl::recipe_name.like(pattern))
+        .into_boxed();
+    match tag {
+        // a tag that can't be stored matches nothing
+        Some(tag) => query.filter(
+            recipe_dsl::id.eq_any(
+                recipe_tag_dsl::recipe_tag
+                    .inner_join(tag_dsl::tag)
+                    .filter(tag_dsl::tag_name.eq(normalize_tag(tag).unwrap_or_default()))
+                    .select(recipe_tag_dsl::recipe_id),
+            ),
+        ),
+        None => query,
+    }
+}
+
+/// Returns a page of `Recipe` structs whose name starts with `recipe_name`
+///
+/// only recipes tagged `tag` are listed if it's sent
 pub fn fuzzy_query(
     conn: &mut PooledPgConnection,
     recipe_name: &String,
-) -> Result<Vec<Recipe>, DieselError> {
+    tag: Option<&str>,
+    page: &UrlPageQuery,
+) -> Result<Page<Recipe>, DieselError> {
     use crate::schema::recipe::dsl as recipe_dsl;
-    Ok(recipe_dsl::recipe
-        .filter(recipe_dsl::recipe_name.like(format!("{:}%", recipe_name)))
-        .get_results(conn)?)
+    let pattern = format!("{:}%", recipe_name);
+
+    let total: i64 = recipes_like(&pattern, tag).count().get_result(conn)?;
+
+    let query = recipes_like(&pattern, tag);
+    let query = match page.sort.unwrap_or(ListSort::IdAsc) {
+        ListSort::IdAsc => query.order(recipe_dsl::id.asc()),
+        ListSort::IdDesc => query.order(recipe_dsl::id.desc()),
+        ListSort::NameAsc => query.order((recipe_dsl::recipe_name.asc(), recipe_dsl::id.asc())),
+        ListSort::NameDesc => query.order((recipe_dsl::recipe_name.desc(), recipe_dsl::id.asc())),
+    };
+    let items = query
+        .limit(page.limit())
+        .offset(page.offset())
+        .get_results(conn)?;
+
+    Ok(Page::new(items, total, page))
+}
+
+/// Builds a prefix-matching `to_tsquery` string out of free user input
+///
+/// ex: "Roast  chick" -> "roast:* & chick:*"
+///
+/// Returns None if the input has no searchable words
+pub fn build_search_terms(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{}:*", word.to_lowercase()))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" & "))
+    }
+}
+
+/// SQL condition keeping the recipes whose `recipe_id` column has the tag bound at `$param`,
+/// every recipe is kept when it's NULL
+fn tag_filter(recipe_id: &str, param: usize) -> String {
+    format!(
+        "AND (${param}::text IS NULL OR {recipe_id} IN (\
+        SELECT rt.recipe_id FROM recipe_tag rt JOIN tag t ON t.id = rt.tag_id \
+        WHERE t.tag_name = ${param}))"
+    )
+}
+
+#[derive(QueryableByName)]
+struct SearchCount {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    total: i64,
+}
+
+/// Full-text search over recipe names, ingredients, observations and steps
+///
+/// Results are ranked by the weighted `recipe_search.document` tsvector unless
+/// a `sort` is supplied, an empty search returns every recipe. Only recipes tagged
+/// `tag` are returned if it's sent
+pub fn search_recipes(
+    conn: &mut PooledPgConnection,
+    search: &str,
+    tag: Option<&str>,
+    page: &UrlPageQuery,
+) -> Result<Page<Recipe>, DieselError> {
+    use diesel::sql_types::{BigInt, Nullable, Text};
+
+    let terms = match build_search_terms(search) {
+        Some(terms) => terms,
+        None => return fuzzy_query(conn, &String::new(), tag, page),
+    };
+    // a tag that can't be stored matches nothing
+    let tag = tag.map(|tag| normalize_tag(tag).unwrap_or_default());
+
+    let total = diesel::sql_query(format!(
+        "SELECT COUNT(*) AS total FROM recipe_search s \
+        WHERE s.document @@ to_tsquery('simple', $1) {}",
+        tag_filter("s.recipe_id", 2)
+    ))
+    .bind::<Text, _>(&terms)
+    .bind::<Nullable<Text>, _>(&tag)
+    .get_result::<SearchCount>(conn)?
+    .total;
+
+    // only whitelisted ORDER BY clauses, never user input
+    let order_by = match page.sort {
+        None => "ts_rank(s.document, q) DESC, r.id",
+        Some(ListSort::IdAsc) => "r.id ASC",
+        Some(ListSort::IdDesc) => "r.id DESC",
+        Some(ListSort::NameAsc) => "r.recipe_name ASC, r.id",
+        Some(ListSort::NameDesc) => "r.recipe_name DESC, r.id",
+    };
+    let items = diesel::sql_query(format!(
+        "SELECT r.id, r.user_id, r.recipe_name, r.recipe_observations, r.servings, r.slug \
+        FROM recipe r \
+        JOIN recipe_search s ON s.recipe_id = r.id, \
+        to_tsquery('simple', $1) q \
+        WHERE s.document @@ q {} \
+        ORDER BY {} \
+        LIMIT $2 OFFSET $3",
+        tag_filter("r.id", 4),
+        order_by
+    ))
+    .bind::<Text, _>(&terms)
+    .bind::<BigInt, _>(page.limit())
+    .bind::<BigInt, _>(page.offset())
+    .bind::<Nullable<Text>, _>(&tag)
+    .load::<Recipe>(conn)?;
+
+    Ok(Page::new(items, total, page))
 }
 
 /// Change details about Recipe (name, observations...)
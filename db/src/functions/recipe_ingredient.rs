@@ -14,31 +14,31 @@ pub fn get_ingredient_detail(
 
 /// Ingredient DB function responsible for creating an ingredient
 pub fn create_ingredient_query(
-    mut conn: PooledPgConnection,
+    conn: &mut PooledPgConnection,
     ingredients: &Vec<NewIngredient>,
 ) -> Result<Vec<Ingredient>, DieselError> {
     use crate::schema::recipe_ingredient::dsl as ingredient_dsl;
 
     Ok(diesel::insert_into(ingredient_dsl::recipe_ingredient)
         .values(ingredients)
-        .get_results(&mut conn)?)
+        .get_results(conn)?)
 }
 
 /// Ingredient DB function responsible for deleting an ingredient
 pub fn delete_ingredient_query(
-    mut conn: PooledPgConnection,
+    conn: &mut PooledPgConnection,
     ingredient: &Ingredient,
 ) -> Result<usize, DieselError> {
     use crate::schema::recipe_ingredient::dsl as ingredient_dsl;
 
     Ok(diesel::delete(ingredient_dsl::recipe_ingredient)
         .filter(ingredient_dsl::id.eq(ingredient.id))
-        .execute(&mut conn)?)
+        .execute(conn)?)
 }
 
 /// Ingredient DB function responsible for updating an ingredient's details
 pub fn update_ingredient_query(
-    mut conn: PooledPgConnection,
+    conn: &mut PooledPgConnection,
     ingredients: &Ingredient,
 ) -> Result<Ingredient, DieselError> {
     use crate::schema::recipe_ingredient::dsl as ingredient_dsl;
@@ -46,5 +46,33 @@ pub fn update_ingredient_query(
     Ok(diesel::update(ingredient_dsl::recipe_ingredient)
         .filter(ingredient_dsl::id.eq(ingredients.id))
         .set(ingredients)
-        .get_result(&mut conn)?)
+        .get_result(conn)?)
+}
+
+/// Rewrites the position of every ingredient in `ids`, in one transaction
+///
+/// Fails (and changes nothing) if any id doesn't belong to `recipe_id`
+pub fn reorder_ingredients_query(
+    conn: &mut PooledPgConnection,
+    recipe_id: i32,
+    ids: &[i32],
+) -> Result<Vec<Ingredient>, DieselError> {
+    use crate::schema::recipe_ingredient::dsl as ingredient_dsl;
+
+    conn.transaction(|conn| {
+        for (position, ingredient_id) in ids.iter().enumerate() {
+            let updated = diesel::update(ingredient_dsl::recipe_ingredient)
+                .filter(ingredient_dsl::id.eq(ingredient_id))
+                .filter(ingredient_dsl::recipe_id.eq(recipe_id))
+                .set(ingredient_dsl::position.eq(position as i32))
+                .execute(conn)?;
+            if updated == 0 {
+                return Err(DieselError::NotFound);
+            }
+        }
+        ingredient_dsl::recipe_ingredient
+            .filter(ingredient_dsl::recipe_id.eq(recipe_id))
+            .order_by((ingredient_dsl::position, ingredient_dsl::id))
+            .get_results(conn)
+    })
 }
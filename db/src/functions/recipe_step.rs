@@ -25,20 +25,48 @@ pub fn create_step_query(
 }
 
 /// # DB crate
-pub fn delete_step_query(mut conn: PooledPgConnection, step: &Step) -> Result<usize, DieselError> {
+pub fn delete_step_query(conn: &mut PooledPgConnection, step: &Step) -> Result<usize, DieselError> {
     use crate::schema::recipe_step::dsl as step_dsl;
 
     Ok(diesel::delete(step_dsl::recipe_step)
         .filter(step_dsl::id.eq(step.id))
-        .execute(&mut conn)?)
+        .execute(conn)?)
 }
 
 /// # DB crate
-pub fn update_step_query(mut conn: PooledPgConnection, steps: &Step) -> Result<Step, DieselError> {
+pub fn update_step_query(conn: &mut PooledPgConnection, steps: &Step) -> Result<Step, DieselError> {
     use crate::schema::recipe_step::dsl as step_dsl;
 
     Ok(diesel::update(step_dsl::recipe_step)
         .filter(step_dsl::id.eq(steps.id))
         .set(steps)
-        .get_result(&mut conn)?)
+        .get_result(conn)?)
+}
+
+/// Rewrites the position of every step in `ids`, in one transaction
+///
+/// Fails (and changes nothing) if any id doesn't belong to `recipe_id`
+pub fn reorder_steps_query(
+    conn: &mut PooledPgConnection,
+    recipe_id: i32,
+    ids: &[i32],
+) -> Result<Vec<Step>, DieselError> {
+    use crate::schema::recipe_step::dsl as step_dsl;
+
+    conn.transaction(|conn| {
+        for (position, step_id) in ids.iter().enumerate() {
+            let updated = diesel::update(step_dsl::recipe_step)
+                .filter(step_dsl::id.eq(step_id))
+                .filter(step_dsl::recipe_id.eq(recipe_id))
+                .set(step_dsl::position.eq(position as i32))
+                .execute(conn)?;
+            if updated == 0 {
+                return Err(DieselError::NotFound);
+            }
+        }
+        step_dsl::recipe_step
+            .filter(step_dsl::recipe_id.eq(recipe_id))
+            .order_by((step_dsl::position, step_dsl::id))
+            .get_results(conn)
+    })
 }
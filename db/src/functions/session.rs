@@ -0,0 +1,113 @@
+use crate::{
+    db_pool::{DieselError, PooledPgConnection},
+    schema::{revoked_token::dsl as revoked_dsl, user_session::dsl as session_dsl},
+    structs::{NewUserSession, RevokedToken, UserSession},
+};
+use chrono::Utc;
+use diesel::{
+    dsl::{exists, now},
+    prelude::*,
+};
+
+/// Stores a new refresh token, dropping the user's expired sessions on the way
+pub fn create_session_query(
+    conn: &mut PooledPgConnection,
+    session: &NewUserSession,
+) -> Result<UserSession, DieselError> {
+    diesel::delete(session_dsl::user_session)
+        .filter(session_dsl::user_id.eq(session.user_id))
+        .filter(session_dsl::expires_at.le(now))
+        .execute(conn)?;
+    diesel::insert_into(session_dsl::user_session)
+        .values(session)
+        .get_result(conn)
+}
+
+/// Swaps the session of `old_token_hash` for `new_session`, in one transaction
+///
+/// a refresh token can only be used once, an unknown or expired one returns `NotFound`
+pub fn rotate_session_query(
+    conn: &mut PooledPgConnection,
+    old_token_hash: &str,
+    new_session: &NewUserSession,
+) -> Result<UserSession, DieselError> {
+    conn.transaction(|conn| {
+        let old_session: UserSession = diesel::delete(session_dsl::user_session)
+            .filter(session_dsl::token_hash.eq(old_token_hash))
+            .get_result(conn)?;
+        if old_session.expires_at <= Utc::now() {
+            return Err(DieselError::NotFound);
+        }
+        diesel::insert_into(session_dsl::user_session)
+            .values(NewUserSession {
+                user_id: old_session.user_id,
+                ..new_session.clone()
+            })
+            .get_result(conn)
+    })
+}
+
+/// Drops the session of a refresh token, returns the number of deleted sessions
+pub fn delete_session_query(
+    conn: &mut PooledPgConnection,
+    token_hash: &str,
+) -> Result<usize, DieselError> {
+    diesel::delete(session_dsl::user_session)
+        .filter(session_dsl::token_hash.eq(token_hash))
+        .execute(conn)
+}
+
+/// Adds an access token to the denylist, dropping the entries that expired on the way
+pub fn revoke_token_query(
+    conn: &mut PooledPgConnection,
+    token: &RevokedToken,
+) -> Result<(), DieselError> {
+    diesel::delete(revoked_dsl::revoked_token)
+        .filter(revoked_dsl::expires_at.le(now))
+        .execute(conn)?;
+    diesel::insert_into(revoked_dsl::revoked_token)
+        .values(token)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(())
+}
+
+/// checks if the access token with this `jti` was revoked
+pub fn is_token_revoked_query(
+    conn: &mut PooledPgConnection,
+    jti: &str,
+) -> Result<bool, DieselError> {
+    diesel::select(exists(
+        revoked_dsl::revoked_token.filter(revoked_dsl::jti.eq(jti)),
+    ))
+    .get_result(conn)
+}
+
+/// Logs a user out everywhere: drops all of their sessions and revokes the
+/// access tokens issued with them, returns the number of sessions dropped
+pub fn revoke_user_sessions_query(
+    conn: &mut PooledPgConnection,
+    user_id: i32,
+) -> Result<usize, DieselError> {
+    conn.transaction(|conn| {
+        let sessions: Vec<UserSession> = diesel::delete(session_dsl::user_session)
+            .filter(session_dsl::user_id.eq(user_id))
+            .get_results(conn)?;
+        for session in sessions
+            .iter()
+            .filter(|session| session.expires_at > Utc::now())
+        {
+            if let Some(jti) = &session.access_jti {
+                // access tokens never outlive their session
+                revoke_token_query(
+                    conn,
+                    &RevokedToken {
+                        jti: jti.clone(),
+                        expires_at: session.expires_at,
+                    },
+                )?;
+            }
+        }
+        Ok(sessions.len())
+    })
+}
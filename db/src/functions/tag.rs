@@ -0,0 +1,169 @@
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+
+use crate::{
+    db_pool::{DieselError, PooledPgConnection},
+    structs::{NewTag, RecipeTag, Tag, TagSummary},
+};
+
+/// max length of a tag name, same as the `tag.tag_name` column
+pub const MAX_TAG_LENGTH: usize = 50;
+
+/// Stored version of a tag name: trimmed, lowercase, inner spaces collapsed
+///
+/// ex: "  Week  Night " -> "week night"
+///
+/// Returns None if the name is empty or longer than `MAX_TAG_LENGTH`
+pub fn normalize_tag(tag_name: &str) -> Option<String> {
+    let tag_name = tag_name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if tag_name.is_empty() || tag_name.chars().count() > MAX_TAG_LENGTH {
+        None
+    } else {
+        Some(tag_name)
+    }
+}
+
+/// `normalize_tag` over every name, duplicates are dropped
+///
+/// Returns an error message naming the first invalid tag
+pub fn normalize_tags(tag_names: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = vec![];
+    for tag_name in tag_names {
+        let Some(tag_name) = normalize_tag(tag_name) else {
+            return Err(format!(
+                "tag \"{}\" must have between 1 and {} characters",
+                tag_name.trim(),
+                MAX_TAG_LENGTH
+            ));
+        };
+        if !normalized.contains(&tag_name) {
+            normalized.push(tag_name);
+        }
+    }
+    Ok(normalized)
+}
+
+/// Tags given to at least one recipe, sorted by name, with how many recipes have them
+pub fn list_tags_query(conn: &mut PooledPgConnection) -> Result<Vec<TagSummary>, DieselError> {
+    use crate::schema::recipe_tag::dsl as recipe_tag_dsl;
+    use crate::schema::tag::dsl as tag_dsl;
+
+    tag_dsl::tag
+        .inner_join(recipe_tag_dsl::recipe_tag)
+        .group_by((tag_dsl::id, tag_dsl::tag_name))
+        .select((tag_dsl::id, tag_dsl::tag_name, count_star()))
+        .order(tag_dsl::tag_name)
+        .load::<TagSummary>(conn)
+}
+
+/// Tags of recipe `recipe_id`, sorted by name
+pub fn recipe_tags_query(
+    conn: &mut PooledPgConnection,
+    recipe_id: i32,
+) -> Result<Vec<Tag>, DieselError> {
+    use crate::schema::recipe_tag::dsl as recipe_tag_dsl;
+    use crate::schema::tag::dsl as tag_dsl;
+
+    tag_dsl::tag
+        .inner_join(recipe_tag_dsl::recipe_tag)
+        .filter(recipe_tag_dsl::recipe_id.eq(recipe_id))
+        .select(Tag::as_select())
+        .order(tag_dsl::tag_name)
+        .load(conn)
+}
+
+/// Tags named `tag_names`, the missing ones are created
+///
+/// names must already be normalized, see `normalize_tags`
+fn find_or_create_tags(
+    conn: &mut PooledPgConnection,
+    tag_names: &[String],
+) -> Result<Vec<Tag>, DieselError> {
+    use crate::schema::tag::dsl as tag_dsl;
+
+    let new_tags: Vec<NewTag> = tag_names
+        .iter()
+        .map(|tag_name| NewTag {
+            tag_name: tag_name.clone(),
+        })
+        .collect();
+    diesel::insert_into(tag_dsl::tag)
+        .values(&new_tags)
+        .on_conflict(tag_dsl::tag_name)
+        .do_nothing()
+        .execute(conn)?;
+    tag_dsl::tag
+        .filter(tag_dsl::tag_name.eq_any(tag_names))
+        .load(conn)
+}
+
+/// Gives `tag_names` to recipe `recipe_id`, tags it already has are kept
+///
+/// Returns every tag of the recipe
+pub fn add_recipe_tags_query(
+    conn: &mut PooledPgConnection,
+    recipe_id: i32,
+    tag_names: &[String],
+) -> Result<Vec<Tag>, DieselError> {
+    use crate::schema::recipe_tag::dsl as recipe_tag_dsl;
+
+    conn.transaction(|conn| {
+        let links: Vec<RecipeTag> = find_or_create_tags(conn, tag_names)?
+            .into_iter()
+            .map(|tag| RecipeTag {
+                recipe_id,
+                tag_id: tag.id,
+            })
+            .collect();
+        diesel::insert_into(recipe_tag_dsl::recipe_tag)
+            .values(&links)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        recipe_tags_query(conn, recipe_id)
+    })
+}
+
+/// Takes `tag_names` away from recipe `recipe_id`, the tags themselves are kept
+///
+/// Returns the remaining tags of the recipe
+pub fn remove_recipe_tags_query(
+    conn: &mut PooledPgConnection,
+    recipe_id: i32,
+    tag_names: &[String],
+) -> Result<Vec<Tag>, DieselError> {
+    use crate::schema::recipe_tag::dsl as recipe_tag_dsl;
+    use crate::schema::tag::dsl as tag_dsl;
+
+    diesel::delete(
+        recipe_tag_dsl::recipe_tag
+            .filter(recipe_tag_dsl::recipe_id.eq(recipe_id))
+            .filter(
+                recipe_tag_dsl::tag_id.eq_any(
+                    tag_dsl::tag
+                        .filter(tag_dsl::tag_name.eq_any(tag_names))
+                        .select(tag_dsl::id),
+                ),
+            ),
+    )
+    .execute(conn)?;
+    recipe_tags_query(conn, recipe_id)
+}
+
+/// Replaces every tag of recipe `recipe_id` with `tag_names`, in one transaction
+pub fn set_recipe_tags_query(
+    conn: &mut PooledPgConnection,
+    recipe_id: i32,
+    tag_names: &[String],
+) -> Result<Vec<Tag>, DieselError> {
+    use crate::schema::recipe_tag::dsl as recipe_tag_dsl;
+
+    conn.transaction(|conn| {
+        diesel::delete(recipe_tag_dsl::recipe_tag.filter(recipe_tag_dsl::recipe_id.eq(recipe_id)))
+            .execute(conn)?;
+        add_recipe_tags_query(conn, recipe_id, tag_names)
+    })
+}
@@ -1,8 +1,8 @@
 use crate::{
     schema::recipe_users::dsl as user_dsl,
-    structs::{NewUser, UrlUserQuery},
+    structs::{ListSort, NewUser, Page, PublicUser, UrlPageQuery, UrlUserQuery},
 };
-use diesel::prelude::*;
+use diesel::{prelude::*, upsert::excluded};
 
 use crate::{
     db_pool::{DieselError, PooledPgConnection},
@@ -82,14 +82,61 @@ pub fn update_user_record(conn: &mut PooledPgConnection, user: &User) -> Result<
     Ok(())
 }
 
-pub fn list_users_query(conn: &mut PooledPgConnection) -> Result<Vec<User>, DieselError> {
-    let users: Vec<User> = user_dsl::recipe_users
-        .select((
+/// Returns a page of users without their password hash, sorted by id unless `page.sort` says otherwise
+pub fn list_users_query(
+    conn: &mut PooledPgConnection,
+    page: &UrlPageQuery,
+) -> Result<Page<PublicUser>, DieselError> {
+    let total: i64 = user_dsl::recipe_users.count().get_result(conn)?;
+
+    let query = user_dsl::recipe_users
+        .select((user_dsl::id, user_dsl::user_name, user_dsl::user_role))
+        .into_boxed();
+    let query = match page.sort.unwrap_or(ListSort::IdAsc) {
+        ListSort::IdAsc => query.order(user_dsl::id.asc()),
+        ListSort::IdDesc => query.order(user_dsl::id.desc()),
+        ListSort::NameAsc => query.order(user_dsl::user_name.asc()),
+        ListSort::NameDesc => query.order(user_dsl::user_name.desc()),
+    };
+    let users: Vec<PublicUser> = query
+        .limit(page.limit())
+        .offset(page.offset())
+        .get_results(conn)?;
+    Ok(Page::new(users, total, page))
+}
+
+/// Creates `user`, or overwrites the password of the user with the same name and role
+///
+/// a user with the same name but another role is left as is, `DieselError::NotFound` is returned
+pub fn upsert_user_record(
+    conn: &mut PooledPgConnection,
+    user: &NewUser,
+) -> Result<User, DieselError> {
+    // `ON CONFLICT DO UPDATE ... WHERE`, not part of QueryDsl
+    use diesel::query_dsl::methods::FilterDsl;
+
+    diesel::insert_into(user_dsl::recipe_users)
+        .values(user)
+        .on_conflict(user_dsl::user_name)
+        .do_update()
+        .set(user_dsl::user_pwd.eq(excluded(user_dsl::user_pwd)))
+        .filter(user_dsl::user_role.eq(excluded(user_dsl::user_role)))
+        .returning((
             user_dsl::id,
             user_dsl::user_name,
             user_dsl::user_role,
             user_dsl::user_pwd,
         ))
-        .get_results(conn)?;
-    Ok(users)
+        .get_result(conn)
+}
+
+/// Names of the users whose password hash is exactly `pwd_hash`
+pub fn users_with_pwd_hash(
+    conn: &mut PooledPgConnection,
+    pwd_hash: &str,
+) -> Result<Vec<String>, DieselError> {
+    user_dsl::recipe_users
+        .select(user_dsl::user_name)
+        .filter(user_dsl::user_pwd.eq(pwd_hash))
+        .load(conn)
 }
@@ -1,8 +1,12 @@
 // cfg changes how the code will be compiled
+pub mod conversion;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod db_pool;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod functions;
+pub mod password;
+pub mod quantity;
+pub mod scaling;
 pub mod schema;
 
 pub mod structs;
@@ -0,0 +1,180 @@
+//! Password rules used when creating or updating users
+//!
+//! shared with the frontend, so the register form shows the same errors the backend returns
+#[cfg(not(target_arch = "wasm32"))]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// bundled list of leaked passwords, see `PasswordPolicy::reject_common`
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// passwords this close (edits) to the user name are refused
+const MAX_USERNAME_DISTANCE: usize = 2;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Configurable password rules, the backend builds it from env vars
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// refuse passwords from the bundled common password list
+    pub reject_common: bool,
+    /// refuse passwords that contain or look like the user name
+    pub reject_username: bool,
+}
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: false,
+            reject_common: true,
+            reject_username: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+/// Rule a password broke, sent to the frontend with each error
+pub enum PasswordRule {
+    MinLength,
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+    Common,
+    Username,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+pub struct PasswordError {
+    pub rule: PasswordRule,
+    pub msg: String,
+}
+impl PasswordError {
+    fn new<S: Into<String>>(rule: PasswordRule, msg: S) -> Self {
+        Self {
+            rule,
+            msg: msg.into(),
+        }
+    }
+}
+impl Display for PasswordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl PasswordPolicy {
+    /// Checks `pwd` against every rule, returns all the broken ones (empty if the password is fine)
+    pub fn check(&self, user_name: &str, pwd: &str) -> Vec<PasswordError> {
+        let mut errors = vec![];
+        if pwd.chars().count() < self.min_length {
+            errors.push(PasswordError::new(
+                PasswordRule::MinLength,
+                format!("must have at least {} characters", self.min_length),
+            ));
+        }
+        let classes = [
+            (
+                self.require_lowercase,
+                PasswordRule::Lowercase,
+                "must have a lowercase letter",
+                char::is_lowercase as fn(char) -> bool,
+            ),
+            (
+                self.require_uppercase,
+                PasswordRule::Uppercase,
+                "must have an uppercase letter",
+                char::is_uppercase,
+            ),
+            (
+                self.require_digit,
+                PasswordRule::Digit,
+                "must have a digit",
+                |c: char| c.is_ascii_digit(),
+            ),
+            (
+                self.require_symbol,
+                PasswordRule::Symbol,
+                "must have a symbol (ex: !@#$)",
+                |c: char| !c.is_alphanumeric() && !c.is_whitespace(),
+            ),
+        ];
+        for (required, rule, msg, matches) in classes {
+            if required && !pwd.chars().any(matches) {
+                errors.push(PasswordError::new(rule, msg));
+            }
+        }
+        if self.reject_common && is_common_password(pwd) {
+            errors.push(PasswordError::new(
+                PasswordRule::Common,
+                "is too common, pick something less guessable",
+            ));
+        }
+        if self.reject_username && is_similar_to_username(user_name, pwd) {
+            errors.push(PasswordError::new(
+                PasswordRule::Username,
+                "is too similar to the user name",
+            ));
+        }
+        errors
+    }
+}
+
+/// checks `pwd` against the bundled common password list, ignoring case
+pub fn is_common_password(pwd: &str) -> bool {
+    let pwd = pwd.to_lowercase();
+    COMMON_PASSWORDS
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .any(|common| common.trim() == pwd)
+}
+
+/// lowercase letters and digits only, "John_Doe" -> "johndoe"
+fn simplify(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// checks if `pwd` contains the user name (or the other way around, or reversed)
+/// or is only a couple of edits away from it
+pub fn is_similar_to_username(user_name: &str, pwd: &str) -> bool {
+    let name = simplify(user_name);
+    let pwd = simplify(pwd);
+    if name.is_empty() || pwd.is_empty() {
+        return false;
+    }
+    let reversed: String = name.chars().rev().collect();
+    // a 2 letter user name inside a long password is fine
+    let long_enough = name.chars().count() >= 3;
+    (long_enough && (pwd.contains(&name) || pwd.contains(&reversed)))
+        || (pwd.chars().count() >= 3 && name.contains(&pwd))
+        || edit_distance(&name, &pwd) <= MAX_USERNAME_DISTANCE
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,93 @@
+//! Ingredient quantities, stored as `NUMERIC(10, 3)` and handled as `BigDecimal`
+//!
+//! shared by the backend (recipe import) and the frontend (ingredient forms)
+pub use bigdecimal::{BigDecimal, Zero};
+
+use std::str::FromStr;
+
+/// decimal places kept for a quantity, matches the `recipe_ingredient.ingredient_quantity` column
+pub const QUANTITY_SCALE: i64 = 3;
+/// quantities must be below this, the column keeps 7 digits before the point
+pub const MAX_QUANTITY: u32 = 10_000_000;
+
+/// Parses user input into a quantity
+///
+/// accepts integers/decimals ("2", "0.75", "1,5"), fractions ("1/2"),
+/// mixed numbers ("1 1/2") and unicode fractions ("½", "1½")
+///
+/// Returns None for empty, negative or invalid input
+pub fn parse_quantity(input: &str) -> Option<BigDecimal> {
+    let normalized = normalize_fractions(input.trim()).replace(',', ".");
+    let parts: Vec<&str> = normalized.split_whitespace().collect();
+
+    let value = match parts.as_slice() {
+        [single] => parse_part(single)?,
+        // mixed number, "1 1/2"
+        [whole, fraction] if !whole.contains(['.', '/']) && fraction.contains('/') => {
+            parse_part(whole)? + parse_part(fraction)?
+        }
+        _ => return None,
+    };
+    Some(value.round(QUANTITY_SCALE).normalized())
+}
+
+/// Formats a quantity without trailing zeros ("1.500" -> "1.5", "2.000" -> "2")
+pub fn format_quantity(quantity: &BigDecimal) -> String {
+    let text = quantity.round(QUANTITY_SCALE).to_string();
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+fn parse_part(part: &str) -> Option<BigDecimal> {
+    // only plain numbers, BigDecimal::from_str would also take "1e3" and "-1"
+    if part.is_empty()
+        || !part
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == '/')
+    {
+        return None;
+    }
+    match part.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator = BigDecimal::from_str(numerator).ok()?;
+            let denominator = BigDecimal::from_str(denominator).ok()?;
+            if denominator.is_zero() {
+                return None;
+            }
+            Some(numerator / denominator)
+        }
+        None => BigDecimal::from_str(part).ok(),
+    }
+}
+
+/// Replaces unicode vulgar fractions with "n/d", keeping "1½" as "1 1/2"
+pub fn normalize_fractions(input: &str) -> String {
+    let mut normalized = String::with_capacity(input.len());
+    for c in input.chars() {
+        let fraction = match c {
+            '½' => "1/2",
+            '⅓' => "1/3",
+            '⅔' => "2/3",
+            '¼' => "1/4",
+            '¾' => "3/4",
+            '⅛' => "1/8",
+            '⅜' => "3/8",
+            '⅝' => "5/8",
+            '⅞' => "7/8",
+            // fraction slash, "1⁄2"
+            '⁄' => "/",
+            _ => {
+                normalized.push(c);
+                continue;
+            }
+        };
+        if fraction != "/" && normalized.ends_with(|c: char| c.is_ascii_digit()) {
+            normalized.push(' ');
+        }
+        normalized.push_str(fraction);
+    }
+    normalized
+}
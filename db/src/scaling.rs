@@ -0,0 +1,100 @@
+//! Scaling of recipes to a number of servings
+//!
+//! pure functions, used by `/api/get/recipe?servings=N` and by the frontend's servings stepper
+use crate::{
+    quantity::{BigDecimal, QUANTITY_SCALE},
+    structs::{FullRecipe, Ingredient, Unit},
+};
+
+/// Units that convert into each other, largest first
+///
+/// each entry is `(unit, size in the smallest unit, smallest amount it's used for)`,
+/// both in the smallest unit of the ladder
+type Ladder = &'static [(Unit, u32, u32)];
+
+const METRIC_MASS: Ladder = &[(Unit::Kilogram, 1000, 1000), (Unit::Gram, 1, 0)];
+const IMPERIAL_MASS: Ladder = &[(Unit::Pound, 16, 16), (Unit::Ounce, 1, 0)];
+const METRIC_VOLUME: Ladder = &[(Unit::Liter, 1000, 1000), (Unit::Milliliter, 1, 0)];
+/// 3 tsp = 1 tbsp, 48 tsp = 1 cup, cups are used from 1/4 cup on
+const US_VOLUME: Ladder = &[
+    (Unit::Cup, 48, 12),
+    (Unit::Tablespoon, 3, 3),
+    (Unit::Teaspoon, 1, 0),
+];
+
+fn ladder(unit: Unit) -> Option<Ladder> {
+    [METRIC_MASS, IMPERIAL_MASS, METRIC_VOLUME, US_VOLUME]
+        .into_iter()
+        .find(|ladder| ladder.iter().any(|(step, _, _)| *step == unit))
+}
+
+/// Multiplies `quantity` by `to_servings / from_servings`, rounded to `QUANTITY_SCALE`
+pub fn scale_quantity(quantity: &BigDecimal, from_servings: i32, to_servings: i32) -> BigDecimal {
+    if from_servings <= 0 || from_servings == to_servings {
+        return quantity.clone();
+    }
+    (quantity * BigDecimal::from(to_servings) / BigDecimal::from(from_servings))
+        .round(QUANTITY_SCALE)
+        .normalized()
+}
+
+/// Rewrites a quantity in the most readable unit of the same system
+///
+/// ex: 48 tsp -> 1 cup, 1200 g -> 1.2 kg, 0.5 kg -> 500 g
+///
+/// metric and imperial units are never mixed, units without a ladder (count, pinch...) are kept
+pub fn normalize_unit(quantity: &BigDecimal, unit: Unit) -> (BigDecimal, Unit) {
+    let ladder = match ladder(unit) {
+        Some(ladder) => ladder,
+        None => return (quantity.clone(), unit),
+    };
+    let size = ladder
+        .iter()
+        .find(|(step, _, _)| *step == unit)
+        .map(|(_, size, _)| *size)
+        .unwrap_or(1);
+    let base = quantity * BigDecimal::from(size);
+
+    for (step, size, threshold) in ladder {
+        if base >= *threshold {
+            let converted = (&base / BigDecimal::from(*size))
+                .round(QUANTITY_SCALE)
+                .normalized();
+            return (converted, *step);
+        }
+    }
+    (quantity.clone(), unit)
+}
+
+/// Scales a single ingredient and converts it to a sensible unit
+pub fn scale_ingredient(
+    ingredient: &Ingredient,
+    from_servings: i32,
+    to_servings: i32,
+) -> Ingredient {
+    let scaled = scale_quantity(&ingredient.ingredient_quantity, from_servings, to_servings);
+    let (ingredient_quantity, quantity_unit) = normalize_unit(&scaled, ingredient.quantity_unit);
+    Ingredient {
+        ingredient_quantity,
+        quantity_unit,
+        ..ingredient.clone()
+    }
+}
+
+/// Returns `full_recipe` with every ingredient scaled to `servings`
+///
+/// `servings` lower than 1, or equal to the recipe's, leave the recipe untouched
+pub fn scale_recipe(full_recipe: &FullRecipe, servings: i32) -> FullRecipe {
+    if servings < 1 || servings == full_recipe.recipe.servings {
+        return full_recipe.clone();
+    }
+    let from_servings = full_recipe.recipe.servings;
+    let mut scaled = full_recipe.clone();
+    scaled.recipe.servings = servings;
+    scaled.ingredients = full_recipe
+        .ingredients
+        .iter()
+        .map(|ingredient| scale_ingredient(ingredient, from_servings, servings))
+        .collect();
+    scaled
+}
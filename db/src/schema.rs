@@ -7,6 +7,9 @@ diesel::table! {
         #[max_length = 100]
         recipe_name -> Varchar,
         recipe_observations -> Nullable<Array<Nullable<Text>>>,
+        servings -> Int4,
+        #[max_length = 120]
+        slug -> Varchar,
     }
 }
 
@@ -16,9 +19,11 @@ diesel::table! {
         recipe_id -> Int4,
         #[max_length = 100]
         ingredient_name -> Varchar,
-        ingredient_quantity -> Int4,
+        ingredient_quantity -> Numeric,
         #[max_length = 10]
         quantity_unit -> Varchar,
+        position -> Int4,
+        ingredient_density -> Nullable<Numeric>,
     }
 }
 
@@ -30,6 +35,7 @@ diesel::table! {
         step_name -> Bpchar,
         step_instruction -> Text,
         step_duration_min -> Int4,
+        position -> Int4,
     }
 }
 
@@ -42,8 +48,65 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    login_attempt (user_id) {
+        user_id -> Int4,
+        failed_count -> Int4,
+        last_failed_at -> Timestamptz,
+        locked_until -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    revoked_token (jti) {
+        jti -> Text,
+        expires_at -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    user_session (id) {
+        id -> Int4,
+        user_id -> Int4,
+        token_hash -> Text,
+        expires_at -> Timestamptz,
+        created_at -> Timestamptz,
+        access_jti -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    tag (id) {
+        id -> Int4,
+        #[max_length = 50]
+        tag_name -> Varchar,
+    }
+}
+
+diesel::table! {
+    recipe_tag (recipe_id, tag_id) {
+        recipe_id -> Int4,
+        tag_id -> Int4,
+    }
+}
+
+diesel::joinable!(login_attempt -> recipe_users (user_id));
 diesel::joinable!(recipe -> recipe_users (user_id));
 diesel::joinable!(recipe_ingredient -> recipe (recipe_id));
 diesel::joinable!(recipe_step -> recipe (recipe_id));
+diesel::joinable!(recipe_tag -> recipe (recipe_id));
+diesel::joinable!(recipe_tag -> tag (tag_id));
+diesel::joinable!(user_session -> recipe_users (user_id));
 
-diesel::allow_tables_to_appear_in_same_query!(recipe, recipe_ingredient, recipe_step, recipe_users,);
+diesel::allow_tables_to_appear_in_same_query!(
+    login_attempt,
+    recipe,
+    recipe_ingredient,
+    recipe_step,
+    recipe_tag,
+    recipe_users,
+    revoked_token,
+    tag,
+    user_session,
+);
@@ -1,8 +1,12 @@
 #[cfg(not(target_arch = "wasm32"))]
-use crate::schema::{recipe, recipe_ingredient, recipe_step, recipe_users};
+use crate::schema::{
+    login_attempt, recipe, recipe_ingredient, recipe_step, recipe_tag, recipe_users, revoked_token,
+    tag, user_session,
+};
 use std::{
     fmt::{Debug, Display},
-    io::{Error as IOError, Write},
+    io::Write,
+    str::FromStr,
 };
 
 use diesel::{
@@ -14,10 +18,18 @@ use diesel::{
     sql_types::Text,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::password::{PasswordError, PasswordPolicy};
+use crate::quantity::{BigDecimal, MAX_QUANTITY, QUANTITY_SCALE};
+use bigdecimal::num_bigint::Sign;
+use chrono::{DateTime, Utc};
+
 pub trait RecipeTrait {}
 impl RecipeTrait for FullRecipe {}
+impl RecipeTrait for NewFullRecipe {}
 
 impl RecipeTrait for NewRecipe {}
 impl RecipeTrait for UpdateRecipe {}
@@ -38,6 +50,7 @@ impl RecipeTrait for UpdateStep {}
 impl<T> RecipeTrait for Vec<T> {}
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(
     not(target_arch = "wasm32"),
     derive(Queryable, Selectable, AsChangeset, Identifiable, Associations,)
@@ -52,6 +65,9 @@ pub struct Step {
     pub step_name: String,
     pub step_instruction: String,
     pub step_duration_min: i32,
+    /// order inside the recipe, starting at 0
+    #[serde(default)]
+    pub position: i32,
 }
 impl Default for Step {
     fn default() -> Self {
@@ -61,6 +77,7 @@ impl Default for Step {
             step_name: String::new(),
             step_instruction: String::new(),
             step_duration_min: 0,
+            position: 0,
         }
     }
 }
@@ -71,8 +88,13 @@ impl Step {
 }
 
 #[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 pub struct UpdateStep {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub id: i32,
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub recipe_id: i32,
     pub step_name: Option<String>,
     pub step_instruction: Option<String>,
@@ -80,6 +102,7 @@ pub struct UpdateStep {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(
     not(target_arch = "wasm32"),
     derive(AsChangeset, Associations, Insertable,)
@@ -89,6 +112,8 @@ pub struct UpdateStep {
     diesel(table_name = recipe_step),
 )]
 pub struct NewStep {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub recipe_id: i32,
     pub step_name: String,
     pub step_instruction: String,
@@ -106,6 +131,7 @@ impl Default for NewStep {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(
     not(target_arch = "wasm32"),
     derive(Queryable, Selectable, AsChangeset, Identifiable, Associations,)
@@ -118,8 +144,16 @@ pub struct Ingredient {
     pub id: i32,
     pub recipe_id: i32,
     pub ingredient_name: String,
-    pub ingredient_quantity: i32,
-    pub quantity_unit: String,
+    pub ingredient_quantity: BigDecimal,
+    pub quantity_unit: Unit,
+    /// order inside the recipe, starting at 0
+    #[serde(default)]
+    pub position: i32,
+    /// g/mL, used to convert between mass and volume units
+    ///
+    /// None falls back to `conversion::known_density`
+    #[serde(default)]
+    pub ingredient_density: Option<BigDecimal>,
 }
 impl Default for Ingredient {
     fn default() -> Self {
@@ -127,8 +161,10 @@ impl Default for Ingredient {
             id: -1,
             recipe_id: -1,
             ingredient_name: String::new(),
-            ingredient_quantity: 0,
-            quantity_unit: String::new(),
+            ingredient_quantity: BigDecimal::from(0),
+            quantity_unit: Unit::default(),
+            position: 0,
+            ingredient_density: None,
         }
     }
 }
@@ -139,15 +175,23 @@ impl Ingredient {
 }
 
 #[derive(PartialEq, Serialize, Deserialize, Debug)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 pub struct UpdateIngredient {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub id: i32,
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub recipe_id: i32,
     pub ingredient_name: Option<String>,
-    pub ingredient_quantity: Option<i32>,
-    pub quantity_unit: Option<String>,
+    pub ingredient_quantity: Option<BigDecimal>,
+    pub quantity_unit: Option<Unit>,
+    #[serde(default)]
+    pub ingredient_density: Option<BigDecimal>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(
     not(target_arch = "wasm32"),
     derive(AsChangeset, Associations, Insertable)
@@ -157,28 +201,42 @@ pub struct UpdateIngredient {
     diesel(table_name = recipe_ingredient),
 )]
 pub struct NewIngredient {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub recipe_id: i32,
     pub ingredient_name: String,
-    pub ingredient_quantity: i32,
-    pub quantity_unit: String,
+    pub ingredient_quantity: BigDecimal,
+    pub quantity_unit: Unit,
+    /// g/mL, see `Ingredient::ingredient_density`
+    #[serde(default)]
+    pub ingredient_density: Option<BigDecimal>,
 }
 impl Default for NewIngredient {
     fn default() -> Self {
         Self {
             recipe_id: -1,
             ingredient_name: String::new(),
-            ingredient_quantity: -1,
-            quantity_unit: String::new(),
+            ingredient_quantity: BigDecimal::from(-1),
+            quantity_unit: Unit::default(),
+            ingredient_density: None,
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 // configuring attributes
 // if target_arch (architeture the code is being compiled in) is wasm32, ignore these (diesel stuff)
 #[cfg_attr(
     not(target_arch = "wasm32"),
-    derive(AsChangeset, Queryable, Selectable, Associations, Identifiable,)
+    derive(
+        AsChangeset,
+        Queryable,
+        QueryableByName,
+        Selectable,
+        Associations,
+        Identifiable,
+    )
 )]
 #[cfg_attr(not(target_arch = "wasm32"), 
     diesel(belongs_to(User)),
@@ -189,6 +247,17 @@ pub struct Recipe {
     pub user_id: i32,
     pub recipe_name: String,
     pub recipe_observations: Option<Vec<Option<String>>>,
+    /// servings the ingredient quantities are written for
+    #[serde(default = "default_servings")]
+    pub servings: i32,
+    /// URL-safe name, unique per user, set when the recipe is created and kept on renames
+    #[serde(default)]
+    #[cfg_attr(not(target_arch = "wasm32"), diesel(skip_update))]
+    pub slug: String,
+}
+/// servings of recipes sent without the field
+fn default_servings() -> i32 {
+    1
 }
 impl Default for Recipe {
     fn default() -> Self {
@@ -197,6 +266,8 @@ impl Default for Recipe {
             user_id: -1,
             recipe_name: String::new(),
             recipe_observations: None,
+            servings: 1,
+            slug: String::new(),
         }
     }
 }
@@ -221,14 +292,21 @@ impl Recipe {
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 pub struct UpdateRecipe {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub id: i32,
+    /// unused, recipes keep their owner
+    #[serde(default)]
     pub user_id: i32,
     pub recipe_name: Option<String>,
     pub recipe_observations: Option<Vec<Option<String>>>,
+    pub servings: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 // configuring attributes
 // if target_arch (architeture the code is being compiled in) is wasm32, ignore these (diesel stuff)
 #[cfg_attr(
@@ -240,9 +318,13 @@ pub struct UpdateRecipe {
     diesel(table_name = recipe),
 )]
 pub struct NewRecipe {
+    /// set from the login token
+    #[serde(default)]
     pub user_id: i32,
     pub recipe_name: String,
     pub recipe_observations: Option<Vec<Option<String>>>,
+    #[serde(default = "default_servings")]
+    pub servings: i32,
 }
 impl NewRecipe {
     pub fn set_user_id(&mut self, id: i32) {
@@ -255,11 +337,13 @@ impl Default for NewRecipe {
             user_id: -1,
             recipe_name: String::new(),
             recipe_observations: None,
+            servings: 1,
         }
     }
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Queryable))]
 /// used to represent a recipe with its steps and ingredients
 pub struct FullRecipe {
@@ -267,7 +351,115 @@ pub struct FullRecipe {
     pub ingredients: Vec<Ingredient>,
     pub steps: Vec<Step>,
     pub recipe_owner_name: String,
+    /// sorted by name
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+}
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+#[cfg_attr(
+    not(target_arch = "wasm32"),
+    derive(Queryable, Selectable, Identifiable)
+)]
+#[cfg_attr(not(target_arch = "wasm32"), diesel(table_name = tag))]
+/// Label grouping recipes ("breakfast", "vegan"...), shared by every user
+pub struct Tag {
+    pub id: i32,
+    /// trimmed and lowercase, unique
+    pub tag_name: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Insertable))]
+#[cfg_attr(not(target_arch = "wasm32"), diesel(table_name = tag))]
+pub struct NewTag {
+    pub tag_name: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    not(target_arch = "wasm32"),
+    derive(Queryable, Selectable, Insertable, Associations)
+)]
+#[cfg_attr(not(target_arch = "wasm32"),
+    diesel(belongs_to(Recipe)),
+    diesel(belongs_to(Tag)),
+    diesel(table_name = recipe_tag),
+)]
+/// Tag `tag_id` given to recipe `recipe_id`
+pub struct RecipeTag {
+    pub recipe_id: i32,
+    pub tag_id: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Queryable))]
+/// Tag with the number of recipes using it, sent by the tag list endpoints
+pub struct TagSummary {
+    pub id: i32,
+    pub tag_name: String,
+    pub recipe_count: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Tag names added to, removed from or set on a recipe, sent to `/api/create/tag`,
+/// `/api/delete/tag` and `/api/update/tags`
+///
+/// names are trimmed and lowercased, unknown ones are created
+pub struct RecipeTags {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
+    pub recipe_id: i32,
+    pub tags: Vec<String>,
 }
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Recipe with all its parts, created at once by `/api/create/full_recipe`
+///
+/// `recipe_id` of ingredients and steps is ignored, the created recipe's id is used
+pub struct NewFullRecipe {
+    pub recipe: NewRecipe,
+    pub ingredients: Vec<NewIngredient>,
+    pub steps: Vec<NewStep>,
+}
+impl From<FullRecipe> for NewFullRecipe {
+    /// drops every id, used for sending a locally built FullRecipe
+    fn from(full_recipe: FullRecipe) -> Self {
+        NewFullRecipe {
+            recipe: NewRecipe {
+                user_id: full_recipe.recipe.user_id,
+                recipe_name: full_recipe.recipe.recipe_name,
+                recipe_observations: full_recipe.recipe.recipe_observations,
+                servings: full_recipe.recipe.servings,
+            },
+            ingredients: full_recipe
+                .ingredients
+                .into_iter()
+                .map(|ingredient| NewIngredient {
+                    recipe_id: ingredient.recipe_id,
+                    ingredient_name: ingredient.ingredient_name,
+                    ingredient_quantity: ingredient.ingredient_quantity,
+                    quantity_unit: ingredient.quantity_unit,
+                    ingredient_density: ingredient.ingredient_density,
+                })
+                .collect(),
+            steps: full_recipe
+                .steps
+                .into_iter()
+                .map(|step| NewStep {
+                    recipe_id: step.recipe_id,
+                    step_name: step.step_name,
+                    step_instruction: step.step_instruction,
+                    step_duration_min: step.step_duration_min,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl Default for FullRecipe {
     fn default() -> Self {
         FullRecipe {
@@ -275,6 +467,7 @@ impl Default for FullRecipe {
             ingredients: vec![],
             steps: vec![],
             recipe_owner_name: String::new(),
+            tags: vec![],
         }
     }
 }
@@ -297,6 +490,11 @@ impl FullRecipe {
         self.recipe_owner_name = name
     }
 
+    /// modify Tags inside FullRecipe
+    pub fn set_tags(&mut self, tags: Vec<Tag>) {
+        self.tags = tags
+    }
+
     /// Replaces item from list
     ///
     /// # Returns
@@ -423,6 +621,7 @@ impl FullRecipe {
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(
     not(target_arch = "wasm32"),
     derive(AsChangeset, Queryable, Selectable, Insertable)
@@ -447,13 +646,78 @@ impl Default for NewUser {
 impl NewUser {
     /// Password validation function
     ///
-    /// # TODO
-    /// create Regex pwd validation    
-    pub fn validate(&self, pwd: &str) -> Result<String, IOError> {
-        return Ok(pwd.into());
+    /// checks `pwd` (the new password) against `policy`, returning every rule it breaks
+    pub fn validate(&self, pwd: &str, policy: &PasswordPolicy) -> Result<(), Vec<PasswordError>> {
+        let errors = policy.check(&self.user_name, pwd);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 }
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    not(target_arch = "wasm32"),
+    derive(Queryable, Selectable, Identifiable, Associations)
+)]
+#[cfg_attr(not(target_arch = "wasm32"),
+    diesel(belongs_to(User)),
+    diesel(table_name = user_session),
+)]
+/// Server side half of a refresh token, see `/api/refresh`
+pub struct UserSession {
+    pub id: i32,
+    pub user_id: i32,
+    /// SHA-256 of the refresh token, hex encoded
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    /// jti of the access token issued with this session
+    pub access_jti: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Insertable))]
+#[cfg_attr(not(target_arch = "wasm32"), diesel(table_name = user_session))]
+pub struct NewUserSession {
+    pub user_id: i32,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub access_jti: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    not(target_arch = "wasm32"),
+    derive(Queryable, Selectable, Identifiable, Associations)
+)]
+#[cfg_attr(not(target_arch = "wasm32"),
+    diesel(belongs_to(User)),
+    diesel(table_name = login_attempt),
+    diesel(primary_key(user_id)),
+)]
+/// Failed logins of a user since their last successful one
+pub struct LoginAttempt {
+    pub user_id: i32,
+    pub failed_count: i32,
+    pub last_failed_at: DateTime<Utc>,
+    /// logins are refused until then
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Insertable))]
+#[cfg_attr(not(target_arch = "wasm32"), diesel(table_name = revoked_token))]
+/// Access token (jwt) that can't be used anymore, by its `jti` claim
+pub struct RevokedToken {
+    pub jti: String,
+    /// the token is rejected as expired after this, the row can be dropped
+    pub expires_at: DateTime<Utc>,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(
     not(target_arch = "wasm32"),
     derive(AsChangeset, Queryable, Selectable, Identifiable,)
@@ -484,8 +748,29 @@ impl User {
     }
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema, Queryable))]
+/// `User` as sent to clients, the password hash never leaves the server
+pub struct PublicUser {
+    pub id: i32,
+    pub user_name: String,
+    pub user_role: UserRole,
+}
+impl From<User> for PublicUser {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            user_name: user.user_name,
+            user_role: user.user_role,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 pub struct UpdateUser {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
     pub id: i32,
     pub user_name: Option<String>,
     pub user_role: Option<UserRole>,
@@ -504,6 +789,7 @@ impl Default for UpdateUser {
 }
 
 #[derive(Deserialize, Clone, Debug, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 #[cfg_attr(not(target_arch = "wasm32"), derive(FromSqlRow, AsExpression))]
 #[cfg_attr(not(target_arch = "wasm32"), diesel(sql_type = Text))
 ]
@@ -555,25 +841,411 @@ impl FromSql<Text, Pg> for UserRole {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+/// What a `Unit` measures
+pub enum UnitKind {
+    Mass,
+    Volume,
+    Count,
+    /// no measurable quantity ("salt to taste")
+    ToTaste,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+/// Measuring system ingredients can be displayed in
+pub enum UnitSystem {
+    /// grams, kilograms, milliliters and liters
+    Metric,
+    /// ounces, pounds, teaspoons, tablespoons and cups
+    Imperial,
+}
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromSqlRow, AsExpression))]
+#[cfg_attr(not(target_arch = "wasm32"), diesel(sql_type = Text))
+]
+/// Measuring unit of an ingredient, stored as its name in `recipe_ingredient.quantity_unit`
+///
+/// (De)serialized as the name ("tablespoon"), but abbreviations, plurals and
+/// other aliases ("tbsp", "Tablespoons") are accepted when parsing
+pub enum Unit {
+    Gram,
+    Kilogram,
+    Ounce,
+    Pound,
+    Milliliter,
+    Liter,
+    Teaspoon,
+    Tablespoon,
+    Cup,
+    /// counted items ("3 eggs")
+    #[default]
+    Count,
+    Pinch,
+    Clove,
+    ToTaste,
+}
+impl Unit {
+    /// every unit, in the order they're offered to users
+    pub const ALL: [Unit; 13] = [
+        Unit::Gram,
+        Unit::Kilogram,
+        Unit::Ounce,
+        Unit::Pound,
+        Unit::Milliliter,
+        Unit::Liter,
+        Unit::Teaspoon,
+        Unit::Tablespoon,
+        Unit::Cup,
+        Unit::Count,
+        Unit::Pinch,
+        Unit::Clove,
+        Unit::ToTaste,
+    ];
+
+    /// name stored in the db and sent through the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Gram => "gram",
+            Unit::Kilogram => "kilogram",
+            Unit::Ounce => "ounce",
+            Unit::Pound => "pound",
+            Unit::Milliliter => "milliliter",
+            Unit::Liter => "liter",
+            Unit::Teaspoon => "teaspoon",
+            Unit::Tablespoon => "tablespoon",
+            Unit::Cup => "cup",
+            Unit::Count => "unit",
+            Unit::Pinch => "pinch",
+            Unit::Clove => "clove",
+            Unit::ToTaste => "to taste",
+        }
+    }
+
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Ounce => "oz",
+            Unit::Pound => "lb",
+            Unit::Milliliter => "mL",
+            Unit::Liter => "L",
+            Unit::Teaspoon => "tsp",
+            Unit::Tablespoon => "tbsp",
+            Unit::Cup => "cup",
+            Unit::Count => "un",
+            Unit::Pinch => "pinch",
+            Unit::Clove => "clove",
+            Unit::ToTaste => "to taste",
+        }
+    }
+
+    pub fn kind(&self) -> UnitKind {
+        match self {
+            Unit::Gram | Unit::Kilogram | Unit::Ounce | Unit::Pound => UnitKind::Mass,
+            Unit::Milliliter | Unit::Liter | Unit::Teaspoon | Unit::Tablespoon | Unit::Cup => {
+                UnitKind::Volume
+            }
+            Unit::Count | Unit::Pinch | Unit::Clove => UnitKind::Count,
+            Unit::ToTaste => UnitKind::ToTaste,
+        }
+    }
+
+    /// measuring system of mass and volume units, None for counted/unmeasured ones
+    pub fn system(&self) -> Option<UnitSystem> {
+        match self {
+            Unit::Gram | Unit::Kilogram | Unit::Milliliter | Unit::Liter => {
+                Some(UnitSystem::Metric)
+            }
+            Unit::Ounce | Unit::Pound | Unit::Teaspoon | Unit::Tablespoon | Unit::Cup => {
+                Some(UnitSystem::Imperial)
+            }
+            Unit::Count | Unit::Pinch | Unit::Clove | Unit::ToTaste => None,
+        }
+    }
+
+    /// Checks if `quantity` makes sense for this unit
+    ///
+    /// quantities must be positive, except for `ToTaste` which has no quantity,
+    /// and below `MAX_QUANTITY` once rounded to the stored scale
+    pub fn validate_quantity(&self, quantity: &BigDecimal) -> Result<(), String> {
+        let max = BigDecimal::from(MAX_QUANTITY);
+        if quantity.round(QUANTITY_SCALE) >= max {
+            return Err(format!("quantity must be less than {}", MAX_QUANTITY));
+        }
+        match self {
+            Unit::ToTaste if quantity.sign() == Sign::Minus => {
+                Err(String::from("quantity cannot be negative"))
+            }
+            Unit::ToTaste => Ok(()),
+            _ if quantity.sign() != Sign::Plus => Err(format!(
+                "quantity in {} must be greater than zero",
+                self.as_str()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// text shown in unit pickers, ex: "Tablespoon (tbsp)"
+    pub fn label(&self) -> String {
+        let name = self.as_str();
+        let mut label = name[..1].to_uppercase() + &name[1..];
+        if self.abbreviation() != name {
+            label.push_str(&format!(" ({})", self.abbreviation()));
+        }
+        label
+    }
+}
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for Unit {
+    type Err = String;
+
+    /// case insensitive, accepts names, abbreviations, plurals and common aliases
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let unit = match input.trim().to_lowercase().as_str() {
+            "gram" | "grams" | "g" | "gr" => Unit::Gram,
+            "kilogram" | "kilograms" | "kg" | "kgs" => Unit::Kilogram,
+            "ounce" | "ounces" | "oz" => Unit::Ounce,
+            "pound" | "pounds" | "lb" | "lbs" => Unit::Pound,
+            "milliliter" | "milliliters" | "millilitre" | "millilitres" | "ml" => Unit::Milliliter,
+            "liter" | "liters" | "litre" | "litres" | "l" => Unit::Liter,
+            "teaspoon" | "teaspoons" | "tsp" | "tsps" => Unit::Teaspoon,
+            "tablespoon" | "tablespoons" | "tbsp" | "tbsps" | "tbs" => Unit::Tablespoon,
+            "cup" | "cups" => Unit::Cup,
+            "unit" | "units" | "un" | "piece" | "pieces" | "pc" | "pcs" => Unit::Count,
+            "pinch" | "pinches" => Unit::Pinch,
+            "clove" | "cloves" => Unit::Clove,
+            "to taste" | "to_taste" => Unit::ToTaste,
+            _ => return Err(format!("unknown unit: {:?}", input)),
+        };
+        Ok(unit)
+    }
+}
+impl Serialize for Unit {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        Unit::from_str(&input).map_err(serde::de::Error::custom)
+    }
+}
+/// the unit names, aliases are accepted too but not listed
+#[cfg(not(target_arch = "wasm32"))]
+impl JsonSchema for Unit {
+    fn schema_name() -> String {
+        String::from("Unit")
+    }
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            enum_values: Some(Unit::ALL.iter().map(|unit| unit.as_str().into()).collect()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ToSql<Text, Pg> for Unit {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, Pg>,
+    ) -> diesel::serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl FromSql<Text, Pg> for Unit {
+    fn from_sql(
+        bytes: <Pg as diesel::backend::Backend>::RawValue<'_>,
+    ) -> diesel::deserialize::Result<Self> {
+        let unit = std::str::from_utf8(bytes.as_bytes())?;
+        Ok(Unit::from_str(unit)?)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 /// Types of query that can be received from user as param
 ///
-/// ex: https://.../?name=<RecipeName>
+/// ex: https://.../?name=<RecipeName> or https://.../?user=<UserName>&slug=<slug>
+///
+/// `id` wins over the others, names and slugs are only unique per user so `user` must be sent with them
 pub struct UrlRecipeQuery {
     pub id: Option<i32>,
     pub name: Option<String>,
+    /// name of the recipe owner
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub slug: Option<String>,
+}
+
+impl UrlRecipeQuery {
+    pub fn by_id(id: i32) -> Self {
+        Self {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+    /// recipe `slug` of the user named `user`
+    pub fn by_slug<U: Into<String>, S: Into<String>>(user: U, slug: S) -> Self {
+        Self {
+            user: Some(user.into()),
+            slug: Some(slug.into()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Full-text search query received from user as param
+///
+/// ex: https://.../?query=<search terms>
+pub struct UrlSearchQuery {
+    pub query: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// File formats a recipe can be exported to
+pub enum ExportFormat {
+    /// schema.org `Recipe` as JSON-LD
+    #[serde(rename = "jsonld")]
+    JsonLd,
+    #[serde(rename = "markdown")]
+    Markdown,
+    /// plain text
+    #[serde(rename = "txt")]
+    Txt,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Export format received from user as param, used alongside `UrlRecipeQuery`
+///
+/// ex: https://.../?id=<RecipeId>&format=markdown
+pub struct UrlExportQuery {
+    /// if None, JSON-LD is used
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// New order of a recipe's steps or ingredients, sent to `/api/reorder/steps` and `/api/reorder/ingredients`
+///
+/// `ids` must contain every step/ingredient of the recipe, first one first
+pub struct ReorderItems {
+    /// optional for `/api/v2`, where it's taken from the path
+    #[serde(default)]
+    pub recipe_id: i32,
+    pub ids: Vec<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Tag received from user as param, narrows the recipe list and search endpoints
+///
+/// ex: https://.../?query=<search terms>&tag=vegan
+pub struct UrlTagQuery {
+    /// if None, recipes aren't filtered by tag
+    pub tag: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Servings received from user as param, used alongside `UrlRecipeQuery`
+///
+/// ex: https://.../?id=<RecipeId>&servings=4
+pub struct UrlServingsQuery {
+    /// if None, the recipe is sent as stored
+    pub servings: Option<i32>,
 }
 
-impl Default for UrlRecipeQuery {
+/// default number of items returned by list endpoints
+pub const DEFAULT_PAGE_LIMIT: i64 = 20;
+/// max number of items a client can request in a single page
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+/// Sorting options accepted by list endpoints
+///
+/// `name_*` sorts by recipe name (or user name when listing users)
+pub enum ListSort {
+    IdAsc,
+    IdDesc,
+    NameAsc,
+    NameDesc,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Pagination/sorting params shared by every list endpoint
+///
+/// ex: https://.../?name=<RecipeName>&limit=10&offset=20&sort=name_asc
+pub struct UrlPageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// if None, the endpoint's default ordering is used
+    pub sort: Option<ListSort>,
+}
+impl UrlPageQuery {
+    /// requested limit, clamped to 1..=MAX_PAGE_LIMIT
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT)
+    }
+    /// requested offset, negative values are treated as 0
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Envelope returned by list endpoints
+///
+/// `next_cursor` is the offset of the next page, None if this is the last one
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub next_cursor: Option<i64>,
+}
+impl<T> Default for Page<T> {
     fn default() -> Self {
-        UrlRecipeQuery {
-            id: None,
-            name: None,
+        Page {
+            items: vec![],
+            total: 0,
+            next_cursor: None,
+        }
+    }
+}
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: i64, page: &UrlPageQuery) -> Self {
+        let end = page.offset() + items.len() as i64;
+        Page {
+            next_cursor: if end < total { Some(end) } else { None },
+            items,
+            total,
         }
     }
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 pub struct UrlUserQuery {
     pub id: Option<i32>,
     pub name: Option<String>,
@@ -589,7 +1261,90 @@ impl Default for UrlUserQuery {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
 /// This struct represents possible Ok Values the API can generate
 pub struct ApiOkResponse {
     pub msg: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+/// Machine-readable reason of an error response, clients match on it instead of `message`
+///
+/// codes are never renamed, new ones can be added
+pub enum ErrorCode {
+    /// unknown endpoint or a missing item of an unnamed kind
+    NotFound,
+    RecipeNotFound,
+    StepNotFound,
+    IngredientNotFound,
+    UserNotFound,
+    /// a recipe or user with this name already exists
+    NameTaken,
+    /// another unique value already exists
+    AlreadyExists,
+    /// not logged in or wrong credentials
+    Unauthorized,
+    /// logged in, but not allowed to do this
+    Forbidden,
+    /// access token expired, the client should call `/api/refresh` and retry
+    TokenExpired,
+    /// the body or query is invalid, see `field_errors`
+    InvalidPayload,
+    /// password broke the policy, every broken rule is in `field_errors`
+    WeakPassword,
+    /// rate limited or locked out, see the `Retry-After` header
+    TooManyRequests,
+    DatabaseError,
+    InternalError,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Problem with one field of the request
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+impl FieldError {
+    pub fn new<F: Into<String>, M: Into<String>>(field: F, message: M) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+impl From<&PasswordError> for FieldError {
+    fn from(value: &PasswordError) -> Self {
+        FieldError::new("user_pwd", &value.msg)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(JsonSchema))]
+/// Body of every error response the API generates
+pub struct ApiErrorResponse {
+    pub code: ErrorCode,
+    /// human readable, may change, match on `code` instead
+    pub message: String,
+    #[serde(default)]
+    pub field_errors: Vec<FieldError>,
+    /// id of the request, also in the `X-Request-Id` header
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+impl Display for ApiErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        for field_error in &self.field_errors {
+            write!(f, "\n{}: {}", field_error.field, field_error.message)?;
+        }
+        Ok(())
+    }
+}
+impl From<ApiErrorResponse> for String {
+    fn from(value: ApiErrorResponse) -> Self {
+        value.to_string()
+    }
+}
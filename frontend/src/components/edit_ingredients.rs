@@ -7,8 +7,12 @@ use crate::{
     functions::{recipe_functions::update_ingredient, ApiResponse},
     DEFAULT_NOTIFICATION_DURATION,
 };
-use db::structs::Ingredient;
+use db::{
+    quantity::{format_quantity, parse_quantity},
+    structs::{Ingredient, Unit},
+};
 use log::error;
+use std::str::FromStr;
 use web_sys::HtmlInputElement;
 use yew::{platform::spawn_local, prelude::*};
 use yew_notifications::{use_notification, Notification};
@@ -29,6 +33,7 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
     let name_input = use_node_ref();
     let ingredient_quantity_input = use_node_ref();
     let quantity_unit_input = use_node_ref();
+    let ingredient_density_input = use_node_ref();
 
     {
         let mode = mode.clone();
@@ -56,6 +61,7 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
         let name_input = name_input.clone();
         let quantity_input = ingredient_quantity_input.clone();
         let unit_input = quantity_unit_input.clone();
+        let density_input = ingredient_density_input.clone();
 
         Callback::from(move |event: SubmitEvent| {
             let old_part = old_part.clone();
@@ -85,7 +91,7 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
                 if val.is_empty() {
                     old_part.ingredient_quantity
                 } else {
-                    val.parse::<i32>().unwrap_or(old_part.ingredient_quantity)
+                    parse_quantity(&val).unwrap_or(old_part.ingredient_quantity)
                 }
             };
             let unit_input = unit_input.cast::<HtmlInputElement>().unwrap();
@@ -94,7 +100,18 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
                 if val.is_empty() {
                     old_part.quantity_unit
                 } else {
-                    val
+                    // accepts aliases like "tbsp", unknown units keep the old value
+                    Unit::from_str(&val).unwrap_or(old_part.quantity_unit)
+                }
+            };
+
+            let density_input = density_input.cast::<HtmlInputElement>().unwrap();
+            let ingredient_density = {
+                let val = density_input.value();
+                if val.is_empty() {
+                    old_part.ingredient_density
+                } else {
+                    parse_quantity(&val).or(old_part.ingredient_density)
                 }
             };
 
@@ -104,6 +121,8 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
                 ingredient_name,
                 ingredient_quantity,
                 quantity_unit,
+                position: old_part.position,
+                ingredient_density,
             };
 
             {
@@ -149,6 +168,7 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
                 name_input.set_value("");
                 quantity_input.set_value("");
                 unit_input.set_value("");
+                density_input.set_value("");
             }
         })
     };
@@ -182,10 +202,10 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
 
                 <Input
                     input_node_ref={ingredient_quantity_input.clone()}
-                    input_placeholder={format!("Current quantity {}",ingredient_state.quantity_unit)}
+                    input_placeholder={format!("Current quantity {}",format_quantity(&ingredient_state.ingredient_quantity))}
                     is_required={false}
                     input_name="ingredient quantity"
-                    input_type={InputType::Number}
+                    input_type={InputType::Text}
                 />
 
                 <Input
@@ -195,6 +215,17 @@ pub fn edit_ingredients(props: &RecipePartProps<Ingredient>) -> Html {
                     is_required={false}
                     input_type={InputType::Text}
                 />
+
+                <Input
+                    input_node_ref={ingredient_density_input.clone()}
+                    input_placeholder={match &ingredient_state.ingredient_density {
+                        Some(density) => format!("Current density {} g/mL", format_quantity(density)),
+                        None => String::from("Density in g/mL, optional"),
+                    }}
+                    input_name="ingredient density"
+                    is_required={false}
+                    input_type={InputType::Text}
+                />
                     <button>{format!("Update ingredient {}",ingredient_state.ingredient_name)}</button>
 
             </form>
@@ -3,19 +3,26 @@ use crate::{
         edit_ingredients::EditIngredient,
         edit_steps::EditStep,
         input_component::{Input, InputType},
+        reorder_list::ReorderList,
+        tag_editor::TagEditor,
         RecipeMode,
     },
     functions::{
-        recipe_functions::{delete_recipe, update_recipe},
+        recipe_functions::{
+            delete_recipe, reorder_ingredients, reorder_steps, update_recipe, update_tags,
+        },
         ApiResponse,
     },
     views::Route,
     DEFAULT_NOTIFICATION_DURATION,
 };
+use gloo_net::Error as GlooError;
+use serde::Deserialize;
+use std::future::Future;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
-use db::structs::{FullRecipe, Ingredient, Step};
+use db::structs::{FullRecipe, Ingredient, RecipeTags, ReorderItems, Step};
 use log::error;
 use yew::platform::spawn_local;
 use yew_notifications::{use_notification, Notification};
@@ -34,6 +41,42 @@ pub struct EditRecipeProps {
     pub step_to_edit: Step,
 }
 
+/// Callback saving the order picked in a `ReorderList`
+///
+/// `reorder` sends the ids to the backend, `set_items` puts the items it answers with back
+/// in the recipe. Errors are passed to `notify_error`
+fn save_order<T, F>(
+    recipe_state: UseStateHandle<FullRecipe>,
+    notify_error: Callback<String>,
+    reorder: fn(ReorderItems) -> F,
+    set_items: fn(&mut FullRecipe, Vec<T>),
+) -> Callback<Vec<i32>>
+where
+    T: for<'a> Deserialize<'a> + 'static,
+    F: Future<Output = Result<ApiResponse<Vec<T>, String>, GlooError>> + 'static,
+{
+    Callback::from(move |ids: Vec<i32>| {
+        let recipe_state = recipe_state.clone();
+        let notify_error = notify_error.clone();
+        let request = reorder(ReorderItems {
+            recipe_id: recipe_state.recipe.id,
+            ids,
+        });
+        spawn_local(async move {
+            match request.await {
+                Ok(ApiResponse::OkPart(items)) => {
+                    let mut full_recipe = (*recipe_state).clone();
+                    set_items(&mut full_recipe, items);
+                    recipe_state.set(full_recipe);
+                }
+                Ok(ApiResponse::ApiError(err)) => notify_error.emit(err.to_string()),
+                Ok(_) => {}
+                Err(err) => notify_error.emit(err.to_string()),
+            }
+        });
+    })
+}
+
 #[function_component(EditRecipe)]
 pub fn edit_recipe(props: &EditRecipeProps) -> Html {
     let EditRecipeProps {
@@ -144,6 +187,71 @@ pub fn edit_recipe(props: &EditRecipeProps) -> Html {
         })
     };
 
+    // saves the order picked in the step and ingredient ReorderLists
+    let notify_error = {
+        let use_notification = use_notification::<Notification>();
+        Callback::from(move |msg: String| {
+            use_notification.spawn(Notification::new(
+                yew_notifications::NotificationType::Error,
+                "Error!",
+                msg,
+                DEFAULT_NOTIFICATION_DURATION,
+            ));
+        })
+    };
+    let handle_step_reorder = save_order(
+        recipe_state.clone(),
+        notify_error.clone(),
+        reorder_steps,
+        |recipe, steps| recipe.steps = steps,
+    );
+    let handle_ingredient_reorder = save_order(
+        recipe_state.clone(),
+        notify_error,
+        reorder_ingredients,
+        |recipe, ingredients| recipe.ingredients = ingredients,
+    );
+
+    // saves the tags left in the TagEditor
+    let handle_tags = {
+        let recipe_state = recipe_state.clone();
+        let use_notification = use_notification::<Notification>();
+
+        Callback::from(move |tags: Vec<String>| {
+            let recipe_state = recipe_state.clone();
+            let use_notification = use_notification.clone();
+            let recipe_tags = RecipeTags {
+                recipe_id: recipe_state.recipe.id,
+                tags,
+            };
+            spawn_local(async move {
+                match update_tags(&recipe_tags).await {
+                    Ok(ApiResponse::OkPart(tags)) => recipe_state.set(FullRecipe {
+                        tags,
+                        ..(*recipe_state).clone()
+                    }),
+                    Ok(ApiResponse::ApiError(err)) => {
+                        use_notification.spawn(Notification::new(
+                            yew_notifications::NotificationType::Error,
+                            "Error!",
+                            err,
+                            DEFAULT_NOTIFICATION_DURATION,
+                        ));
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        use_notification.spawn(Notification::new(
+                            yew_notifications::NotificationType::Error,
+                            "Error!",
+                            err.to_string(),
+                            DEFAULT_NOTIFICATION_DURATION,
+                        ));
+                    }
+                }
+            });
+        })
+    };
+
     let recipe = old_recipe.recipe.clone();
     let new_name_ref = use_node_ref();
 
@@ -285,6 +393,11 @@ pub fn edit_recipe(props: &EditRecipeProps) -> Html {
             <button >{"Rename"}</button>
         </form>
 
+        <TagEditor
+        tags={recipe_state.tags.clone()}
+        onchange={handle_tags}
+        />
+
         <EditStep
         recipe_id={recipe_state.clone().recipe.id}
         old_part={step_to_edit.clone()}
@@ -297,6 +410,18 @@ pub fn edit_recipe(props: &EditRecipeProps) -> Html {
         callback={ingredient_cb}
         />
 
+        <ReorderList
+        title="Drag to reorder steps"
+        items={recipe_state.steps.iter().map(|step| (step.id, step.step_name.trim().to_string())).collect::<Vec<_>>()}
+        onreorder={handle_step_reorder}
+        />
+
+        <ReorderList
+        title="Drag to reorder ingredients"
+        items={recipe_state.ingredients.iter().map(|ingredient| (ingredient.id, ingredient.ingredient_name.clone())).collect::<Vec<_>>()}
+        onreorder={handle_ingredient_reorder}
+        />
+
         </div>
         <div class="edit-actions">
             // delete recipe
@@ -104,6 +104,7 @@ pub fn edit_step(props: &RecipePartProps<Step>) -> Html {
                 step_name,
                 step_instruction,
                 step_duration_min,
+                position: old_part.position,
             };
 
             {
@@ -1,4 +1,4 @@
-use db::structs::Ingredient;
+use db::{quantity::format_quantity, structs::Ingredient};
 use log::error;
 use yew::{platform::spawn_local, prelude::*};
 use yew_notifications::{use_notification, Notification};
@@ -104,11 +104,19 @@ pub fn ingredient_component(props: &ItemProps<Ingredient>) -> Html {
         onclick={handle_delete}
         >{"Delete"}</button>
         </>
+    }} else if edit_mode == RecipeMode::New && *focus_state {
+    // not saved yet, it's only dropped from the parent's list
+    html!{
+        <button onclick={{
+            let ingredient = item.clone();
+            let curr_focus = curr_focus.clone();
+            Callback::from(move |_| curr_focus.emit((RecipeMode::Delete,ingredient.clone())))
+        }}>{"Remove"}</button>
     }}else {html!{<></>}}}
 
             {item.ingredient_name.clone()}
             {format!(" {} {}",
-            item.ingredient_quantity,item.quantity_unit)}
+            format_quantity(&item.ingredient_quantity),item.quantity_unit)}
         </div>
     }
 }
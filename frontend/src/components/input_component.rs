@@ -9,6 +9,8 @@ pub struct InputProps {
     pub input_type: InputType,
     pub input_node_ref: NodeRef,
     pub is_required: bool,
+    #[prop_or_default]
+    pub oninput: Callback<InputEvent>,
 }
 impl Display for InputType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -37,6 +39,8 @@ pub enum InputType {
 ///
 /// input_node_ref -> uses use_node_ref(). Used for event handling with CallBack (https://yew.rs/docs/concepts/html/events#using-noderef)
 ///
+/// oninput -> optional, called on every keystroke
+///
 pub fn input(props: &InputProps) -> Html {
     let InputProps {
         input_placeholder,
@@ -44,6 +48,7 @@ pub fn input(props: &InputProps) -> Html {
         input_type,
         input_node_ref,
         is_required,
+        oninput,
     } = props;
     html! {
         <input
@@ -52,6 +57,7 @@ pub fn input(props: &InputProps) -> Html {
             placeholder={input_placeholder.clone()}
             ref={input_node_ref.clone()}
             required={is_required.clone()}
+            oninput={oninput.clone()}
 
         />
     }
@@ -9,7 +9,11 @@ pub mod new_step;
 pub mod recipe_card_component;
 pub mod recipe_component;
 pub mod recipe_title;
+pub mod reorder_list;
+pub mod servings_stepper;
 pub mod steps_component;
+pub mod tag_editor;
+pub mod unit_system_toggle;
 pub mod units;
 
 use db::structs::RecipeTrait;
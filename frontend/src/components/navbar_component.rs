@@ -1,14 +1,61 @@
-use crate::views::Route;
-use yew::prelude::*;
+use crate::{
+    functions::{user_functions::logout_user, ApiResponse},
+    views::Route,
+    DEFAULT_NOTIFICATION_DURATION,
+};
+use log::error;
+use yew::{platform::spawn_local, prelude::*};
+use yew_notifications::{use_notification, Notification, NotificationType};
 use yew_router::prelude::*;
 
 #[function_component(NavBar)]
 pub fn navbar() -> Html {
+    let use_notification = use_notification::<Notification>();
+    let navigator = use_navigator().unwrap();
+
+    let logout = Callback::from(move |_: MouseEvent| {
+        let use_notification = use_notification.clone();
+        let navigator = navigator.clone();
+        spawn_local(async move {
+            match logout_user().await {
+                Ok(ApiResponse::ApiMessage(msg)) => {
+                    use_notification.spawn(Notification::new(
+                        NotificationType::Info,
+                        "Logged out",
+                        msg,
+                        DEFAULT_NOTIFICATION_DURATION,
+                    ));
+                    navigator.push(&Route::Home);
+                }
+                Ok(ApiResponse::ApiError(msg)) => {
+                    use_notification.spawn(Notification::new(
+                        NotificationType::Error,
+                        "Error!",
+                        msg,
+                        DEFAULT_NOTIFICATION_DURATION,
+                    ));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    error!("error: {:?}", err);
+                    use_notification.spawn(Notification::new(
+                        NotificationType::Error,
+                        "Error!",
+                        err.to_string(),
+                        DEFAULT_NOTIFICATION_DURATION,
+                    ));
+                }
+            }
+        })
+    });
+
     html! {
         <nav class="top-nav">
             <Link<Route> to={Route::Home} >{"Home"}</ Link<Route>>
             <Link<Route> to={Route::NewRecipe}>{"New Recipe"}</Link<Route>>
+            <Link<Route> to={Route::Tags}>{"Tags"}</Link<Route>>
             <Link<Route> to={Route::UserPage}>{"User Register/Login"}</Link<Route>>
+            <button class="logout-button" onclick={logout}>{"Logout"}</button>
 
         </nav>
     }
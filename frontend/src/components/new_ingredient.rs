@@ -1,5 +1,10 @@
-use db::structs::{Ingredient, NewIngredient};
+use db::{
+    conversion::validate_density,
+    quantity::parse_quantity,
+    structs::{Ingredient, NewIngredient, Unit},
+};
 use log::error;
+use std::str::FromStr;
 use web_sys::{wasm_bindgen::UnwrapThrowExt, FormData, HtmlFormElement, HtmlInputElement};
 
 use yew::{platform::spawn_local, prelude::*};
@@ -30,6 +35,7 @@ pub fn new_ingredient(props: &RecipePartProps<Ingredient>) -> Html {
 
     let name_input = use_node_ref();
     let ingredient_quantity_input = use_node_ref();
+    let ingredient_density_input = use_node_ref();
 
     // handling form submit (adding new ingredient to list)
     let handle_new_ingredient = {
@@ -39,6 +45,7 @@ pub fn new_ingredient(props: &RecipePartProps<Ingredient>) -> Html {
         // cloning node ref
         let name_input = name_input.clone();
         let quantity_input = ingredient_quantity_input.clone();
+        let density_input = ingredient_density_input.clone();
 
         Callback::from(move |e: SubmitEvent| {
             let callback = callback.clone();
@@ -59,14 +66,88 @@ pub fn new_ingredient(props: &RecipePartProps<Ingredient>) -> Html {
             // getting form input values...
             let name = name_input.cast::<HtmlInputElement>().unwrap();
             let quantity = quantity_input.cast::<HtmlInputElement>().unwrap();
+            let density = density_input.cast::<HtmlInputElement>().unwrap();
+
+            // accepts "2", "0.75", "1 1/2" and "½"
+            let ingredient_quantity = match parse_quantity(&quantity.value()) {
+                Some(ingredient_quantity) => ingredient_quantity,
+                None => {
+                    use_notification.spawn(Notification::new(
+                        yew_notifications::NotificationType::Error,
+                        "Error!",
+                        format!("invalid quantity: {}", quantity.value()),
+                        DEFAULT_NOTIFICATION_DURATION,
+                    ));
+                    return;
+                }
+            };
+
+            // same checks the backend does, so users get the error right away
+            let quantity_unit = match Unit::from_str(&unit).and_then(|quantity_unit| {
+                quantity_unit
+                    .validate_quantity(&ingredient_quantity)
+                    .map(|_| quantity_unit)
+            }) {
+                Ok(quantity_unit) => quantity_unit,
+                Err(msg) => {
+                    use_notification.spawn(Notification::new(
+                        yew_notifications::NotificationType::Error,
+                        "Error!",
+                        msg,
+                        DEFAULT_NOTIFICATION_DURATION,
+                    ));
+                    return;
+                }
+            };
+
+            // optional, ingredients without one use the densities known by name
+            let ingredient_density = match density.value().trim() {
+                "" => None,
+                val => match parse_quantity(val)
+                    .ok_or(format!("invalid density: {}", val))
+                    .and_then(|density| validate_density(&density).map(|_| density))
+                {
+                    Ok(density) => Some(density),
+                    Err(msg) => {
+                        use_notification.spawn(Notification::new(
+                            yew_notifications::NotificationType::Error,
+                            "Error!",
+                            msg,
+                            DEFAULT_NOTIFICATION_DURATION,
+                        ));
+                        return;
+                    }
+                },
+            };
 
             let ingredient = NewIngredient {
                 recipe_id: old_part.recipe_id,
                 ingredient_name: name.value(),
-                ingredient_quantity: quantity.value().parse::<i32>().unwrap_or(0),
-                quantity_unit: unit,
+                ingredient_quantity,
+                quantity_unit,
+                ingredient_density,
             };
-            {
+            if ingredient.recipe_id < 0 {
+                // recipe wasn't saved yet, the parent keeps it until the whole recipe is created
+                callback.emit((
+                    RecipeMode::New,
+                    Ingredient {
+                        id: -1,
+                        recipe_id: ingredient.recipe_id,
+                        ingredient_name: ingredient.ingredient_name,
+                        ingredient_quantity: ingredient.ingredient_quantity,
+                        quantity_unit: ingredient.quantity_unit,
+                        ingredient_density: ingredient.ingredient_density,
+                        ..Default::default()
+                    },
+                ));
+                use_notification.spawn(Notification::new(
+                    yew_notifications::NotificationType::Info,
+                    "Sucess",
+                    "Ingredient added, it's saved with the recipe",
+                    DEFAULT_NOTIFICATION_DURATION,
+                ));
+            } else {
                 let ingredient = ingredient.clone();
                 spawn_local(async move {
                     let use_notification = use_notification.clone();
@@ -120,15 +201,22 @@ pub fn new_ingredient(props: &RecipePartProps<Ingredient>) -> Html {
                 input_type={InputType::Text}/>
             <Input
             input_node_ref={ingredient_quantity_input.clone()}
-            input_placeholder="Ingredient quantity"
+            input_placeholder="Ingredient quantity (1 1/2, 0.75...)"
                 is_required={true}
 
             input_name="ingredient quantity"
-            input_type={InputType::Number}/>
+            input_type={InputType::Text}/>
 
             <MeasuringUnits
             id={"new-ingredient"}
             />
+            <Input
+            input_node_ref={ingredient_density_input.clone()}
+            input_placeholder="Density in g/mL, optional (0.53 for flour)"
+                is_required={false}
+
+            input_name="ingredient density"
+            input_type={InputType::Text}/>
             <button>{"New ingredient"}</button>
 
         </form>
@@ -57,7 +57,26 @@ pub fn new_recipe_step(props: &RecipePartProps<Step>) -> Html {
                 step_instruction: step_instruction.value(),
                 step_duration_min: step_duration_min.value().parse::<i32>().unwrap(),
             };
-            {
+            if step.recipe_id < 0 {
+                // recipe wasn't saved yet, the parent keeps it until the whole recipe is created
+                callback.emit((
+                    RecipeMode::New,
+                    Step {
+                        id: -1,
+                        recipe_id: step.recipe_id,
+                        step_name: step.step_name,
+                        step_instruction: step.step_instruction,
+                        step_duration_min: step.step_duration_min,
+                        ..Default::default()
+                    },
+                ));
+                use_notification.spawn(Notification::new(
+                    yew_notifications::NotificationType::Info,
+                    "Success!",
+                    "Step added, it's saved with the recipe",
+                    DEFAULT_NOTIFICATION_DURATION,
+                ));
+            } else {
                 let callback = callback.clone();
                 spawn_local(async move {
                     let use_notification = use_notification.clone();
@@ -80,7 +99,7 @@ pub fn new_recipe_step(props: &RecipePartProps<Step>) -> Html {
                                 use_notification.spawn(Notification::new(
                                     yew_notifications::NotificationType::Info,
                                     "Success!",
-                                    "Ingredient created",
+                                    "Step created",
                                     DEFAULT_NOTIFICATION_DURATION,
                                 ));
                             }
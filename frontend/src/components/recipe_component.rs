@@ -1,4 +1,8 @@
-use db::structs::{FullRecipe, Ingredient, Step};
+use db::{
+    conversion::convert_ingredient,
+    scaling::scale_recipe,
+    structs::{FullRecipe, Ingredient, Step},
+};
 use yew::{platform::spawn_local, prelude::*};
 use yew_notifications::{use_notification, Notification};
 
@@ -31,12 +35,26 @@ pub fn recipe_component(props: &RecipeProps) -> Html {
     let step_to_edit = use_state(|| Step::default());
     let mode_state = use_state(|| mode.clone());
     let edit_mode = use_state(|| false);
+    let servings = use_state(|| full_recipe.recipe.servings);
+    let unit_system = use_state(stored_unit_system);
 
     // let full_recipe = (*recipe_state).clone();
     let full_recipe = recipe_state.clone();
 
     let recipe = full_recipe.recipe.clone();
-    let ingredients = full_recipe.ingredients.clone();
+    // ingredients are only scaled/converted for viewing, editing always uses the stored quantities
+    let ingredients = if RecipeMode::View == (*mode_state).clone() {
+        let scaled = scale_recipe(&full_recipe, *servings).ingredients;
+        match *unit_system {
+            Some(system) => scaled
+                .iter()
+                .map(|ingredient| convert_ingredient(ingredient, system))
+                .collect(),
+            None => scaled,
+        }
+    } else {
+        full_recipe.ingredients.clone()
+    };
     let steps = full_recipe.steps.clone();
 
     let onclick = {
@@ -86,8 +104,27 @@ pub fn recipe_component(props: &RecipeProps) -> Html {
                 html!{<button {onclick}>{"Edit Recipe"}</button>}
             } else{html!{}}
         }
-            <RecipeTitle owner={full_recipe.recipe_owner_name.clone()} title={recipe.recipe_name}/>
+            <RecipeTitle owner={full_recipe.recipe_owner_name.clone()} title={recipe.recipe_name} tags={full_recipe.tags.clone()}/>
+            {
+                if RecipeMode::View == (*mode_state).clone() {
+                    let servings = servings.clone();
+                    let unit_system = unit_system.clone();
+                    html!{<>
+                        <ServingsStepper
+                        servings={*servings}
+                        onchange={Callback::from(move |value| servings.set(value))}
+                        />
+                        <UnitSystemToggle
+                        system={*unit_system}
+                        onchange={Callback::from(move |value| unit_system.set(value))}
+                        />
+                    </>}
+                } else {html!{}}
+            }
 
+            {
+                // new recipes list their pending items themselves, see NewRecipeComponent
+                if RecipeMode::New == (*mode_state).clone() {html!{}} else {html!{<>
             <IngredientList
             curr_focus={{
                 let ingredient_to_edit = ingredient_to_edit.clone();
@@ -169,6 +206,8 @@ pub fn recipe_component(props: &RecipeProps) -> Html {
             mode={(*mode_state).clone()}
             item_list={steps}
             />
+                </>}}
+            }
         </div>
 
         {
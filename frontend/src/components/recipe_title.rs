@@ -1,14 +1,22 @@
+use db::structs::Tag;
 use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::views::Route;
 
 #[derive(Properties, PartialEq)]
 pub struct TitleProps {
     pub title: String,
     pub owner: String,
+    #[prop_or_default]
+    pub tags: Vec<Tag>,
 }
 
 #[function_component(RecipeTitle)]
 /// A recipe title consists of the recipe name + the creator
-pub fn recipe_title(TitleProps { title, owner }: &TitleProps) -> Html {
+///
+/// tags are shown under it, each one links to the recipes sharing it
+pub fn recipe_title(TitleProps { title, owner, tags }: &TitleProps) -> Html {
     html! {
             <div class="recipe-title">
             <h1>{title}</h1>
@@ -20,6 +28,17 @@ pub fn recipe_title(TitleProps { title, owner }: &TitleProps) -> Html {
             }
 
             }</h6>
+            <ul class="tag-chips">
+            {
+                tags.iter().map(|tag| html! {
+                    <li key={tag.id} class="tag-chip">
+                        <Link<Route> to={Route::TagRecipes { tag: tag.tag_name.clone() }}>
+                            {&tag.tag_name}
+                        </Link<Route>>
+                    </li>
+                }).collect::<Html>()
+            }
+            </ul>
 
         </div>
     }
@@ -0,0 +1,84 @@
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct ReorderProps {
+    pub title: AttrValue,
+    /// `(id, label)` of each item, in the current order
+    pub items: Vec<(i32, String)>,
+    /// emits every id in the new order after an item is dropped
+    pub onreorder: Callback<Vec<i32>>,
+}
+
+/// Moves the item at `from` so it ends up at `to`
+fn move_item(ids: &[i32], from: usize, to: usize) -> Vec<i32> {
+    let mut ids = ids.to_vec();
+    if from < ids.len() && to < ids.len() {
+        let id = ids.remove(from);
+        ids.insert(to, id);
+    }
+    ids
+}
+
+#[function_component(ReorderList)]
+/// Drag-to-reorder list of steps or ingredients
+pub fn reorder_list(
+    ReorderProps {
+        title,
+        items,
+        onreorder,
+    }: &ReorderProps,
+) -> Html {
+    // index of the item being dragged
+    let dragged = use_state(|| None::<usize>);
+    let ids: Vec<i32> = items.iter().map(|(id, _)| *id).collect();
+
+    let list = items
+        .iter()
+        .enumerate()
+        .map(|(idx, (id, label))| {
+            let ondragstart = {
+                let dragged = dragged.clone();
+                Callback::from(move |_: DragEvent| dragged.set(Some(idx)))
+            };
+            // dropping is only allowed if dragover is cancelled
+            let ondragover = Callback::from(|e: DragEvent| e.prevent_default());
+            let ondrop = {
+                let dragged = dragged.clone();
+                let onreorder = onreorder.clone();
+                let ids = ids.clone();
+                Callback::from(move |e: DragEvent| {
+                    e.prevent_default();
+                    if let Some(from) = *dragged {
+                        if from != idx {
+                            onreorder.emit(move_item(&ids, from, idx));
+                        }
+                    }
+                    dragged.set(None);
+                })
+            };
+            let ondragend = {
+                let dragged = dragged.clone();
+                Callback::from(move |_: DragEvent| dragged.set(None))
+            };
+            html! {
+                <li
+                key={*id}
+                draggable="true"
+                class={classes!("reorder-item", (*dragged == Some(idx)).then_some("dragging"))}
+                {ondragstart} {ondragover} {ondrop} {ondragend}
+                >
+                    {label}
+                </li>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="reorder-list">
+            <h3>{title}</h3>
+            <ol>
+                {list}
+            </ol>
+        </div>
+    }
+}
@@ -0,0 +1,37 @@
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct ServingsProps {
+    pub servings: i32,
+    /// emits the new number of servings, never lower than 1
+    pub onchange: Callback<i32>,
+}
+
+#[function_component(ServingsStepper)]
+/// "- 4 servings +" buttons, used to scale a recipe's ingredients
+pub fn servings_stepper(ServingsProps { servings, onchange }: &ServingsProps) -> Html {
+    let decrease = {
+        let onchange = onchange.clone();
+        let servings = *servings;
+        Callback::from(move |_| onchange.emit((servings - 1).max(1)))
+    };
+    let increase = {
+        let onchange = onchange.clone();
+        let servings = *servings;
+        Callback::from(move |_| onchange.emit(servings + 1))
+    };
+
+    html! {
+        <div class="servings-stepper">
+            <button onclick={decrease} disabled={*servings <= 1} aria-label="Fewer servings">{"-"}</button>
+            <span>{
+                if *servings == 1 {
+                    String::from("1 serving")
+                } else {
+                    format!("{} servings", servings)
+                }
+            }</span>
+            <button onclick={increase} aria-label="More servings">{"+"}</button>
+        </div>
+    }
+}
@@ -96,6 +96,14 @@ pub fn step_component(props: &ItemProps<Step>) -> Html {
             }}>{"Edit"}</button>
             <button onclick={handle_delete}>{"Delete"}</button>
         </>
+    }} else if edit_mode == RecipeMode::New && *focus_state {
+    // not saved yet, it's only dropped from the parent's list
+    html!{
+        <button onclick={{
+            let step = item.clone();
+            let curr_focus = curr_focus.clone();
+            Callback::from(move |_| curr_focus.emit((RecipeMode::Delete,step.clone())))
+        }}>{"Remove"}</button>
     }} else {html!{<></>}}
         }
         <h4>{item.clone().step_name}</h4>
@@ -0,0 +1,85 @@
+use db::structs::Tag;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::views::Route;
+
+#[derive(Properties, PartialEq)]
+pub struct TagEditorProps {
+    /// current tags of the recipe
+    pub tags: Vec<Tag>,
+    /// emits every tag name the recipe should end up with after a chip is added or removed
+    pub onchange: Callback<Vec<String>>,
+}
+
+#[function_component(TagEditor)]
+/// Recipe tags as removable chips, with an input adding new ones
+pub fn tag_editor(TagEditorProps { tags, onchange }: &TagEditorProps) -> Html {
+    let new_tag_ref = use_node_ref();
+    let names: Vec<String> = tags.iter().map(|tag| tag.tag_name.clone()).collect();
+
+    let handle_add = {
+        let new_tag_ref = new_tag_ref.clone();
+        let onchange = onchange.clone();
+        let names = names.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let input = new_tag_ref.cast::<HtmlInputElement>().unwrap();
+            let new_tag = input.value().trim().to_lowercase();
+            if !new_tag.is_empty() && !names.contains(&new_tag) {
+                let mut names = names.clone();
+                names.push(new_tag);
+                onchange.emit(names);
+            }
+            input.set_value("");
+        })
+    };
+
+    let chips = tags
+        .iter()
+        .map(|tag| {
+            let onremove = {
+                let onchange = onchange.clone();
+                let names = names.clone();
+                let removed = tag.tag_name.clone();
+                Callback::from(move |_: MouseEvent| {
+                    onchange.emit(
+                        names
+                            .iter()
+                            .filter(|name| **name != removed)
+                            .cloned()
+                            .collect(),
+                    )
+                })
+            };
+            html! {
+                <li key={tag.id} class="tag-chip">
+                    <Link<Route> to={Route::TagRecipes { tag: tag.tag_name.clone() }}>
+                        {&tag.tag_name}
+                    </Link<Route>>
+                    <button type="button" title="Remove tag" onclick={onremove}>{"×"}</button>
+                </li>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div class="tag-editor">
+            <h3>{"Tags"}</h3>
+            <ul class="tag-chips">
+                {chips}
+            </ul>
+            <form onsubmit={handle_add}>
+                <input
+                ref={new_tag_ref}
+                type="text"
+                name="new-tag"
+                maxlength="50"
+                placeholder="Add a tag (breakfast, vegan...)"
+                />
+                <button>{"Add tag"}</button>
+            </form>
+        </div>
+    }
+}
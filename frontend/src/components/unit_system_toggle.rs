@@ -0,0 +1,60 @@
+use db::structs::UnitSystem;
+use gloo_storage::{LocalStorage, Storage};
+use yew::prelude::*;
+
+/// localStorage key of the user's preferred `UnitSystem`
+const UNIT_SYSTEM_KEY: &str = "unit_system";
+
+/// Preferred measuring system, None shows ingredients as they were written
+pub fn stored_unit_system() -> Option<UnitSystem> {
+    LocalStorage::get(UNIT_SYSTEM_KEY).ok()
+}
+
+fn store_unit_system(system: Option<UnitSystem>) {
+    match system {
+        Some(system) => {
+            if let Err(err) = LocalStorage::set(UNIT_SYSTEM_KEY, system) {
+                log::error!("could not save unit system: {}", err)
+            }
+        }
+        None => LocalStorage::delete(UNIT_SYSTEM_KEY),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct UnitSystemProps {
+    pub system: Option<UnitSystem>,
+    pub onchange: Callback<Option<UnitSystem>>,
+}
+
+#[function_component(UnitSystemToggle)]
+/// "As written / Metric / Imperial" buttons, the choice is remembered in localStorage
+pub fn unit_system_toggle(UnitSystemProps { system, onchange }: &UnitSystemProps) -> Html {
+    let options = [
+        (None, "As written"),
+        (Some(UnitSystem::Metric), "Metric"),
+        (Some(UnitSystem::Imperial), "Imperial"),
+    ]
+    .into_iter()
+    .map(|(option, label)| {
+        let onclick = {
+            let onchange = onchange.clone();
+            Callback::from(move |_| {
+                store_unit_system(option);
+                onchange.emit(option)
+            })
+        };
+        html! {
+            <button {onclick} class={classes!((*system == option).then_some("selected"))}>
+                {label}
+            </button>
+        }
+    })
+    .collect::<Html>();
+
+    html! {
+        <div class="unit-system-toggle">
+            {options}
+        </div>
+    }
+}
@@ -1,25 +1,55 @@
+use db::structs::{Unit, UnitKind};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct UnitProps {
     pub id: String,
 }
+
+/// `<optgroup>` label for each kind of unit
+fn kind_label(kind: UnitKind) -> &'static str {
+    match kind {
+        UnitKind::Mass => "Mass",
+        UnitKind::Volume => "Volume",
+        UnitKind::Count => "Count",
+        UnitKind::ToTaste => "No quantity",
+    }
+}
+
 #[function_component(MeasuringUnits)]
+/// `<select>` with every `Unit` the backend accepts, grouped by kind
 pub fn measuring_units(props: &UnitProps) -> Html {
+    let groups = [
+        UnitKind::Mass,
+        UnitKind::Volume,
+        UnitKind::Count,
+        UnitKind::ToTaste,
+    ]
+    .into_iter()
+    .map(|kind| {
+        html! {
+            <optgroup label={kind_label(kind)}>
+            {
+                Unit::ALL
+                    .iter()
+                    .filter(|unit| unit.kind() == kind)
+                    .map(|unit| html! {
+                        <option value={unit.as_str()}>{unit.label()}</option>
+                    })
+                    .collect::<Html>()
+            }
+            </optgroup>
+        }
+    })
+    .collect::<Html>();
+
     html! {
 
     <select required={true}  name="measuring_units" id="measuring_units"
     form={props.id.clone()}
     >
         <option selected={true} disabled={true} value={""}>{"--- Choose a value --- "}</option>
-        <option value="teaspoon">{"Teaspoon (tsp)"}</option>
-        <option value="tablespoon">{"Tablespoon (tbsp)"}</option>
-        <option value="cup">{"Cup"}</option>
-        <option value="ounce">{"Ounce (Oz)"}</option>
-        <option value="gram">{"Gram (g)"}</option>
-        <option value="kilogram">{"Kilogram (Kg)"}</option>
-        <option value="liter">{"Liter (L)"}</option>
-        <option value="milliliter">{"Milliliter (mL)"}</option>
+        {groups}
     </select>
     }
 }
@@ -1,8 +1,8 @@
 pub mod recipe_functions;
 pub mod user_functions;
 
-use db::structs::RecipeTrait;
-use gloo_net::Error as GlooError;
+use db::structs::{ApiErrorResponse, ErrorCode, RecipeTrait};
+use gloo_net::{http::Request, Error as GlooError};
 use serde::Deserialize;
 use serde_json::Value;
 use std::fmt::{Debug, Display};
@@ -14,7 +14,7 @@ use std::fmt::{Debug, Display};
 ///
 /// ApiMessage: Generic message from the backend
 ///
-/// ApiError: Error message from the backend
+/// ApiError: Error from the backend, match on its `code`
 pub enum ApiResponse<R, M>
 where
     R: for<'a> Deserialize<'a> + RecipeTrait,
@@ -22,19 +22,16 @@ where
 {
     OkPart(R),
     ApiMessage(M),
-    ApiError(M),
+    ApiError(ApiErrorResponse),
 }
 pub async fn parse_api_response<R, M>(res: Value) -> Result<ApiResponse<R, M>, GlooError>
 where
     R: for<'a> Deserialize<'a> + RecipeTrait,
     M: for<'a> Deserialize<'a> + Display + Debug + PartialEq + Clone,
 {
-    if let Some(err) = res.get("error") {
-        // err key found in response
-
-        Ok(ApiResponse::ApiError(
-            serde_json::from_value::<M>(err.clone()).map_err(|e| GlooError::SerdeError(e))?,
-        ))
+    if let Some(err) = api_error(&res) {
+        // error body found in response
+        Ok(ApiResponse::ApiError(err))
     } else if let Some(msg) = res.get("msg") {
         // handles generic messages from the backend
         Ok(ApiResponse::ApiMessage(
@@ -51,3 +48,32 @@ where
         ))
     }
 }
+
+/// `res` as an error body, None if it isn't one
+pub fn api_error(res: &Value) -> Option<ApiErrorResponse> {
+    if res.get("code").is_none() || res.get("message").is_none() {
+        return None;
+    }
+    serde_json::from_value(res.clone()).ok()
+}
+
+/// Sends the request built by `request`, refreshing the session once if the
+/// backend says the access token expired
+///
+/// `request` is called again for the retry, a request body can only be sent once
+pub async fn send_with_refresh<F>(request: F) -> Result<Value, GlooError>
+where
+    F: Fn() -> Result<Request, GlooError>,
+{
+    let res: Value = request()?.send().await?.json().await?;
+    if api_error(&res).map(|err| err.code) != Some(ErrorCode::TokenExpired) {
+        return Ok(res);
+    }
+    // refresh_token cookie is sent by the browser, new cookies come back the same way
+    let refresh = Request::post("/api/refresh").send().await?;
+    if !refresh.ok() {
+        // refresh token is gone or expired too, the caller shows the original error
+        return Ok(res);
+    }
+    request()?.send().await?.json().await
+}
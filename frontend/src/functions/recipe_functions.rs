@@ -1,18 +1,32 @@
-use db::structs::{FullRecipe, Ingredient, NewIngredient, NewRecipe, NewStep, Recipe, Step};
+use db::structs::{
+    FullRecipe, Ingredient, NewFullRecipe, NewIngredient, NewRecipe, NewStep, Page, Recipe,
+    RecipeTags, ReorderItems, Step, Tag, TagSummary, UrlPageQuery, UrlRecipeQuery,
+};
 use gloo_net::{http::Request, Error as GlooError};
 use serde_json::Value;
 
-use super::{parse_api_response, ApiResponse};
+use super::{parse_api_response, send_with_refresh, ApiResponse};
 
-/// View details about recipe
+/// View details about recipe, found by id or by owner and slug
 ///
 /// # Returns
 ///
 /// 1. ok FullRecipe
 /// 1. error message from backend
-// pub async fn fetch_recipe(recipe_id: i32) -> Result<ApiResponse<FullRecipe, String>, GlooError> {
-pub async fn fetch_recipe(recipe_id: &i32) -> Result<ApiResponse<FullRecipe, String>, GlooError> {
-    let req = Request::get(&format!("/api/get/recipe/?id={recipe_id}"))
+pub async fn fetch_recipe(
+    query: &UrlRecipeQuery,
+) -> Result<ApiResponse<FullRecipe, String>, GlooError> {
+    let params = [
+        ("id", query.id.map(|id| id.to_string())),
+        ("user", query.user.clone()),
+        ("slug", query.slug.clone()),
+    ];
+    let req = Request::get("/api/get/recipe/")
+        .query(
+            params
+                .into_iter()
+                .filter_map(|(key, value)| Some((key, value?))),
+        )
         .send()
         .await?;
 
@@ -20,107 +34,161 @@ pub async fn fetch_recipe(recipe_id: &i32) -> Result<ApiResponse<FullRecipe, Str
     parse_api_response(res).await
 }
 
-pub async fn fuzzy_list_recipe(name: &String) -> Result<Vec<Recipe>, GlooError> {
-    // updating endpoint addresses
-    let req = Request::get(&format!("/api/get/recipes/?name={}", name))
+/// Full-text search, recipes are returned ranked by relevance
+///
+/// only recipes tagged `tag` are returned if it's sent, an empty `query` lists every one of them
+///
+/// `page` selects which slice of the results will be fetched
+pub async fn search_recipes(
+    query: &String,
+    tag: Option<&String>,
+    page: &UrlPageQuery,
+) -> Result<Page<Recipe>, GlooError> {
+    let mut params = vec![
+        ("query", query.clone()),
+        ("limit", page.limit().to_string()),
+        ("offset", page.offset().to_string()),
+    ];
+    if let Some(tag) = tag {
+        params.push(("tag", tag.clone()));
+    }
+    let req = Request::get("/api/search/recipes/")
+        .query(params)
         .send()
         .await?;
     req.json().await
 }
 
-pub async fn create_recipe(recipe: &NewRecipe) -> Result<ApiResponse<Recipe, String>, GlooError> {
-    let req = Request::post("/api/create/recipe")
-        .json(&recipe)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+/// Tags used by at least one recipe, sorted by name
+pub async fn fetch_tags() -> Result<Vec<TagSummary>, GlooError> {
+    Request::get("/api/get/tags").send().await?.json().await
+}
+
+/// Replaces every tag of `recipe_tags.recipe_id`
+///
+/// # Returns
+///
+/// 1. ok the new tags of the recipe
+/// 1. error message from backend
+pub async fn update_tags(
+    recipe_tags: &RecipeTags,
+) -> Result<ApiResponse<Vec<Tag>, String>, GlooError> {
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/update/tags").json(recipe_tags)).await?;
+    parse_api_response(res).await
+}
+
+/// Creates recipe, ingredients and steps in a single request
+///
+/// # Returns
+///
+/// 1. ok FullRecipe as saved by the backend
+/// 1. error message from backend, nothing is saved in this case
+pub async fn create_full_recipe(
+    full_recipe: &NewFullRecipe,
+) -> Result<ApiResponse<FullRecipe, String>, GlooError> {
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/create/full_recipe").json(full_recipe)).await?;
+    parse_api_response(res).await
+}
+
+/// Sends a pasted HTML page or JSON-LD recipe to be imported
+///
+/// # Returns
+///
+/// 1. ok FullRecipe with everything that was created
+/// 1. error message from backend
+pub async fn import_recipe(source: &String) -> Result<ApiResponse<FullRecipe, String>, GlooError> {
+    let res: Value = send_with_refresh(|| {
+        Request::post("/api/import/recipe")
+            .header("content-type", "text/plain")
+            .body(source.clone())
+    })
+    .await?;
     parse_api_response(res).await
 }
 
 pub async fn check_edit_permission(
     recipe_id: &i32,
 ) -> Result<ApiResponse<NewRecipe, String>, GlooError> {
-    let req = Request::get(&format!("/api/get/permission/?id={recipe_id}"))
-        .send()
-        .await?;
-    let res = req.json().await?;
+    let res: Value =
+        send_with_refresh(|| Request::get(&format!("/api/get/permission/?id={recipe_id}")).build())
+            .await?;
     parse_api_response(res).await
 }
 
 pub async fn delete_recipe(recipe_id: i32) -> Result<ApiResponse<FullRecipe, String>, GlooError> {
-    let req = Request::get(&format!("/api/delete/recipe/?id={}", recipe_id))
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value = send_with_refresh(|| {
+        Request::get(&format!("/api/delete/recipe/?id={}", recipe_id)).build()
+    })
+    .await?;
     parse_api_response(res).await
 }
 
 pub async fn delete_step(step: &Step) -> Result<ApiResponse<Step, String>, GlooError> {
-    let req = Request::post("/api/delete/step")
-        // sending step to API
-        .json(step)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value = send_with_refresh(|| {
+        Request::post("/api/delete/step")
+            // sending step to API
+            .json(step)
+    })
+    .await?;
     parse_api_response(res).await
 }
 
 pub async fn delete_ingredient(
     ingredient: &Ingredient,
 ) -> Result<ApiResponse<Ingredient, String>, GlooError> {
-    let req = Request::post("/api/delete/ingredient")
-        .json(ingredient)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/delete/ingredient").json(ingredient)).await?;
     parse_api_response(res).await
 }
 
 pub async fn create_ingredient(
     ingredient: Vec<NewIngredient>,
 ) -> Result<ApiResponse<Vec<Ingredient>, String>, GlooError> {
-    let req = Request::post("/api/create/ingredient")
-        .json(&ingredient)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/create/ingredient").json(&ingredient)).await?;
     parse_api_response(res).await
 }
 
 pub async fn create_step(step: Vec<&NewStep>) -> Result<ApiResponse<Vec<Step>, String>, GlooError> {
-    let req = Request::post("/api/create/step")
-        .json(&step)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value = send_with_refresh(|| Request::post("/api/create/step").json(&step)).await?;
     parse_api_response(res).await
 }
 
 pub async fn update_recipe(recipe: &Recipe) -> Result<ApiResponse<Recipe, String>, GlooError> {
-    let req = Request::post("/api/update/recipe")
-        .json(&recipe)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/update/recipe").json(&recipe)).await?;
     parse_api_response(res).await
 }
 
 pub async fn update_steps(step: &Step) -> Result<ApiResponse<Step, String>, GlooError> {
-    let req = Request::post("/api/update/step")
-        .json(&step)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value = send_with_refresh(|| Request::post("/api/update/step").json(&step)).await?;
     parse_api_response(res).await
 }
 
 pub async fn update_ingredient(
     ingredient: &Ingredient,
 ) -> Result<ApiResponse<Ingredient, String>, GlooError> {
-    let req = Request::post("/api/update/ingredient")
-        .json(&ingredient)?
-        .send()
-        .await?;
-    let res: Value = req.json().await?;
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/update/ingredient").json(&ingredient)).await?;
+    parse_api_response(res).await
+}
+
+/// Saves a new step order, `reorder.ids` must list every step of the recipe
+pub async fn reorder_steps(
+    reorder: ReorderItems,
+) -> Result<ApiResponse<Vec<Step>, String>, GlooError> {
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/reorder/steps").json(&reorder)).await?;
+    parse_api_response(res).await
+}
+
+/// Saves a new ingredient order, `reorder.ids` must list every ingredient of the recipe
+pub async fn reorder_ingredients(
+    reorder: ReorderItems,
+) -> Result<ApiResponse<Vec<Ingredient>, String>, GlooError> {
+    let res: Value =
+        send_with_refresh(|| Request::post("/api/reorder/ingredients").json(&reorder)).await?;
     parse_api_response(res).await
 }
@@ -1,4 +1,7 @@
-use db::structs::{NewRecipe, NewUser, User};
+use db::{
+    password::PasswordPolicy,
+    structs::{NewRecipe, NewUser, User},
+};
 use gloo_net::{http::Request, Error as GlooError};
 use serde_json::Value;
 
@@ -24,6 +27,23 @@ pub async fn create_user(user: NewUser) -> Result<ApiResponse<NewRecipe, String>
     parse_api_response(res).await
 }
 
+/// Ends the current session, the backend expires the `jwt` and `refresh_token` cookies
+pub async fn logout_user() -> Result<ApiResponse<NewRecipe, String>, GlooError> {
+    let req = Request::post("/api/logout").send().await?;
+
+    let res: Value = req.json().await?;
+    parse_api_response(res).await
+}
+
+/// Password rules enforced by the backend, used to check passwords as they're typed
+pub async fn fetch_password_policy() -> Result<PasswordPolicy, GlooError> {
+    Request::get("/api/get/password_policy")
+        .send()
+        .await?
+        .json()
+        .await
+}
+
 pub async fn is_admin() -> Result<ApiResponse<NewUser, String>, GlooError> {
     todo!()
 }
@@ -5,8 +5,10 @@ pub mod new_recipe;
 pub mod recipe;
 pub mod recipe_list;
 pub mod register;
+pub mod tag_list;
 pub mod user_page;
 
+use db::structs::UrlRecipeQuery;
 use recipe::RecipePage;
 use user_page::UserPage;
 use yew::prelude::*;
@@ -20,6 +22,7 @@ use self::{
     login::UserLogin,
     recipe_list::RecipeList,
     register::UserRegister,
+    tag_list::TagList,
 };
 
 /// # Routes enum
@@ -29,12 +32,19 @@ use self::{
 pub enum Route {
     #[at("/")]
     Home,
+    /// older links, replaced by the slug route once the recipe is loaded
     #[at("/recipe/:id")]
-    Recipe { id: i32 },
+    RecipeById { id: i32 },
+    #[at("/recipe/:user/:slug")]
+    Recipe { user: String, slug: String },
     #[at("/recipe/list/:name")]
     RecipeList { name: String },
     #[at("/recipe/add")]
     NewRecipe,
+    #[at("/tags")]
+    Tags,
+    #[at("/tags/:tag")]
+    TagRecipes { tag: String },
     #[at("/user-auth")]
     UserPage,
     #[not_found]
@@ -63,9 +73,22 @@ pub fn switch(route: Route) -> Html {
             <RecipeList recipe_name={name}/>
             }
         }
-        Route::Recipe { id } => {
+        Route::RecipeById { id } => {
             html! {
-            <RecipePage mode={RecipeMode::View} recipe_id={id}/>
+            <RecipePage mode={RecipeMode::View} query={UrlRecipeQuery::by_id(id)}/>
+            }
+        }
+        Route::Recipe { user, slug } => {
+            html! {
+            <RecipePage mode={RecipeMode::View} query={UrlRecipeQuery::by_slug(user, slug)}/>
+            }
+        }
+        Route::Tags => {
+            html! {<TagList />}
+        }
+        Route::TagRecipes { tag } => {
+            html! {
+            <RecipeList tag={Some(tag)}/>
             }
         }
         Route::UserPage => {
@@ -76,7 +99,7 @@ pub fn switch(route: Route) -> Html {
 
         Route::NewRecipe => {
             html! {
-                <RecipePage recipe_id={-1} mode={RecipeMode::New}/>
+                <RecipePage mode={RecipeMode::New}/>
             }
         }
     }
@@ -1,6 +1,6 @@
-use db::structs::{FullRecipe, Ingredient, NewRecipe, Step};
+use db::structs::{FullRecipe, Ingredient, NewFullRecipe, Step};
 use log::error;
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
 
 use yew::{platform::spawn_local, prelude::*};
 use yew_router::prelude::*;
@@ -10,9 +10,11 @@ use crate::{
         input_component::{Input, InputType},
         new_ingredient::NewIngredientComponent,
         new_step::NewStepComponent,
+        recipe_component::{IngredientList, StepList},
+        RecipeMode,
     },
     functions::{
-        recipe_functions::{create_recipe, delete_recipe},
+        recipe_functions::{create_full_recipe, import_recipe},
         ApiResponse,
     },
     views::Route,
@@ -29,8 +31,16 @@ pub struct NewRecipeProps {
     pub new_recipe_cb: Callback<FullRecipe>,
 }
 
+/// Id for an item that isn't saved yet, below every id in `ids` so removals can't cause duplicates
+fn unsaved_id(ids: impl Iterator<Item = i32>) -> i32 {
+    ids.min().unwrap_or(0).min(0) - 1
+}
+
 #[function_component(NewRecipeComponent)]
 /// Handles recipe creation
+///
+/// Ingredients and steps are kept locally until "Save recipe" is clicked,
+/// then everything is sent to the backend in a single request
 pub fn new_recipe(props: &NewRecipeProps) -> Html {
     let NewRecipeProps {
         full_recipe,
@@ -56,12 +66,16 @@ pub fn new_recipe(props: &NewRecipeProps) -> Html {
     let ingredient_callback = {
         // making a copy of the current recipe_state
         let recipe_state = recipe_state.clone();
-        Callback::from(move |(_, ingredient)| {
+        Callback::from(move |(_, ingredient): (_, Ingredient)| {
             let recipe_state = recipe_state.clone();
             // local full_recipe (w/o UseStateHandle)
             let full_recipe = (*recipe_state).clone();
             let mut ingredients = full_recipe.ingredients;
-            ingredients.push(ingredient);
+            // unsaved items get unique negative ids, so they can be told apart in the list
+            ingredients.push(Ingredient {
+                id: unsaved_id(ingredients.iter().map(|ingredient| ingredient.id)),
+                ..ingredient
+            });
             // updating local recipe_state with the local ingredients
             recipe_state.set(FullRecipe {
                 ingredients,
@@ -73,13 +87,16 @@ pub fn new_recipe(props: &NewRecipeProps) -> Html {
     // <Step> Callback handler
     let step_callback = {
         let recipe_state = recipe_state.clone();
-        Callback::from(move |(_, step)| {
+        Callback::from(move |(_, step): (_, Step)| {
             let recipe_state = recipe_state.clone();
 
             // local full_recipe (w/o UseStateHandle)
             let full_recipe = (*recipe_state).clone();
             let mut steps = full_recipe.steps.clone();
-            steps.push(step);
+            steps.push(Step {
+                id: unsaved_id(steps.iter().map(|step| step.id)),
+                ..step
+            });
 
             // updating local recipe_state with the local steps
             recipe_state.set(FullRecipe {
@@ -89,19 +106,51 @@ pub fn new_recipe(props: &NewRecipeProps) -> Html {
         })
     };
 
+    // drops an unsaved ingredient from the list
+    let remove_ingredient = {
+        let recipe_state = recipe_state.clone();
+        Callback::from(move |(mode, ingredient): (RecipeMode, Ingredient)| {
+            if mode == RecipeMode::Delete {
+                let mut full_recipe = (*recipe_state).clone();
+                if let Ok(ingredients) = full_recipe.remove_ingredient(ingredient.id) {
+                    recipe_state.set(FullRecipe {
+                        ingredients,
+                        ..full_recipe
+                    });
+                }
+            }
+        })
+    };
+
+    // drops an unsaved step from the list
+    let remove_step = {
+        let recipe_state = recipe_state.clone();
+        Callback::from(move |(mode, step): (RecipeMode, Step)| {
+            if mode == RecipeMode::Delete {
+                let mut full_recipe = (*recipe_state).clone();
+                if let Ok(steps) = full_recipe.remove_step(step.id) {
+                    recipe_state.set(FullRecipe {
+                        steps,
+                        ..full_recipe
+                    });
+                }
+            }
+        })
+    };
+
     // on submit CallBack handler
-    // this handles creating a new Recipe
+    // this sends the recipe, its ingredients and steps to the backend at once
     let onsubmit = {
         let use_notification = use_notification.clone();
         // cloning here so these variables can be used inside this block*
         let recipe_name = recipe_name_ref.clone();
-
-        // let notifications_manager = use_notification::<Notification>();
         let recipe_state = recipe_state.clone();
+        let navigator = navigator.clone();
 
         Callback::from(move |e: SubmitEvent| {
             let use_notification = use_notification.clone();
             let recipe_state = recipe_state.clone();
+            let navigator = navigator.clone();
 
             // *necessary because of this 'move'
             e.prevent_default();
@@ -110,26 +159,90 @@ pub fn new_recipe(props: &NewRecipeProps) -> Html {
             let name = recipe_name
                 .cast::<HtmlInputElement>()
                 .expect("Invalid element!");
-            let new = NewRecipe {
-                recipe_name: name.value(),
-                ..Default::default()
-            };
+            let mut new = NewFullRecipe::from((*recipe_state).clone());
+            new.recipe.recipe_name = name.value();
 
             // making request to API backend
             spawn_local(async move {
-                let new = new.clone();
-                match create_recipe(&new).await {
+                match create_full_recipe(&new).await {
                     Ok(api_response) => match api_response {
                         ApiResponse::OkPart(ok_recipe) => {
-                            let mut f = FullRecipe::default();
-                            f.set_recipe(ok_recipe.clone());
-                            recipe_state.set(f);
                             use_notification.spawn(Notification::new(
                                 yew_notifications::NotificationType::Info,
                                 "Recipe created!",
-                                format!("recipe {} created! ", ok_recipe.recipe_name),
+                                format!("recipe {} created! ", ok_recipe.recipe.recipe_name),
+                                DEFAULT_NOTIFICATION_DURATION,
+                            ));
+                            navigator.push(&Route::Recipe {
+                                user: ok_recipe.recipe_owner_name.clone(),
+                                slug: ok_recipe.recipe.slug.clone(),
+                            });
+                            recipe_state.set(ok_recipe);
+                        }
+                        ApiResponse::ApiError(msg) => {
+                            error!("error: {}", msg);
+                            use_notification.spawn(Notification::new(
+                                yew_notifications::NotificationType::Error,
+                                "Error!",
+                                msg,
+                                DEFAULT_NOTIFICATION_DURATION,
+                            ));
+                        }
+                        ApiResponse::ApiMessage(msg) => {
+                            use_notification.spawn(Notification::new(
+                                yew_notifications::NotificationType::Info,
+                                "",
+                                msg,
+                                DEFAULT_NOTIFICATION_DURATION,
+                            ));
+                        }
+                    },
+                    Err(err) => {
+                        error!("{:?}", err);
+                        use_notification.spawn(Notification::new(
+                            yew_notifications::NotificationType::Error,
+                            "Error!",
+                            err.to_string(),
+                            DEFAULT_NOTIFICATION_DURATION,
+                        ));
+                    }
+                }
+            });
+        })
+    };
+
+    let import_source_ref = use_node_ref();
+
+    // on submit CallBack handler for importing a recipe from another site
+    let onimport = {
+        let use_notification = use_notification.clone();
+        let import_source = import_source_ref.clone();
+        let navigator = navigator.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            let use_notification = use_notification.clone();
+            let navigator = navigator.clone();
+            e.prevent_default();
+
+            let source = import_source
+                .cast::<HtmlTextAreaElement>()
+                .expect("Invalid element!");
+            let source_value = source.value();
+
+            spawn_local(async move {
+                match import_recipe(&source_value).await {
+                    Ok(api_response) => match api_response {
+                        ApiResponse::OkPart(imported) => {
+                            use_notification.spawn(Notification::new(
+                                yew_notifications::NotificationType::Info,
+                                "Recipe imported!",
+                                format!("recipe {} imported! ", imported.recipe.recipe_name),
                                 DEFAULT_NOTIFICATION_DURATION,
                             ));
+                            navigator.push(&Route::Recipe {
+                                user: imported.recipe_owner_name,
+                                slug: imported.recipe.slug,
+                            });
                         }
                         ApiResponse::ApiError(msg) => {
                             error!("error: {}", msg);
@@ -160,119 +273,70 @@ pub fn new_recipe(props: &NewRecipeProps) -> Html {
                     }
                 }
             });
-            name.set_value("")
         })
     };
 
     html! {
     <>
         <h1>{"New Recipe"}</h1>
-        {
-        // only show new recipe form if recipe_state is_none()
-        if recipe_state.recipe.id <0 {
-        html! {
-        <form {onsubmit} class="new-recipe">
+        <form {onsubmit} id="new-recipe" class="new-recipe">
             <Input
                 input_node_ref={recipe_name_ref.clone()}
                 input_placeholder="Recipe name"
                 input_name="recipe name"
                 is_required={true}
                 input_type={InputType::Text}/>
-            <button >{"Create recipe"}</button>
         </form>
-        }
-        } else {html! {}}
-        }
-
-        {
-            // only show <IngredientList> if there's a valid recipe at recipe_state
-            if  recipe_state.recipe.id >0{
-            // TODO! add Observation component
-
-            html! {
-                <>
-                <h1>{"New ingredient"}</h1>
-                <NewIngredientComponent
-                callback={ingredient_callback}
-                old_part={
-                    {
-                        Ingredient {
-                            recipe_id:(*recipe_state).clone().recipe.id,
-                            ..Default::default()
-                        }
-                    }
-                }
-                />
-                <h1>{"New Step"}</h1>
-                <NewStepComponent
-                callback={step_callback}
-                old_part={
-                    Step{
-                        recipe_id:(*recipe_state).clone().recipe.id,
-                        ..Default::default()
-                    }
-                }/>
-
-    <h6>
-            {format!("Note: when done, just click Home or go to")}
-            <Link<Route> to={Route::Recipe {id:recipe_state.recipe.id}}>{
-                format!("recipe {}",recipe_state.recipe.recipe_name)}
-                </Link<Route>>
-
-    // TODO! add confirmation for deleting recipe
-    <button
-    onclick={
-    Callback::from(
-    move |_|{
-        let recipe_state = recipe_state.clone();
-        let use_notification = use_notification.clone();
-        let navigator = navigator.clone();
-
-    spawn_local(async move {
-        match delete_recipe(recipe_state.recipe.id).await {
-            Ok(ok_fetch)=>{
-                match ok_fetch{
-                    ApiResponse::ApiError(err)=>{
-                        error!("API error: {:?}", err);
-                        use_notification.spawn(Notification::new(
-                            yew_notifications::NotificationType::Error,
-                            "Error!",
-                            err,
-                            DEFAULT_NOTIFICATION_DURATION,
-                            ));
-                        },
-                    ApiResponse::ApiMessage(msg) => {
-
-                        use_notification.spawn(Notification::new(
-                        yew_notifications::NotificationType::Info,
-                        "Sucess",
-                        msg,
-                        DEFAULT_NOTIFICATION_DURATION,
 
-                    ));
-                            navigator.push(&Route::Home);
+        // items added so far, only saved with the recipe
+        <IngredientList
+        mode={RecipeMode::New}
+        item_list={recipe_state.ingredients.clone()}
+        curr_focus={remove_ingredient}
+        />
+        <StepList
+        mode={RecipeMode::New}
+        item_list={recipe_state.steps.clone()}
+        curr_focus={remove_step}
+        />
 
-            },
-            _ => {} // this is a placeholder
-        }}
-                Err(err)=>{
-                    use_notification.spawn(Notification::new(
-                    yew_notifications::NotificationType::Error,
-                    "Error!",
-                    err.to_string(),
-                    DEFAULT_NOTIFICATION_DURATION,
-                ));
-                }
-            };
-        });
-    })}>{"cancel"}</button>
+        <h1>{"New ingredient"}</h1>
+        <NewIngredientComponent
+        callback={ingredient_callback}
+        old_part={
+            Ingredient {
+                recipe_id:recipe_state.recipe.id,
+                ..Default::default()
+            }
+        }
+        />
+        <h1>{"New Step"}</h1>
+        <NewStepComponent
+        callback={step_callback}
+        old_part={
+            Step{
+                recipe_id:recipe_state.recipe.id,
+                ..Default::default()
+            }
+        }/>
 
+        <h6>
+            // the button lives outside the form, so ingredient/step forms aren't nested in it
+            <button form="new-recipe">{"Save recipe"}</button>
+            <button onclick={
+                Callback::from(move |_| navigator.push(&Route::Home))
+            }>{"cancel"}</button>
         </h6>
-    </>
-    }
-    } else {html!()}
-    }
 
+        <form onsubmit={onimport} class="import-recipe">
+            <h2>{"...or import it"}</h2>
+            <textarea
+                ref={import_source_ref.clone()}
+                name="import source"
+                placeholder="Paste a recipe page (HTML) or its JSON-LD"
+                required={true}/>
+            <button >{"Import recipe"}</button>
+        </form>
     </>
     }
 }
@@ -1,17 +1,21 @@
-use db::structs::FullRecipe;
+use db::structs::{FullRecipe, UrlRecipeQuery};
 use log::error;
 use yew::{platform::spawn_local, prelude::*};
 use yew_notifications::{use_notification, Notification};
+use yew_router::prelude::*;
 
 use crate::{
     components::{recipe_component::RecipeComponent, RecipeMode},
     functions::{recipe_functions::fetch_recipe, ApiResponse},
+    views::Route,
     DEFAULT_NOTIFICATION_DURATION,
 };
 
 #[derive(Properties, PartialEq)]
 pub struct RecipeProps {
-    pub recipe_id: i32,
+    /// recipe to show, unused in `RecipeMode::New`
+    #[prop_or_default]
+    pub query: UrlRecipeQuery,
     #[prop_or(RecipeMode::View)]
     pub mode: RecipeMode,
 }
@@ -19,25 +23,35 @@ pub struct RecipeProps {
 #[function_component(RecipePage)]
 /// # Recipe page
 ///
-/// Handles Displaying single recipe when user accesses {url}/recipe/{id}
+/// Handles Displaying single recipe when user accesses {url}/recipe/{user}/{slug}
+///
+/// recipes opened with {url}/recipe/{id} get their slug URL once loaded
 pub fn recipe_page(props: &RecipeProps) -> Html {
-    let recipe_id = props.recipe_id;
+    let query = props.query.clone();
     let use_notification = use_notification::<Notification>();
+    let navigator = use_navigator().unwrap();
 
     // same as:
     // const [recipe,setRecipe] = useState(recipe)
     let recipe_state = use_state(|| FullRecipe::default());
     {
         let recipe_state = recipe_state.clone();
-        use_effect_with(props.mode.clone(), move |mode| {
+        use_effect_with((props.mode.clone(), query), move |(mode, query)| {
             if let RecipeMode::View = mode {
                 let recipe_state = recipe_state.clone();
+                let query = query.clone();
                 spawn_local(async move {
                     let use_notification = use_notification.clone();
 
-                    match fetch_recipe(&recipe_id).await {
+                    match fetch_recipe(&query).await {
                         Ok(ok_fetch) => match ok_fetch {
                             ApiResponse::OkPart(ok_recipe) => {
+                                if query.slug.is_none() {
+                                    navigator.replace(&Route::Recipe {
+                                        user: ok_recipe.recipe_owner_name.clone(),
+                                        slug: ok_recipe.recipe.slug.clone(),
+                                    });
+                                }
                                 recipe_state.set(ok_recipe);
                             }
                             ApiResponse::ApiError(err) => {
@@ -72,12 +86,13 @@ pub fn recipe_page(props: &RecipeProps) -> Html {
                 html! {
                     <>
                         <RecipeComponent mode={RecipeMode::View} full_recipe={(*recipe_state).clone()}/>
+                        <ExportMenu recipe_id={recipe_state.recipe.id}/>
                     </>
                 }
             } else {
                 html! {
                 <>
-                <h1>{"No recipe with this id!"}</h1>
+                <h1>{"Recipe not found!"}</h1>
 
                 </>
                 }
@@ -92,3 +107,38 @@ pub fn recipe_page(props: &RecipeProps) -> Html {
         }
     }
 }
+
+#[derive(Properties, PartialEq)]
+pub struct ExportMenuProps {
+    pub recipe_id: i32,
+}
+
+#[function_component(ExportMenu)]
+/// Links for downloading the recipe as a file
+///
+/// the backend sends these with `Content-Disposition: attachment`, so the browser saves them
+fn export_menu(props: &ExportMenuProps) -> Html {
+    let formats = [
+        ("jsonld", "JSON-LD"),
+        ("markdown", "Markdown"),
+        ("txt", "Plain text"),
+    ];
+    html! {
+        <details class="export-menu">
+            <summary>{"Export"}</summary>
+            <ul>
+            {
+                formats.iter().map(|(format, label)| html! {
+                    <li>
+                        <a
+                            href={format!("/api/export/recipe/?id={}&format={}", props.recipe_id, format)}
+                            download="">
+                            {*label}
+                        </a>
+                    </li>
+                }).collect::<Html>()
+            }
+            </ul>
+        </details>
+    }
+}
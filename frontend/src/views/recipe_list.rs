@@ -1,8 +1,9 @@
+use db::structs::{Page, Recipe, UrlPageQuery};
 use log::error;
 use yew::{platform::spawn_local, prelude::*};
 
 use crate::{
-    components::recipe_card_component::RecipeCard, functions::recipe_functions::fuzzy_list_recipe,
+    components::recipe_card_component::RecipeCard, functions::recipe_functions::search_recipes,
     DEFAULT_NOTIFICATION_DURATION,
 };
 
@@ -10,29 +11,48 @@ use yew_notifications::{use_notification, Notification};
 
 #[derive(Properties, PartialEq)]
 pub struct RecipeListProps {
+    #[prop_or_default]
     pub recipe_name: String,
+    /// only recipes with this tag are listed, used by the tag pages
+    #[prop_or_default]
+    pub tag: Option<String>,
 }
 
 /// # Recipe list view
 ///
 /// Iterates through provided recipe list and displays them.
 ///
-/// Results from Home search bar
+/// Results from Home search bar or recipes of a tag, fetched one page at a time ("load more")
 #[function_component(RecipeList)]
-pub fn recipe_list(RecipeListProps { recipe_name }: &RecipeListProps) -> Html {
-    let name = recipe_name.clone();
-    let recipe_state = use_state(|| vec![]);
+pub fn recipe_list(RecipeListProps { recipe_name, tag }: &RecipeListProps) -> Html {
+    let recipe_state = use_state(Page::<Recipe>::default);
 
     let use_notification = use_notification::<Notification>();
 
-    {
+    // fetches the page starting at `offset`
+    // offset 0 replaces the current list, anything else is appended to it
+    let load_page = {
         let recipe_state = recipe_state.clone();
-        use_effect_with(recipe_state.clone(), move |_| {
+        let name = recipe_name.clone();
+        let tag = tag.clone();
+        Callback::from(move |offset: i64| {
+            let recipe_state = recipe_state.clone();
+            let name = name.clone();
+            let tag = tag.clone();
+            let use_notification = use_notification.clone();
             spawn_local(async move {
-                let use_notification = use_notification.clone();
-                match fuzzy_list_recipe(&name).await {
-                    Ok(ok_recipes) => {
-                        recipe_state.set(ok_recipes);
+                let page = UrlPageQuery {
+                    offset: Some(offset),
+                    ..Default::default()
+                };
+                match search_recipes(&name, tag.as_ref(), &page).await {
+                    Ok(mut ok_page) => {
+                        if offset > 0 {
+                            let mut items = recipe_state.items.clone();
+                            items.append(&mut ok_page.items);
+                            ok_page.items = items;
+                        }
+                        recipe_state.set(ok_page);
                     }
                     Err(err) => {
                         error!("err {}", err.to_string());
@@ -46,9 +66,17 @@ pub fn recipe_list(RecipeListProps { recipe_name }: &RecipeListProps) -> Html {
                 }
             });
         })
+    };
+
+    {
+        let load_page = load_page.clone();
+        use_effect_with((recipe_name.clone(), tag.clone()), move |_| {
+            load_page.emit(0)
+        })
     }
 
     let list: Html = recipe_state
+        .items
         .iter()
         .map(|recipe| {
             let id = recipe.id;
@@ -63,7 +91,7 @@ pub fn recipe_list(RecipeListProps { recipe_name }: &RecipeListProps) -> Html {
     html! {
 
             {
-                if recipe_state.len() ==0 {
+                if recipe_state.items.is_empty() {
                     html! {
                         <h1>{"No recipes found!"}</h1>
                     }
@@ -71,13 +99,25 @@ pub fn recipe_list(RecipeListProps { recipe_name }: &RecipeListProps) -> Html {
                 else {
                     html! {
                     <>
-                        <h1>{format!("Found {} recipes",recipe_state.clone().len())}</h1>
+                        <h1>{
+                            match tag {
+                                Some(tag) => format!("{} recipes tagged \"{}\"",recipe_state.total,tag),
+                                None => format!("Found {} recipes",recipe_state.total),
+                            }
+                        }</h1>
 
                     <div class="recipe-card-container">
                         <ul class="recipes-list">
                         {list}
                         </ul>
                     </div>
+                    {
+                        if let Some(next_cursor) = recipe_state.next_cursor {
+                            html! {
+                                <button onclick={move |_| load_page.emit(next_cursor)}>{"Load more"}</button>
+                            }
+                        } else {html! {}}
+                    }
                     </>
 
                     }
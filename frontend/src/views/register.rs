@@ -1,4 +1,7 @@
-use db::structs::NewUser;
+use db::{
+    password::{PasswordError, PasswordPolicy},
+    structs::NewUser,
+};
 use log::error;
 use web_sys::HtmlInputElement;
 use yew::{platform::spawn_local, prelude::*};
@@ -6,7 +9,10 @@ use yew_notifications::{use_notification, Notification};
 
 use crate::{
     components::input_component::{Input, InputType},
-    functions::{user_functions::create_user, ApiResponse},
+    functions::{
+        user_functions::{create_user, fetch_password_policy},
+        ApiResponse,
+    },
     DEFAULT_NOTIFICATION_DURATION,
 };
 
@@ -15,6 +21,42 @@ pub fn user_register() -> Html {
     let use_notification = use_notification::<Notification>();
     let user_name_ref = use_node_ref();
     let user_pwd_ref = use_node_ref();
+    // defaults until the backend's rules are fetched
+    let policy = use_state(PasswordPolicy::default);
+    let pwd_errors = use_state(Vec::<PasswordError>::new);
+
+    {
+        let policy = policy.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                match fetch_password_policy().await {
+                    Ok(fetched) => policy.set(fetched),
+                    Err(err) => error!("error fetching password policy: {:?}", err),
+                }
+            })
+        });
+    }
+
+    // re-checks the password on every keystroke, in both fields (user name similarity)
+    let check_pwd = {
+        let user_name_ref = user_name_ref.clone();
+        let user_pwd_ref = user_pwd_ref.clone();
+        let policy = policy.clone();
+        let pwd_errors = pwd_errors.clone();
+        Callback::from(move |_: InputEvent| {
+            let value = |node: &NodeRef| {
+                node.cast::<HtmlInputElement>()
+                    .map(|input| input.value())
+                    .unwrap_or_default()
+            };
+            let pwd = value(&user_pwd_ref);
+            if pwd.is_empty() {
+                pwd_errors.set(vec![]);
+            } else {
+                pwd_errors.set(policy.check(&value(&user_name_ref), &pwd));
+            }
+        })
+    };
 
     let onsubmit = {
         let user_name_ref = user_name_ref.clone();
@@ -82,6 +124,7 @@ pub fn user_register() -> Html {
                 input_node_ref={user_name_ref.clone()}
                 input_placeholder={"username"}
                 is_required={true}
+                oninput={check_pwd.clone()}
 
                 input_name={"user name"}
                 input_type={InputType::Text}/>
@@ -90,9 +133,15 @@ pub fn user_register() -> Html {
                 input_placeholder={"password"}
                 input_name={"user password"}
                 is_required={true}
+                oninput={check_pwd}
 
                 input_type={InputType::Password}/>
-            <button>{"Register"}</button>
+            if !pwd_errors.is_empty() {
+                <ul class="password-errors">
+                    { for pwd_errors.iter().map(|err| html! { <li>{format!("Password {}", err)}</li> }) }
+                </ul>
+            }
+            <button disabled={!pwd_errors.is_empty()}>{"Register"}</button>
             </form>
         </div>
     }
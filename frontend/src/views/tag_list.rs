@@ -0,0 +1,58 @@
+use db::structs::TagSummary;
+use log::error;
+use yew::{platform::spawn_local, prelude::*};
+use yew_notifications::{use_notification, Notification};
+use yew_router::prelude::*;
+
+use crate::{functions::recipe_functions::fetch_tags, views::Route, DEFAULT_NOTIFICATION_DURATION};
+
+/// # Tag list view
+///
+/// Every tag used by a recipe, each one links to its recipes
+#[function_component(TagList)]
+pub fn tag_list() -> Html {
+    let tags_state = use_state(Vec::<TagSummary>::new);
+    let use_notification = use_notification::<Notification>();
+
+    {
+        let tags_state = tags_state.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                match fetch_tags().await {
+                    Ok(tags) => tags_state.set(tags),
+                    Err(err) => {
+                        error!("err {}", err.to_string());
+                        use_notification.spawn(Notification::new(
+                            yew_notifications::NotificationType::Error,
+                            "Error!",
+                            err.to_string(),
+                            DEFAULT_NOTIFICATION_DURATION,
+                        ));
+                    }
+                }
+            });
+        })
+    }
+
+    if tags_state.is_empty() {
+        return html! {
+            <h1>{"No tags yet!"}</h1>
+        };
+    }
+    html! {
+        <>
+            <h1>{"Tags"}</h1>
+            <ul class="tag-chips">
+            {
+                tags_state.iter().map(|tag| html! {
+                    <li key={tag.id} class="tag-chip">
+                        <Link<Route> to={Route::TagRecipes { tag: tag.tag_name.clone() }}>
+                            {format!("{} ({})", tag.tag_name, tag.recipe_count)}
+                        </Link<Route>>
+                    </li>
+                }).collect::<Html>()
+            }
+            </ul>
+        </>
+    }
+}